@@ -21,6 +21,7 @@ except ImportError:
 
 from .encryption import EncryptionManager
 from .exceptions import PasskeyError, AuthenticationError
+from .storage import default_base_dir
 
 
 class Fido2PasskeyManager:
@@ -32,7 +33,7 @@ class Fido2PasskeyManager:
         self.user_display_name = "VibeSafe User"
         
         # Storage paths
-        self.base_dir = Path.home() / '.vibesafe'
+        self.base_dir = default_base_dir()
         self.fido2_dir = self.base_dir / 'fido2'
         self.credential_file = self.fido2_dir / 'credential.json'
         self.wrapped_key_file = self.fido2_dir / 'wrapped_private_key.enc'
@@ -10,10 +10,18 @@ from .encryption import EncryptionManager
 from .exceptions import StorageError
 
 
+def default_base_dir():
+    """Store directory: $VIBESAFE_HOME if set, otherwise ~/.vibesafe"""
+    home = os.environ.get('VIBESAFE_HOME')
+    if home:
+        return Path(home).expanduser()
+    return Path.home() / '.vibesafe'
+
+
 class StorageManager:
     def __init__(self, base_dir=None):
         if base_dir is None:
-            base_dir = Path.home() / '.vibesafe'
+            base_dir = default_base_dir()
         self.base_dir = Path(base_dir)
         self.priv_key_file = self.base_dir / 'private.pem'
         self.pub_key_file = self.base_dir / 'public.pem'
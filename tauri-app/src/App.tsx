@@ -9,12 +9,69 @@ interface SecretInfo {
   created_at?: string;
 }
 
+type PasskeyDetails =
+  | { status: "disabled" }
+  | { status: "passkey_details_unavailable"; reported_type: string | null }
+  | {
+      status: "known";
+      passkey_type: "touch_id" | "security_key" | "pin";
+      enrolled_at: string | null;
+      device_name: string | null;
+    };
+
 interface VibeSafeStatus {
   initialized: boolean;
   key_exists: boolean;
   passkey_enabled: boolean;
   secrets_count: number;
   claude_integration: boolean;
+  passkey?: PasskeyDetails;
+  sync_provider?: "icloud_drive" | "dropbox" | "onedrive" | "google_drive" | null;
+  sync_warning?: string | null;
+  passphrase_protected?: boolean;
+  state?: "CliMissing" | "CliIncompatible" | "Uninitialized" | "Locked" | "Ready";
+}
+
+// Errors from commands: `{ code, message, details, error }`, `error` repeating
+// `message` for the old string-only shape.
+interface CommandError {
+  code: string;
+  message: string;
+}
+
+// Codes from the name checks, shown next to the name field.
+const NAME_ERROR_CODES = ["EMPTY_NAME", "NAME_TOO_LONG", "INVALID_NAME_CHARACTERS", "RESERVED_NAME"];
+
+function commandError(error: unknown): CommandError {
+  if (error && typeof error === "object" && "code" in error) {
+    const { code, message, error: text } = error as { code: string; message?: string; error?: string };
+    return { code, message: message ?? text ?? code };
+  }
+  return { code: "UNKNOWN", message: String(error) };
+}
+
+const PASSKEY_TYPE_LABELS = {
+  touch_id: "Touch ID",
+  security_key: "Security Key",
+  pin: "PIN",
+};
+
+function passkeyLabel(passkey?: PasskeyDetails): string {
+  if (passkey?.status === "known") {
+    return PASSKEY_TYPE_LABELS[passkey.passkey_type];
+  }
+  return "Passkey";
+}
+
+function passkeyDetail(passkey?: PasskeyDetails): string | null {
+  if (passkey?.status === "passkey_details_unavailable") {
+    return "Type not reported by this CLI";
+  }
+  if (passkey?.status !== "known") {
+    return null;
+  }
+  const parts = [passkey.device_name, passkey.enrolled_at && `enrolled ${passkey.enrolled_at}`];
+  return parts.filter(Boolean).join(", ") || null;
 }
 
 interface ButtonProps extends React.ButtonHTMLAttributes<HTMLButtonElement> {
@@ -82,8 +139,11 @@ const CardContent: React.FC<CardProps> = ({ children, className }) => (
 function App() {
   const [status, setStatus] = useState<VibeSafeStatus | null>(null);
   const [secrets, setSecrets] = useState<SecretInfo[]>([]);
+  const [readonlyNames, setReadonlyNames] = useState<string[]>([]);
   const [loading, setLoading] = useState(true);
   const [searchTerm, setSearchTerm] = useState("");
+  const [passphrase, setPassphrase] = useState("");
+  const [actionError, setActionError] = useState<CommandError | null>(null);
   const [showAddDialog, setShowAddDialog] = useState(false);
   const [newSecretName, setNewSecretName] = useState("");
   const [newSecretValue, setNewSecretValue] = useState("");
@@ -103,17 +163,25 @@ function App() {
     };
   }, []);
 
+  const reportError = (what: string, error: unknown) => {
+    console.error(`${what}:`, error);
+    setActionError(commandError(error));
+  };
+
   const loadData = async () => {
     setLoading(true);
+    setActionError(null);
     try {
-      const [statusResult, secretsResult] = await Promise.all([
+      const [statusResult, secretsResult, readonlyResult] = await Promise.all([
         invoke<VibeSafeStatus>("vibesafe_status"),
         invoke<SecretInfo[]>("vibesafe_list"),
+        invoke<string[]>("list_readonly_secrets"),
       ]);
       setStatus(statusResult);
       setSecrets(secretsResult);
+      setReadonlyNames(readonlyResult);
     } catch (error) {
-      console.error("Failed to load data:", error);
+      reportError("Failed to load data", error);
     } finally {
       setLoading(false);
     }
@@ -183,19 +251,27 @@ function App() {
       setShowAddDialog(false);
       await loadData();
     } catch (error) {
-      console.error("Failed to add secret:", error);
-      // You could set an error state here to show user feedback
+      const { code, message } = commandError(error);
+      if (NAME_ERROR_CODES.includes(code)) {
+        setNameValidationError(`${message} (${code})`);
+      } else {
+        reportError("Failed to add secret", error);
+      }
     }
   };
 
   const handleDeleteSecret = async (name: string) => {
-    if (!confirm(`Are you sure you want to delete the secret "${name}"?`)) return;
+    const readonly = readonlyNames.includes(name);
+    const prompt = readonly
+      ? `"${name}" is read-only. Delete it anyway?`
+      : `Are you sure you want to delete the secret "${name}"?`;
+    if (!confirm(prompt)) return;
 
     try {
-      await invoke("vibesafe_delete", { name });
+      await invoke("vibesafe_delete", { name, overrideReadonly: readonly });
       await loadData();
     } catch (error) {
-      console.error("Failed to delete secret:", error);
+      reportError("Failed to delete secret", error);
     }
   };
 
@@ -204,7 +280,21 @@ function App() {
       await invoke("copy_secret_to_clipboard", { name });
       // You could show a toast notification here
     } catch (error) {
-      console.error("Failed to copy secret:", error);
+      reportError("Failed to copy secret", error);
+    }
+  };
+
+  const handleUnlock = async () => {
+    try {
+      if (status?.passphrase_protected) {
+        await invoke("unlock_with_passphrase", { passphrase });
+        setPassphrase("");
+      } else {
+        await invoke("unlock_vault");
+      }
+      await loadData();
+    } catch (error) {
+      reportError("Failed to unlock VibeSafe", error);
     }
   };
 
@@ -213,7 +303,7 @@ function App() {
       await invoke("vibesafe_init");
       await loadData();
     } catch (error) {
-      console.error("Failed to initialize VibeSafe:", error);
+      reportError("Failed to initialize VibeSafe", error);
     }
   };
 
@@ -221,6 +311,17 @@ function App() {
     secret.name.toLowerCase().includes(searchTerm.toLowerCase())
   );
 
+  const errorBanner = actionError && (
+    <div className="mb-6 p-4 border border-destructive rounded-lg text-sm flex items-center justify-between">
+      <span>
+        {actionError.message} <code className="text-xs text-muted-foreground">{actionError.code}</code>
+      </span>
+      <Button variant="ghost" size="sm" onClick={() => setActionError(null)}>
+        Dismiss
+      </Button>
+    </div>
+  );
+
   if (loading) {
     return (
       <div className="min-h-screen bg-background flex items-center justify-center">
@@ -244,6 +345,7 @@ function App() {
             </p>
           </CardHeader>
           <CardContent>
+            {errorBanner}
             <p className="text-sm text-muted-foreground mb-6">
               VibeSafe is not initialized yet. Click the button below to generate your encryption keys and get started.
             </p>
@@ -271,7 +373,7 @@ function App() {
               {status.passkey_enabled ? (
                 <div className="flex items-center text-green-600">
                   <Lock className="h-4 w-4 mr-1" />
-                  <span className="text-sm">Touch ID Protected</span>
+                  <span className="text-sm">{passkeyLabel(status.passkey)} Protected</span>
                 </div>
               ) : (
                 <div className="flex items-center text-yellow-600">
@@ -290,6 +392,39 @@ function App() {
       {/* Main Content */}
       <main className="p-6">
         <div className="max-w-4xl mx-auto">
+          {errorBanner}
+
+          {status?.sync_warning && (
+            <div className="mb-6 p-4 border border-destructive rounded-lg text-sm">
+              {status.sync_warning}
+            </div>
+          )}
+
+          {status.state === "Locked" && (
+            <div className="mb-6 p-4 border rounded-lg flex items-center justify-between">
+              <div className="flex items-center text-sm">
+                <Lock className="h-4 w-4 mr-2" />
+                {status.passphrase_protected
+                  ? "Enter your passphrase to add or delete secrets."
+                  : "Confirm your passkey to add or delete secrets."}
+              </div>
+              <div className="flex items-center gap-2">
+                {status.passphrase_protected && (
+                  <input
+                    type="password"
+                    placeholder="Passphrase"
+                    value={passphrase}
+                    onChange={(e) => setPassphrase(e.target.value)}
+                    className="px-3 py-2 border border-input rounded-md bg-background text-sm focus:outline-none focus:ring-2 focus:ring-ring"
+                  />
+                )}
+                <Button size="sm" onClick={handleUnlock}>
+                  Unlock
+                </Button>
+              </div>
+            </div>
+          )}
+
           {/* Stats Cards */}
           <div className="grid grid-cols-1 md:grid-cols-3 gap-6 mb-8">
             <Card>
@@ -322,7 +457,10 @@ function App() {
                   <Lock className="h-8 w-8 text-blue-600" />
                   <div className="ml-4">
                     <p className="text-2xl font-bold">{status.passkey_enabled ? "ON" : "OFF"}</p>
-                    <p className="text-sm text-muted-foreground">Touch ID</p>
+                    <p className="text-sm text-muted-foreground">{passkeyLabel(status.passkey)}</p>
+                    {passkeyDetail(status.passkey) && (
+                      <p className="text-xs text-muted-foreground">{passkeyDetail(status.passkey)}</p>
+                    )}
                   </div>
                 </div>
               </CardContent>
@@ -369,7 +507,15 @@ function App() {
                       <div className="flex items-center">
                         <Key className="h-5 w-5 text-muted-foreground mr-3" />
                         <div>
-                          <p className="font-medium">{secret.name}</p>
+                          <p className="font-medium flex items-center">
+                            {secret.name}
+                            {readonlyNames.includes(secret.name) && (
+                              <Lock
+                                className="h-3 w-3 ml-2 text-muted-foreground"
+                                aria-label="Read-only"
+                              />
+                            )}
+                          </p>
                           {secret.created_at && (
                             <p className="text-sm text-muted-foreground">
                               Created {new Date(secret.created_at).toLocaleDateString()}
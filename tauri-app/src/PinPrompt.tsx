@@ -0,0 +1,49 @@
+import React, { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/tauri";
+import { Lock } from "lucide-react";
+
+// Rendered in the "pin" window the backend opens when polkit isn't available.
+function PinPrompt() {
+  const [reason, setReason] = useState("");
+  const [pin, setPin] = useState("");
+
+  useEffect(() => {
+    invoke<string | null>("get_pin_prompt").then((text) => setReason(text ?? ""));
+  }, []);
+
+  const submit = async (event: React.FormEvent) => {
+    event.preventDefault();
+    await invoke("submit_confirmation_pin", { pin });
+    setPin("");
+  };
+
+  const cancel = () => invoke("cancel_confirmation_pin");
+
+  return (
+    <form onSubmit={submit} className="min-h-screen bg-background p-6 flex flex-col justify-center space-y-4">
+      <div className="flex items-center space-x-2">
+        <Lock className="h-5 w-5 text-primary" />
+        <p className="text-sm font-medium">{reason || "Confirm with your PIN"}</p>
+      </div>
+      <input
+        type="password"
+        inputMode="numeric"
+        autoComplete="off"
+        autoFocus
+        value={pin}
+        onChange={(e) => setPin(e.target.value)}
+        className="h-10 w-full rounded-md border border-input bg-background px-3 text-sm"
+      />
+      <div className="flex justify-end space-x-2">
+        <button type="button" onClick={cancel} className="h-9 px-3 rounded-md border border-input text-sm">
+          Cancel
+        </button>
+        <button type="submit" disabled={!pin} className="h-9 px-3 rounded-md bg-primary text-primary-foreground text-sm disabled:opacity-50">
+          Confirm
+        </button>
+      </div>
+    </form>
+  );
+}
+
+export default PinPrompt;
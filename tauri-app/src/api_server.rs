@@ -0,0 +1,737 @@
+use crate::atomic::atomic_write_json;
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::state::{self, VaultState};
+use crate::{cli, names, paths, settings, SecretInfo};
+use chrono::{DateTime, Utc};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Manager, State, Window};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// In the app data directory. Holds token hashes, never the tokens.
+const CLIENTS_FILE: &str = "api_clients.json";
+const TOKEN_PREFIX: &str = "vsk_";
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const AUDIT_REASON_PREFIX: &str = "Local API client";
+
+/// Held while the clients file is read, changed and written back, so concurrent
+/// creates and revokes don't drop each other's change.
+static CLIENTS_LOCK: Mutex<()> = Mutex::new(());
+
+/// A tool allowed to use the local API, as stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiClient {
+    id: String,
+    name: String,
+    /// Hex SHA-256 of the bearer token.
+    token_hash: String,
+    /// Exact secret names the client may list and read.
+    allowed_secrets: Vec<String>,
+    requests_per_minute: u32,
+    created_at: DateTime<Utc>,
+}
+
+/// A client as the UI sees it, without the token hash.
+#[derive(Debug, Serialize)]
+pub struct ApiClientInfo {
+    pub id: String,
+    pub name: String,
+    pub allowed_secrets: Vec<String>,
+    pub requests_per_minute: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&ApiClient> for ApiClientInfo {
+    fn from(client: &ApiClient) -> Self {
+        ApiClientInfo {
+            id: client.id.clone(),
+            name: client.name.clone(),
+            allowed_secrets: client.allowed_secrets.clone(),
+            requests_per_minute: client.requests_per_minute,
+            created_at: client.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewApiClient {
+    pub client: ApiClientInfo,
+    /// Shown once; only its hash is kept.
+    pub token: String,
+}
+
+struct Running {
+    address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// The server, while it runs, and each client's requests in the current minute.
+#[derive(Default)]
+pub struct ApiServer {
+    running: Mutex<Option<Running>>,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl ApiServer {
+    /// Counts a request against `client`; the seconds until the window resets when
+    /// it's over its limit.
+    fn throttle(&self, client: &ApiClient) -> Result<(), u64> {
+        let mut windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+        let (started, count) = windows
+            .entry(client.id.clone())
+            .or_insert((Instant::now(), 0));
+        if started.elapsed() >= RATE_WINDOW {
+            *started = Instant::now();
+            *count = 0;
+        }
+        if *count >= client.requests_per_minute {
+            return Err(RATE_WINDOW.saturating_sub(started.elapsed()).as_secs() + 1);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+fn clients_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(paths::app_data_dir(app)?.join(CLIENTS_FILE))
+}
+
+fn read_clients_from(path: &Path) -> Result<Vec<ApiClient>, AppError> {
+    match fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .map_err(|e| AppError::Cli(format!("{} is invalid: {}", path.display(), e))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(AppError::Cli(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+fn write_clients_to(path: &Path, clients: &[ApiClient]) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    atomic_write_json(path, &clients)
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+fn read_clients(app: &AppHandle) -> Result<Vec<ApiClient>, AppError> {
+    read_clients_from(&clients_path(app)?)
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The client whose token is in the request's `Authorization: Bearer` header.
+/// The file is read on every request, so a revoked token stops working at once.
+fn authenticate(clients_file: &Path, request: &Request<Body>) -> Option<ApiClient> {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?
+        .trim();
+    let hash = hash_token(token);
+    read_clients_from(clients_file)
+        .ok()?
+        .into_iter()
+        .find(|client| client.token_hash == hash)
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    response
+}
+
+fn refuse(status: StatusCode, code: &str, message: &str) -> Response<Body> {
+    respond(status, json!({ "code": code, "message": message }))
+}
+
+fn failed(error: AppError) -> Response<Body> {
+    let status = match error {
+        AppError::InvalidInput(_)
+        | AppError::EmptyName
+        | AppError::NameTooLong { .. }
+        | AppError::InvalidNameCharacters
+        | AppError::ReservedName(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    respond(status, json!(error))
+}
+
+/// Where the routes keep their state, apart from the app so tests can serve
+/// them from a temporary directory.
+struct Files {
+    clients: PathBuf,
+    audit_log: PathBuf,
+}
+
+impl Files {
+    fn of(app: &AppHandle) -> Result<Self, AppError> {
+        Ok(Files {
+            clients: clients_path(app)?,
+            audit_log: audit::log_path(app)?,
+        })
+    }
+}
+
+async fn read_secret<F, Fut>(
+    files: &Files,
+    list: F,
+    client: &ApiClient,
+    name: &str,
+) -> Response<Body>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<SecretInfo>, AppError>>,
+{
+    if let Err(error) = names::check_secret_name(name) {
+        return failed(error);
+    }
+    if !client.allowed_secrets.iter().any(|allowed| allowed == name) {
+        return refuse(
+            StatusCode::FORBIDDEN,
+            "OUT_OF_SCOPE",
+            "This client isn't allowed to read that secret",
+        );
+    }
+    match list().await {
+        Ok(secrets) if !secrets.iter().any(|secret| secret.name == name) => {
+            return refuse(StatusCode::NOT_FOUND, "NOT_FOUND", "No such secret")
+        }
+        Ok(_) => {}
+        Err(error) => return failed(error),
+    }
+    let stdout = match cli::get_secret(name).await {
+        Ok(stdout) => stdout,
+        Err(error) => return failed(error),
+    };
+    let reason = format!("{} {}", AUDIT_REASON_PREFIX, client.name);
+    if let Err(error) = audit::record_to(&files.audit_log, name, AccessAction::Read, Some(&reason))
+    {
+        // A read that can't be audited isn't handed out.
+        return failed(error);
+    }
+    let value = crate::SecretValue::from_bytes(&stdout[..]);
+    respond(StatusCode::OK, json!({ "name": name, "secret": value }))
+}
+
+/// Lets a request through to the routes: a GET with a known token from a client
+/// under its rate limit. Anything else gets the refusal to send back.
+fn admit(
+    server: &ApiServer,
+    clients_file: &Path,
+    request: &Request<Body>,
+) -> Result<ApiClient, Box<Response<Body>>> {
+    if request.method() != Method::GET {
+        return Err(Box::new(refuse(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "METHOD_NOT_ALLOWED",
+            "Only GET is supported",
+        )));
+    }
+    let client = authenticate(clients_file, request).ok_or_else(|| {
+        Box::new(refuse(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "Missing or unknown bearer token",
+        ))
+    })?;
+    if let Err(retry_after) = server.throttle(&client) {
+        let mut response = refuse(
+            StatusCode::TOO_MANY_REQUESTS,
+            "RATE_LIMITED",
+            "Too many requests",
+        );
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, retry_after.to_string().parse().unwrap());
+        return Err(Box::new(response));
+    }
+    Ok(client)
+}
+
+fn health() -> Response<Body> {
+    respond(
+        StatusCode::OK,
+        json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }),
+    )
+}
+
+/// Admits `request` and answers it, with `list` giving the store's secrets.
+async fn route<F, Fut>(
+    server: &ApiServer,
+    files: &Files,
+    list: F,
+    request: Request<Body>,
+) -> Response<Body>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<SecretInfo>, AppError>>,
+{
+    let client = match admit(server, &files.clients, &request) {
+        Ok(client) => client,
+        Err(refusal) => return *refusal,
+    };
+
+    let path = request.uri().path();
+    match path {
+        "/v1/health" => health(),
+        "/v1/secrets" => match list().await {
+            Ok(secrets) => {
+                let names: Vec<String> = secrets
+                    .into_iter()
+                    .map(|secret| secret.name)
+                    .filter(|name| client.allowed_secrets.contains(name))
+                    .collect();
+                respond(StatusCode::OK, json!({ "secrets": names }))
+            }
+            Err(error) => failed(error),
+        },
+        _ => match path.strip_prefix("/v1/secrets/") {
+            Some(name) => read_secret(files, list, &client, name).await,
+            None => refuse(StatusCode::NOT_FOUND, "NOT_FOUND", "No such endpoint"),
+        },
+    }
+}
+
+async fn handle(app: AppHandle, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let files = match Files::of(&app) {
+        Ok(files) => files,
+        Err(error) => return Ok(failed(error)),
+    };
+    let list = || crate::cached_secrets(&app, false);
+    Ok(route(&app.state::<ApiServer>(), &files, list, request).await)
+}
+
+/// Where the server listens, from settings.
+fn listen_address(app: &AppHandle) -> Result<SocketAddr, AppError> {
+    let settings = settings::load_settings(app)?;
+    let ip: IpAddr = settings.api_server_address.parse().map_err(|_| {
+        AppError::InvalidInput(format!(
+            "'{}' isn't an IP address",
+            settings.api_server_address
+        ))
+    })?;
+    Ok(SocketAddr::new(ip, settings.api_server_port))
+}
+
+/// Starts the server on `address` and returns where it's listening (port 0 picks
+/// a free one). Anything but a loopback address is refused: the API hands out
+/// secret values.
+pub fn start_on(app: &AppHandle, address: SocketAddr) -> Result<SocketAddr, AppError> {
+    if !address.ip().is_loopback() {
+        return Err(AppError::InvalidInput(format!(
+            "The local API only listens on loopback addresses, not {}",
+            address.ip()
+        )));
+    }
+    let server = app.state::<ApiServer>();
+    let mut running = server
+        .running
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(running) = running.as_ref() {
+        return Ok(running.address);
+    }
+    let builder = Server::try_bind(&address)
+        .map_err(|e| AppError::Cli(format!("Failed to listen on {}: {}", address, e)))?;
+    let app_handle = app.clone();
+    let service = make_service_fn(move |_| {
+        let app = app_handle.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle(app.clone(), request))) }
+    });
+    let server = builder.serve(service);
+    let bound = server.local_addr();
+    let (shutdown, stopped) = oneshot::channel();
+    tauri::async_runtime::spawn(server.with_graceful_shutdown(async {
+        let _ = stopped.await;
+    }));
+    *running = Some(Running {
+        address: bound,
+        shutdown,
+    });
+    Ok(bound)
+}
+
+/// Stops the server if it's running. Requests in flight finish first. Also runs
+/// when the app locks or the machine goes to sleep; it stays off until started
+/// again.
+pub fn stop(app: &AppHandle) {
+    let server = app.state::<ApiServer>();
+    let running = server
+        .running
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(running) = running {
+        let _ = running.shutdown.send(());
+    }
+}
+
+/// Starts the server when `api_server_enabled` is set and the app is Ready: at
+/// launch, and again when the app leaves Locked. Failures leave it off;
+/// `get_api_server_status` shows that.
+pub async fn start_if_enabled(app: AppHandle) {
+    let enabled = settings::load_settings(&app).is_ok_and(|settings| settings.api_server_enabled);
+    if enabled
+        && state::current(&app)
+            .await
+            .is_ok_and(|state| state == VaultState::Ready)
+    {
+        if let Ok(address) = listen_address(&app) {
+            let _ = start_on(&app, address);
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiServerStatus {
+    pub running: bool,
+    pub address: Option<SocketAddr>,
+}
+
+/// Starts the local API on the loopback address and port from settings.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn start_api_server(app: AppHandle) -> Result<ApiServerStatus, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let address = start_on(&app, listen_address(&app)?)?;
+    Ok(ApiServerStatus {
+        running: true,
+        address: Some(address),
+    })
+}
+
+#[command]
+pub fn stop_api_server(app: AppHandle) {
+    stop(&app);
+}
+
+#[command]
+pub fn get_api_server_status(server: State<'_, ApiServer>) -> ApiServerStatus {
+    let address = server
+        .running
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .map(|running| running.address);
+    ApiServerStatus {
+        running: address.is_some(),
+        address,
+    }
+}
+
+/// Pairs a tool with the local API once the user confirms. It may list and read
+/// only `allowed_secrets`, at most `requests_per_minute` times a minute (60 by
+/// default). The token is returned once and only its hash is stored.
+#[command]
+pub async fn create_api_client(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    name: String,
+    allowed_secrets: Vec<String>,
+    requests_per_minute: Option<u32>,
+) -> Result<NewApiClient, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput(
+            "The client needs a name".to_string(),
+        ));
+    }
+    for secret in &allowed_secrets {
+        names::check_secret_name(secret)?;
+    }
+    let requests_per_minute = requests_per_minute.unwrap_or(DEFAULT_REQUESTS_PER_MINUTE);
+    if requests_per_minute == 0 {
+        return Err(AppError::InvalidInput(
+            "The rate limit must allow at least one request a minute".to_string(),
+        ));
+    }
+
+    let reason = format!("Allow {} to read secrets through the local API", name);
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let token = format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let client = ApiClient {
+        id: Uuid::new_v4().to_string(),
+        name,
+        token_hash: hash_token(&token),
+        allowed_secrets,
+        requests_per_minute,
+        created_at: Utc::now(),
+    };
+    add(&clients_path(&app)?, client.clone())?;
+    Ok(NewApiClient {
+        client: ApiClientInfo::from(&client),
+        token,
+    })
+}
+
+#[command]
+pub fn list_api_clients(app: AppHandle) -> Result<Vec<ApiClientInfo>, AppError> {
+    Ok(read_clients(&app)?
+        .iter()
+        .map(ApiClientInfo::from)
+        .collect())
+}
+
+/// Appends `client` to the file.
+fn add(clients_file: &Path, client: ApiClient) -> Result<(), AppError> {
+    let _guard = CLIENTS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut clients = read_clients_from(clients_file)?;
+    clients.push(client);
+    write_clients_to(clients_file, &clients)
+}
+
+/// Drops client `id` from the file.
+fn revoke(clients_file: &Path, id: &str) -> Result<(), AppError> {
+    let _guard = CLIENTS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut clients = read_clients_from(clients_file)?;
+    let before = clients.len();
+    clients.retain(|client| client.id != id);
+    if clients.len() == before {
+        return Err(AppError::InvalidInput(format!(
+            "No API client with id '{}'",
+            id
+        )));
+    }
+    write_clients_to(clients_file, &clients)
+}
+
+/// Revokes a client's token immediately, including for a running server.
+#[command]
+pub fn revoke_api_client(app: AppHandle, id: String) -> Result<(), AppError> {
+    revoke(&clients_path(&app)?, &id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    fn client(id: &str, token: &str, requests_per_minute: u32) -> ApiClient {
+        ApiClient {
+            id: id.to_string(),
+            name: id.to_string(),
+            token_hash: hash_token(token),
+            allowed_secrets: vec!["API_KEY".to_string()],
+            requests_per_minute,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Serves the real routes on a free loopback port, with the clients file and
+    /// audit log in `dir` and API_KEY and OTHER_KEY in the store.
+    fn serve(dir: &Path) -> SocketAddr {
+        let server = std::sync::Arc::new(ApiServer::default());
+        let files = std::sync::Arc::new(Files {
+            clients: dir.join(CLIENTS_FILE),
+            audit_log: dir.join("access.log"),
+        });
+        let service = make_service_fn(move |_| {
+            let (server, files) = (server.clone(), files.clone());
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let (server, files) = (server.clone(), files.clone());
+                    async move {
+                        let list = || async {
+                            Ok(["API_KEY", "OTHER_KEY"]
+                                .map(|name| SecretInfo {
+                                    name: name.to_string(),
+                                    created_at: None,
+                                })
+                                .to_vec())
+                        };
+                        Ok::<_, Infallible>(route(&server, &files, list, request).await)
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(service);
+        let address = server.local_addr();
+        tokio::spawn(server);
+        address
+    }
+
+    /// The status code and body of a GET of `path`, with `token` as the bearer
+    /// token.
+    async fn get(address: SocketAddr, path: &str, token: Option<&str>) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let authorization = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n",
+            path, authorization
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or_default();
+        (status, body.to_string())
+    }
+
+    async fn get_status(address: SocketAddr, token: Option<&str>) -> u16 {
+        get(address, "/v1/health", token).await.0
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn clients_read_only_the_secrets_in_their_scope() {
+        let fake = crate::test_support::fake_cli(
+            r#"[ "$1" = get ] && echo "$2" >> reads && printf 'value of %s' "$2""#,
+        );
+        let dir = tempfile::tempdir().unwrap();
+        write_clients_to(
+            &dir.path().join(CLIENTS_FILE),
+            &[client("cli", "vsk_good", 60)],
+        )
+        .unwrap();
+        let address = serve(dir.path());
+
+        let (status, body) = get(address, "/v1/secrets/API_KEY", Some("vsk_good")).await;
+        assert_eq!(status, 200);
+        assert!(body.contains("value of API_KEY"), "{}", body);
+        let (status, _) = get(address, "/v1/secrets/API_KEY", Some("vsk_wrong")).await;
+        assert_eq!(status, 401);
+        let (status, body) = get(address, "/v1/secrets/OTHER_KEY", Some("vsk_good")).await;
+        assert_eq!(status, 403);
+        assert!(body.contains("OUT_OF_SCOPE"), "{}", body);
+        let (status, body) = get(address, "/v1/secrets", Some("vsk_good")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body, r#"{"secrets":["API_KEY"]}"#);
+
+        // Only the in-scope read reached the CLI, and it was audited.
+        let reads = fs::read_to_string(fake.path("reads")).unwrap();
+        assert_eq!(reads, "API_KEY\n");
+        let log = fs::read_to_string(dir.path().join("access.log")).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert!(log.contains("Local API client cli"));
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_known_token_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let clients_file = dir.path().join(CLIENTS_FILE);
+        write_clients_to(&clients_file, &[client("cli", "vsk_good", 60)]).unwrap();
+        let address = serve(dir.path());
+
+        assert_eq!(get_status(address, None).await, 401);
+        assert_eq!(get_status(address, Some("vsk_wrong")).await, 401);
+        assert_eq!(get_status(address, Some("vsk_good")).await, 200);
+    }
+
+    #[tokio::test]
+    async fn revoked_tokens_stop_working_on_a_running_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let clients_file = dir.path().join(CLIENTS_FILE);
+        write_clients_to(
+            &clients_file,
+            &[
+                client("kept", "vsk_kept", 60),
+                client("gone", "vsk_gone", 60),
+            ],
+        )
+        .unwrap();
+        let address = serve(dir.path());
+        assert_eq!(get_status(address, Some("vsk_gone")).await, 200);
+
+        revoke(&clients_file, "gone").unwrap();
+        assert_eq!(get_status(address, Some("vsk_gone")).await, 401);
+        assert_eq!(get_status(address, Some("vsk_kept")).await, 200);
+        assert!(revoke(&clients_file, "gone").is_err());
+    }
+
+    #[tokio::test]
+    async fn clients_over_their_limit_are_throttled() {
+        let dir = tempfile::tempdir().unwrap();
+        let clients_file = dir.path().join(CLIENTS_FILE);
+        write_clients_to(
+            &clients_file,
+            &[
+                client("slow", "vsk_slow", 2),
+                client("other", "vsk_other", 2),
+            ],
+        )
+        .unwrap();
+        let address = serve(dir.path());
+
+        assert_eq!(get_status(address, Some("vsk_slow")).await, 200);
+        assert_eq!(get_status(address, Some("vsk_slow")).await, 200);
+        assert_eq!(get_status(address, Some("vsk_slow")).await, 429);
+        // Each client has its own window.
+        assert_eq!(get_status(address, Some("vsk_other")).await, 200);
+    }
+
+    #[test]
+    fn concurrent_changes_to_the_clients_file_are_all_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let clients_file = dir.path().join(CLIENTS_FILE);
+        write_clients_to(&clients_file, &[client("old", "vsk_old", 60)]).unwrap();
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let clients_file = &clients_file;
+                scope.spawn(move || {
+                    let id = format!("new{}", i);
+                    add(clients_file, client(&id, &id, 60)).unwrap();
+                });
+            }
+            scope.spawn(|| revoke(&clients_file, "old").unwrap());
+        });
+        let mut ids: Vec<String> = read_clients_from(&clients_file)
+            .unwrap()
+            .into_iter()
+            .map(|client| client.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, (0..8).map(|i| format!("new{}", i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn throttling_reports_when_the_window_resets() {
+        let server = ApiServer::default();
+        let client = client("one", "vsk_one", 1);
+        assert!(server.throttle(&client).is_ok());
+        let retry_after = server.throttle(&client).unwrap_err();
+        assert!((1..=60).contains(&retry_after));
+    }
+}
@@ -0,0 +1,355 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::{cli, instance, paths, settings, shell_export};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{command, AppHandle, Manager};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Runs the process as an `SSH_ASKPASS` helper; ssh passes the prompt after it.
+pub const ASKPASS_FLAG: &str = "--ssh-askpass";
+/// Name of the helper in the app's bin directory. Started under this name (the
+/// Windows hard link) the binary goes straight to askpass mode.
+pub const HELPER_NAME: &str = "vibesafe-askpass";
+
+/// Key file path from an OpenSSH passphrase prompt: ssh asks "Enter passphrase
+/// for key '/path': ", ssh-add "Enter passphrase for /path: ". Anything else
+/// (host key confirmation, a login password) is `None`.
+pub fn key_path_from_prompt(prompt: &str) -> Option<&str> {
+    let rest = prompt.trim().split("passphrase for ").nth(1)?;
+    let path = match rest.strip_prefix("key '") {
+        Some(quoted) => quoted.split('\'').next()?,
+        None => rest.trim_end().trim_end_matches(':').trim_end(),
+    };
+    if path.is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Secret name for the key at `key_path` under `template`: `{key}` becomes the
+/// file name with characters secret names don't allow replaced by `_`, so
+/// `~/.ssh/id_ed25519` maps to `ssh-id_ed25519` by default.
+pub fn secret_name_for_key(template: &str, key_path: &str) -> String {
+    let file_name = key_path.rsplit(['/', '\\']).next().unwrap_or(key_path);
+    let key: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    template.replace("{key}", &key)
+}
+
+/// Asks the user for the answer themselves, for prompts with no stored secret.
+/// Uses the platform's own dialog tool; `None` if it was cancelled or is missing.
+fn prompt_user(prompt: &str) -> Option<Zeroizing<String>> {
+    let hidden = prompt.contains("passphrase") || prompt.contains("password");
+    let output = if cfg!(target_os = "macos") {
+        let script = format!(
+            "text returned of (display dialog \"{}\" default answer \"\"{} with title \"VibeSafe\")",
+            prompt.replace('\\', "\\\\").replace('"', "\\\""),
+            if hidden { " with hidden answer" } else { "" }
+        );
+        Command::new("osascript").args(["-e", &script]).output()
+    } else if cfg!(windows) {
+        let script = format!(
+            "$c = Get-Credential -UserName ssh -Message '{}'; if ($c) {{ $c.GetNetworkCredential().Password }}",
+            prompt.replace('\'', "''")
+        );
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .output()
+    } else {
+        let kind = if hidden { "--password" } else { "--entry" };
+        Command::new("zenity")
+            .args([kind, "--title=VibeSafe", &format!("--text={}", prompt)])
+            .output()
+            .or_else(|_| {
+                let kind = if hidden { "--password" } else { "--inputbox" };
+                Command::new("kdialog").args([kind, prompt]).output()
+            })
+    };
+    let output = output.ok().filter(|output| output.status.success())?;
+    let answer = Zeroizing::new(String::from_utf8_lossy(&output.stdout).into_owned());
+    Some(Zeroizing::new(
+        answer.trim_end_matches(['\r', '\n']).to_string(),
+    ))
+}
+
+/// The running app's answer to an askpass request over the single-instance
+/// socket.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AskpassReply {
+    /// The stored passphrase, base64-encoded.
+    Answer { value: String },
+    /// No secret follows the naming convention for the key.
+    NoSecret,
+    /// The user declined, or the read failed; `code` is the `AppError` code.
+    Refused { code: String },
+}
+
+/// Looks up the stored passphrase for `key_path` and reads it once the user
+/// confirms with their passkey. `Ok(None)` when no secret follows the naming
+/// convention.
+async fn stored_passphrase(
+    app: &AppHandle,
+    key_path: &str,
+) -> Result<Option<Zeroizing<Vec<u8>>>, AppError> {
+    let template = settings::load_settings(app)
+        .unwrap_or_default()
+        .ssh_askpass_name_template;
+    let name = secret_name_for_key(&template, key_path);
+    if !crate::cached_secrets(app, false)
+        .await?
+        .iter()
+        .any(|secret| secret.name == name)
+    {
+        return Ok(None);
+    }
+
+    let window = crate::show_main_window(app)
+        .ok_or_else(|| AppError::AuthUnavailable("The main window is missing".to_string()))?;
+    let provider = app.state::<SharedAuthProvider>().inner().clone();
+    auth::confirm_export(
+        app,
+        provider,
+        window,
+        format!(
+            "SSH is asking for the passphrase of {}; use the one stored as {}",
+            key_path, name
+        ),
+    )
+    .await?;
+    let value = cli::get_secret(&name).await?;
+    audit::record(
+        app,
+        &name,
+        AccessAction::Read,
+        Some(&format!("SSH askpass: {}", key_path)),
+    )?;
+    Ok(Some(value))
+}
+
+/// Answers an askpass request handed to the running instance.
+pub async fn answer(app: &AppHandle, key_path: &str) -> AskpassReply {
+    match stored_passphrase(app, key_path).await {
+        Ok(Some(value)) => AskpassReply::Answer {
+            value: BASE64.encode(&value[..]),
+        },
+        Ok(None) => AskpassReply::NoSecret,
+        Err(error) => AskpassReply::Refused {
+            code: error.code().to_string(),
+        },
+    }
+}
+
+/// Answers one ssh prompt on stdout and returns the exit code; ssh treats any
+/// non-zero code as a cancelled prompt. `args` are the arguments after the flag
+/// (or all of them when started as the helper), the prompt being the last.
+/// Stored passphrases come from the running app, which asks for the passkey;
+/// when it isn't running or has no matching secret the user types the answer.
+pub fn run(args: &[String], data_dir: Option<PathBuf>) -> i32 {
+    let prompt = args.last().map(String::as_str).unwrap_or_default();
+    let reply = match (key_path_from_prompt(prompt), &data_dir) {
+        (Some(key_path), Some(data_dir)) => instance::askpass(data_dir, key_path),
+        _ => None,
+    };
+    let answer = match reply {
+        Some(AskpassReply::Answer { mut value }) => {
+            let decoded = BASE64.decode(&value);
+            value.zeroize();
+            match decoded {
+                Ok(bytes) => Zeroizing::new(bytes),
+                Err(_) => return 1,
+            }
+        }
+        Some(AskpassReply::Refused { .. }) => return 1,
+        Some(AskpassReply::NoSecret) | None => match prompt_user(prompt) {
+            Some(answer) => Zeroizing::new(answer.as_bytes().to_vec()),
+            None => return 1,
+        },
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let written = stdout
+        .write_all(&answer)
+        .and_then(|_| stdout.write_all(b"\n"))
+        .and_then(|_| stdout.flush());
+    if written.is_ok() {
+        0
+    } else {
+        1
+    }
+}
+
+/// The helper script: starts the app in askpass mode with ssh's arguments.
+fn helper_script(exe: &Path) -> String {
+    format!(
+        "#!/bin/sh\nexec {} {} \"$@\"\n",
+        shell_export::posix_quote(&exe.to_string_lossy()),
+        ASKPASS_FLAG
+    )
+}
+
+/// Lines that point ssh at `helper`, for the user's shell profile.
+fn exports(helper: &Path) -> Vec<String> {
+    let helper = helper.to_string_lossy();
+    if cfg!(windows) {
+        vec![
+            format!("$env:SSH_ASKPASS = '{}'", helper.replace('\'', "''")),
+            "$env:SSH_ASKPASS_REQUIRE = 'force'".to_string(),
+        ]
+    } else {
+        vec![
+            format!("export SSH_ASKPASS={}", shell_export::posix_quote(&helper)),
+            "export SSH_ASKPASS_REQUIRE=force".to_string(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AskpassInstall {
+    pub helper_path: PathBuf,
+    /// Shell lines to add to a profile so ssh and git use the helper.
+    pub exports: Vec<String>,
+}
+
+/// Puts the askpass helper in the app's bin directory: a script that starts the
+/// app in askpass mode, or on Windows (where ssh needs an executable) a hard link
+/// to the app named `vibesafe-askpass.exe`. Returns the exports to enable it.
+#[command]
+pub fn install_ssh_askpass(app: AppHandle) -> Result<AskpassInstall, AppError> {
+    let exe = std::env::current_exe()
+        .map_err(|e| AppError::Cli(format!("Can't find the app executable: {}", e)))?;
+    let dir = paths::app_data_dir(&app)?.join("bin");
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Cli(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+    let helper = dir.join(format!("{}{}", HELPER_NAME, std::env::consts::EXE_SUFFIX));
+    if cfg!(windows) {
+        let _ = fs::remove_file(&helper);
+        fs::hard_link(&exe, &helper)
+            .map_err(|e| AppError::Cli(format!("Failed to link {}: {}", helper.display(), e)))?;
+    } else {
+        atomic_write(&helper, helper_script(&exe).as_bytes())
+            .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", helper.display(), e)))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&helper, fs::Permissions::from_mode(0o700)).map_err(|e| {
+                AppError::Cli(format!("Failed to make the helper executable: {}", e))
+            })?;
+        }
+    }
+
+    Ok(AskpassInstall {
+        exports: exports(&helper),
+        helper_path: helper,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_paths_come_from_passphrase_prompts() {
+        assert_eq!(
+            key_path_from_prompt("Enter passphrase for key '/home/me/.ssh/id_ed25519': "),
+            Some("/home/me/.ssh/id_ed25519")
+        );
+        assert_eq!(
+            key_path_from_prompt("Enter passphrase for /home/me/.ssh/id_rsa: "),
+            Some("/home/me/.ssh/id_rsa")
+        );
+        assert_eq!(
+            key_path_from_prompt("Enter passphrase for key '/keys/my key': "),
+            Some("/keys/my key")
+        );
+        assert_eq!(
+            key_path_from_prompt(
+                "The authenticity of host 'example.com' can't be established.\n\
+                 Are you sure you want to continue connecting (yes/no)? "
+            ),
+            None
+        );
+        assert_eq!(key_path_from_prompt("me@example.com's password: "), None);
+        assert_eq!(key_path_from_prompt("Enter passphrase for key '': "), None);
+    }
+
+    #[test]
+    fn secret_names_follow_the_template() {
+        assert_eq!(
+            secret_name_for_key("ssh-{key}", "/home/me/.ssh/id_ed25519"),
+            "ssh-id_ed25519"
+        );
+        assert_eq!(
+            secret_name_for_key("ssh-{key}", r"C:\Users\me\.ssh\id_rsa"),
+            "ssh-id_rsa"
+        );
+        assert_eq!(
+            secret_name_for_key("{key}_PASSPHRASE", "/keys/deploy key.pem"),
+            "deploy_key_pem_PASSPHRASE"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn helper_script_quotes_the_executable_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("it's \"$(touch pwned)\" `id` $HOME");
+        fs::create_dir(&app_dir).unwrap();
+        let exe = app_dir.join("vibesafe");
+        fs::write(
+            &exe,
+            "#!/bin/sh\nfor arg in \"$@\"; do echo \"$arg\"; done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o700)).unwrap();
+        let helper = dir.path().join(HELPER_NAME);
+        fs::write(&helper, helper_script(&exe)).unwrap();
+
+        let output = Command::new("/bin/sh")
+            .arg(&helper)
+            .arg("Enter passphrase for key '/k': ")
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{}\nEnter passphrase for key '/k': \n", ASKPASS_FLAG)
+        );
+        assert!(!dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn replies_round_trip_over_the_socket_format() {
+        let reply = AskpassReply::Refused {
+            code: "AUTH_CANCELLED".to_string(),
+        };
+        let line = serde_json::to_string(&reply).unwrap();
+        assert_eq!(line, r#"{"status":"refused","code":"AUTH_CANCELLED"}"#);
+        assert!(matches!(
+            serde_json::from_str(r#"{"status":"no_secret"}"#).unwrap(),
+            AskpassReply::NoSecret
+        ));
+    }
+}
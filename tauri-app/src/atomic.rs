@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Replaces `path` with `contents` so that a crash leaves either the old file or
+/// the new one, never a truncated mix. The data goes to a uniquely named temp
+/// file in the same directory, so concurrent writers don't clash, is synced, and
+/// is renamed over the target (`MoveFileEx` with replace-existing on Windows).
+/// On Unix the directory is synced too, so the rename itself survives power
+/// loss. New files are readable by the owner only.
+pub fn atomic_write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_with(path, |file| file.write_all(contents))
+}
+
+/// `atomic_write` with the temp file's contents written by `write`.
+fn write_with(path: &Path, write: impl FnOnce(&mut File) -> io::Result<()>) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // Created 0600 on Unix, and removed when dropped unless persisted.
+    let mut temp = tempfile::Builder::new()
+        .prefix(&format!(".{}.", file_name))
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    write(temp.as_file_mut())?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| e.error)?;
+
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// `atomic_write` of `value` as pretty-printed JSON.
+pub fn atomic_write_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let contents = serde_json::to_vec_pretty(value)?;
+    atomic_write(path, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn writes_replace_the_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        atomic_write(&path, b"a much longer first version").unwrap();
+        atomic_write(&path, b"short").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"short");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn a_failed_temp_write_leaves_the_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        atomic_write_json(&path, &serde_json::json!({ "revision": 1 })).unwrap();
+
+        let failed = write_with(&path, |file| {
+            file.write_all(b"{ \"revision\": 2, \"trunc")?;
+            Err(io::Error::other("disk full"))
+        });
+        assert_eq!(failed.unwrap_err().kind(), io::ErrorKind::Other);
+        let original: serde_json::Value =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(original, serde_json::json!({ "revision": 1 }));
+        // The temp file went with the failure.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn a_failed_rename_cleans_up_its_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("taken");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("inside"), b"").unwrap();
+        assert!(atomic_write(&path, b"new").is_err());
+        assert!(path.join("inside").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn concurrent_writers_each_replace_the_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clients.json");
+        let contents: Vec<Vec<u8>> = (0..8).map(|i| vec![b'a' + i; 4096]).collect();
+        std::thread::scope(|scope| {
+            for contents in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        atomic_write(path, contents).unwrap();
+                    }
+                });
+            }
+        });
+        assert!(contents.contains(&fs::read(&path).unwrap()));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_readable_by_the_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.json");
+        fs::write(&path, b"{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        atomic_write_json(&path, &serde_json::json!({ "revision": 1 })).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
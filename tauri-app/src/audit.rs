@@ -0,0 +1,330 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle};
+
+use crate::error::AppError;
+use crate::{atomic, paths};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// One JSON object per line, appended to; never holds secret values.
+const ACCESS_LOG_FILE: &str = "access-log.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessAction {
+    /// The value was returned to the UI.
+    Read,
+    /// The value was copied to the clipboard.
+    Copy,
+    /// The secret was made available to an integration (e.g. Claude's MCP server).
+    Expose,
+    /// An integration's access to the secret was withdrawn.
+    Unexpose,
+}
+
+impl AccessAction {
+    /// Whether the value itself was handed out, as opposed to a change in who may
+    /// read it.
+    pub fn is_access(self) -> bool {
+        matches!(self, AccessAction::Read | AccessAction::Copy)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessEntry {
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub name: String,
+    pub action: AccessAction,
+    /// Justification given by the user, for audited environments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn log_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(log_path_in(&paths::app_data_dir(app)?))
+}
+
+/// The log's location inside a data directory, for callers without an `AppHandle`.
+pub fn log_path_in(data_dir: &Path) -> PathBuf {
+    data_dir.join(ACCESS_LOG_FILE)
+}
+
+/// Appends an entry for an access to `name`. Callers fail the access when this
+/// fails, so every value handed out is on record.
+pub fn record(
+    app: &AppHandle,
+    name: &str,
+    action: AccessAction,
+    reason: Option<&str>,
+) -> Result<(), AppError> {
+    record_to(&log_path(app)?, name, action, reason)
+}
+
+/// `record` for an explicit log file.
+pub fn record_to(
+    path: &Path,
+    name: &str,
+    action: AccessAction,
+    reason: Option<&str>,
+) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create data directory: {}", e)))?;
+    }
+
+    let entry = AccessEntry {
+        timestamp: now_secs(),
+        name: name.to_string(),
+        action,
+        reason: reason.map(str::to_string),
+    };
+    let mut line = serde_json::to_string(&entry)
+        .map_err(|e| AppError::Cli(format!("Failed to serialize access log entry: {}", e)))?;
+    line.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| AppError::Cli(format!("Failed to write access log: {}", e)))
+}
+
+/// All entries, oldest first. Lines that don't parse (e.g. a torn final write)
+/// are skipped.
+pub fn read_entries(app: &AppHandle) -> Result<Vec<AccessEntry>, AppError> {
+    read_entries_from(&log_path(app)?)
+}
+
+/// `read_entries` for an explicit log file.
+fn read_entries_from(path: &Path) -> Result<Vec<AccessEntry>, AppError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| AppError::Cli(format!("Failed to read access log: {}", e)))?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Unix seconds of the latest read or copy of `name`, or `null` if it hasn't been
+/// accessed since the log began. Reads only the log; nothing is decrypted.
+#[command]
+pub fn get_secret_last_access(app: AppHandle, name: String) -> Result<Option<u64>, AppError> {
+    Ok(read_entries(&app)?
+        .into_iter()
+        .filter(|entry| entry.name == name && entry.action.is_access())
+        .map(|entry| entry.timestamp)
+        .max())
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnusedSecrets {
+    /// Secrets with no read or copy inside the window, sorted by name.
+    pub names: Vec<String>,
+    /// Unix seconds of the oldest log entry, or `null` if nothing is logged yet.
+    pub logging_started: Option<u64>,
+    /// False when the log is younger than the window, so "unused" only means
+    /// "unused since logging started".
+    pub window_covered: bool,
+}
+
+/// Secrets not read or copied in the last `since_days` days, for review before
+/// deleting. Reads only the list and the log; nothing is decrypted or removed.
+#[command]
+pub async fn find_unused_secrets(
+    app: AppHandle,
+    since_days: u32,
+) -> Result<UnusedSecrets, AppError> {
+    let entries = read_entries(&app)?;
+    let cutoff = now_secs().saturating_sub(u64::from(since_days) * SECONDS_PER_DAY);
+
+    let mut last_access: HashMap<&str, u64> = HashMap::new();
+    for entry in entries.iter().filter(|entry| entry.action.is_access()) {
+        let latest = last_access.entry(&entry.name).or_default();
+        *latest = (*latest).max(entry.timestamp);
+    }
+    let logging_started = entries.iter().map(|entry| entry.timestamp).min();
+
+    let mut names: Vec<String> = crate::cached_secrets(&app, false)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .filter(|name| {
+            last_access
+                .get(name.as_str())
+                .map_or(true, |&timestamp| timestamp < cutoff)
+        })
+        .collect();
+    names.sort();
+
+    Ok(UnusedSecrets {
+        names,
+        logging_started,
+        window_covered: logging_started.is_some_and(|started| started <= cutoff),
+    })
+}
+
+/// Quotes a CSV field when needed, and defuses values a spreadsheet would run as a
+/// formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Writes the log entries from `from` to `to` (inclusive) to `dest_path` as CSV:
+/// timestamp (RFC 3339, UTC), action, secret name, reason. Secret values are never
+/// logged, so they can't appear. Returns the number of rows written.
+#[command]
+pub fn export_audit_report(
+    app: AppHandle,
+    dest_path: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    if from > to {
+        return Err(AppError::InvalidInput(
+            "The report range ends before it starts".to_string(),
+        ));
+    }
+    let dest = Path::new(&dest_path);
+    if !dest.parent().is_some_and(Path::is_dir) {
+        return Err(AppError::InvalidInput(format!(
+            "Can't write the report to {}: the folder doesn't exist",
+            dest_path
+        )));
+    }
+
+    let mut csv = String::from("timestamp,action,secret_name,reason\n");
+    let mut rows = 0;
+    for entry in read_entries(&app)? {
+        let timestamp = match Utc.timestamp_opt(entry.timestamp as i64, 0).single() {
+            Some(timestamp) if timestamp >= from && timestamp <= to => timestamp,
+            _ => continue,
+        };
+        let action = match entry.action {
+            AccessAction::Read => "read",
+            AccessAction::Copy => "copy",
+            AccessAction::Expose => "expose",
+            AccessAction::Unexpose => "unexpose",
+        };
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            timestamp.to_rfc3339(),
+            action,
+            csv_field(&entry.name),
+            csv_field(entry.reason.as_deref().unwrap_or_default())
+        ));
+        rows += 1;
+    }
+
+    atomic::atomic_write(dest, csv.as_bytes()).map_err(|e| {
+        AppError::InvalidInput(format!("Can't write the report to {}: {}", dest_path, e))
+    })?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_appended_never_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = log_path_in(&dir.path().join("data"));
+        record_to(&path, "API_KEY", AccessAction::Read, None).unwrap();
+        let first = fs::read(&path).unwrap();
+        record_to(&path, "API_KEY", AccessAction::Copy, Some("deploy")).unwrap();
+        record_to(&path, "DB_URL", AccessAction::Expose, None).unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        assert!(contents.starts_with(&first));
+        let entries = read_entries_from(&path).unwrap();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.action, entry.reason.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("API_KEY", AccessAction::Read, None),
+                ("API_KEY", AccessAction::Copy, Some("deploy")),
+                ("DB_URL", AccessAction::Expose, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn concurrent_writers_each_get_a_whole_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = log_path_in(dir.path());
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        let name = format!("SECRET_{}", writer);
+                        record_to(&path, &name, AccessAction::Read, None).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 200);
+        assert_eq!(read_entries_from(&path).unwrap().len(), 200);
+    }
+
+    #[test]
+    fn a_torn_final_line_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = log_path_in(dir.path());
+        record_to(&path, "API_KEY", AccessAction::Read, None).unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"timestamp": 17, "na"#)
+            .unwrap();
+        assert_eq!(read_entries_from(&path).unwrap().len(), 1);
+        assert!(read_entries_from(&dir.path().join("missing.jsonl"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_and_defused() {
+        assert_eq!(csv_field("API_KEY"), "API_KEY");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("=HYPERLINK(1)"), "'=HYPERLINK(1)");
+        assert_eq!(csv_field("-1,2"), "\"'-1,2\"");
+    }
+}
@@ -0,0 +1,650 @@
+use crate::error::AppError;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Manager, State, Window};
+
+/// Emitted with `{ "retry_after_secs": n }` when refused confirmations trigger a lockout.
+const LOCKED_OUT_EVENT: &str = "auth:locked-out";
+/// Refused confirmations in a row before the lockout.
+const MAX_FAILURES: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(5 * 60);
+/// The wait before the attempt after the first refusal; it doubles with each
+/// further one, up to `MAX_BACKOFF`.
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A passkey type the CLI can be asked to enable (`vibesafe passkey enable --type <id>`).
+#[derive(Debug, Clone, Serialize)]
+pub struct PasskeyType {
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+/// Platform hook for the app-side "fresh confirmation" checks. The CLI still does
+/// its own authentication when it decrypts; this only proves someone is present.
+pub trait AuthProvider: Send + Sync {
+    /// Name of the confirmation backend in use, reported in status.
+    fn backend(&self) -> &'static str;
+
+    /// Passkey types usable on this machine, in order of preference.
+    fn passkey_types(&self) -> Vec<PasskeyType>;
+
+    /// Blocks until the user confirms their presence or the check fails.
+    fn confirm(&self, window: &Window, reason: &str) -> Result<(), AppError>;
+}
+
+pub type SharedAuthProvider = Arc<dyn AuthProvider>;
+
+const FIDO2_KEY: PasskeyType = PasskeyType {
+    id: "fido2",
+    label: "FIDO2 security key",
+};
+
+#[cfg(windows)]
+pub fn platform_provider() -> SharedAuthProvider {
+    Arc::new(windows_hello::WindowsHello)
+}
+
+#[cfg(target_os = "linux")]
+pub fn platform_provider() -> SharedAuthProvider {
+    Arc::new(polkit::Polkit::new())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn platform_provider() -> SharedAuthProvider {
+    Arc::new(CliOnly)
+}
+
+/// Used where the app has no native confirmation backend; authentication is left to the CLI.
+#[cfg(not(any(windows, target_os = "linux")))]
+struct CliOnly;
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl AuthProvider for CliOnly {
+    fn backend(&self) -> &'static str {
+        "none"
+    }
+
+    fn passkey_types(&self) -> Vec<PasskeyType> {
+        let mut types = Vec::new();
+        if cfg!(target_os = "macos") {
+            types.push(PasskeyType {
+                id: "keychain",
+                label: "Touch ID / Face ID (macOS Keychain)",
+            });
+        }
+        types.push(FIDO2_KEY);
+        types
+    }
+
+    fn confirm(&self, _window: &Window, _reason: &str) -> Result<(), AppError> {
+        Err(AppError::AuthUnavailable(
+            "No confirmation backend is available on this platform".to_string(),
+        ))
+    }
+}
+
+#[cfg(windows)]
+mod windows_hello {
+    use super::{AuthProvider, PasskeyType, FIDO2_KEY};
+    use crate::error::AppError;
+    use tauri::Window;
+    use windows::core::{factory, HSTRING};
+    use windows::Foundation::IAsyncOperation;
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+    use windows::Win32::System::WinRT::IUserConsentVerifierInterop;
+
+    pub struct WindowsHello;
+
+    fn unavailable(error: windows::core::Error) -> AppError {
+        AppError::AuthUnavailable(format!("Windows Hello is unavailable: {}", error))
+    }
+
+    fn availability() -> Result<UserConsentVerifierAvailability, AppError> {
+        UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|operation| operation.get())
+            .map_err(unavailable)
+    }
+
+    impl AuthProvider for WindowsHello {
+        fn backend(&self) -> &'static str {
+            "windows-hello"
+        }
+
+        fn passkey_types(&self) -> Vec<PasskeyType> {
+            // The CLI reaches Windows Hello through the platform WebAuthn API, so it
+            // is enabled with the FIDO2 type like an external key.
+            let mut types = Vec::new();
+            if matches!(
+                availability(),
+                Ok(UserConsentVerifierAvailability::Available)
+            ) {
+                types.push(PasskeyType {
+                    id: FIDO2_KEY.id,
+                    label: "Windows Hello",
+                });
+            }
+            types.push(FIDO2_KEY);
+            types
+        }
+
+        fn confirm(&self, window: &Window, reason: &str) -> Result<(), AppError> {
+            match availability()? {
+                UserConsentVerifierAvailability::Available => {}
+                UserConsentVerifierAvailability::DeviceNotPresent => {
+                    return Err(AppError::AuthHardwareAbsent)
+                }
+                UserConsentVerifierAvailability::NotConfiguredForUser => {
+                    return Err(AppError::AuthNotConfigured)
+                }
+                other => {
+                    return Err(AppError::AuthUnavailable(format!(
+                        "Windows Hello is unavailable (status {})",
+                        other.0
+                    )))
+                }
+            }
+
+            // Parent the dialog to our window so it doesn't open behind the app.
+            let hwnd = window
+                .hwnd()
+                .map_err(|e| AppError::AuthUnavailable(format!("No window handle: {}", e)))?;
+            let interop = factory::<UserConsentVerifier, IUserConsentVerifierInterop>()
+                .map_err(unavailable)?;
+            let operation: IAsyncOperation<UserConsentVerificationResult> =
+                unsafe { interop.RequestVerificationForWindowAsync(hwnd, &HSTRING::from(reason)) }
+                    .map_err(unavailable)?;
+
+            match operation.get().map_err(unavailable)? {
+                UserConsentVerificationResult::Verified => Ok(()),
+                UserConsentVerificationResult::Canceled => Err(AppError::AuthCancelled),
+                UserConsentVerificationResult::DeviceNotPresent => {
+                    Err(AppError::AuthHardwareAbsent)
+                }
+                UserConsentVerificationResult::NotConfiguredForUser => {
+                    Err(AppError::AuthNotConfigured)
+                }
+                other => Err(AppError::AuthUnavailable(format!(
+                    "Windows Hello verification failed (status {})",
+                    other.0
+                ))),
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod polkit {
+    use super::{AuthProvider, PasskeyType, FIDO2_KEY};
+    use crate::error::AppError;
+    use once_cell::sync::OnceCell;
+    use std::collections::HashMap;
+    use tauri::{Manager, Window};
+    use zbus::blocking::Connection;
+    use zbus::zvariant::Value;
+
+    /// Declared in linux/com.vibesafe.app.policy, which the deb package installs.
+    const ACTION_ID: &str = "com.vibesafe.app.confirm";
+    /// Lets polkit show its own authentication agent (password or fingerprint via fprintd).
+    const ALLOW_USER_INTERACTION: u32 = 1;
+
+    pub struct Polkit {
+        connection: OnceCell<Option<Connection>>,
+    }
+
+    impl Polkit {
+        pub fn new() -> Self {
+            Self {
+                connection: OnceCell::new(),
+            }
+        }
+
+        /// Connects to the system bus on first use, keeping it off the startup
+        /// path. Concurrent callers wait for the first attempt.
+        fn connection(&self) -> Option<&Connection> {
+            self.connection
+                .get_or_init(|| Connection::system().ok())
+                .as_ref()
+        }
+
+        /// Returns whether the user was authorized, plus polkit's result details.
+        /// The subject is our bus connection, named by its unique name: polkit
+        /// resolves that to the process itself, unlike a PID it would have to
+        /// look up (racy, CVE-2013-4288).
+        fn check_authorization(
+            connection: &Connection,
+            name: &str,
+        ) -> zbus::Result<(bool, HashMap<String, String>)> {
+            let mut subject: HashMap<&str, Value> = HashMap::new();
+            subject.insert("name", Value::from(name));
+            let details: HashMap<&str, &str> = HashMap::new();
+
+            let reply = connection.call_method(
+                Some("org.freedesktop.PolicyKit1"),
+                "/org/freedesktop/PolicyKit1/Authority",
+                Some("org.freedesktop.PolicyKit1.Authority"),
+                "CheckAuthorization",
+                &(
+                    ("system-bus-name", subject),
+                    ACTION_ID,
+                    details,
+                    ALLOW_USER_INTERACTION,
+                    "",
+                ),
+            )?;
+            let (authorized, _challenge, result_details): (bool, bool, HashMap<String, String>) =
+                reply.body().deserialize()?;
+            Ok((authorized, result_details))
+        }
+    }
+
+    /// A dismissed authentication dialog is a cancel, anything else a refusal.
+    pub(super) fn outcome(
+        authorized: bool,
+        details: &HashMap<String, String>,
+    ) -> Result<(), AppError> {
+        if authorized {
+            Ok(())
+        } else if details.get("polkit.dismissed").map(String::as_str) == Some("true") {
+            Err(AppError::AuthCancelled)
+        } else {
+            Err(AppError::AuthDenied)
+        }
+    }
+
+    /// Used when polkit can't be reached or our action isn't installed (e.g. AppImage
+    /// builds): the app's own PIN window, which `backend()` reports as "pin".
+    fn confirm_with_pin(window: &Window, reason: &str) -> Result<(), AppError> {
+        crate::pin::prompt(&window.app_handle(), reason)
+    }
+
+    impl AuthProvider for Polkit {
+        fn backend(&self) -> &'static str {
+            if self.connection().is_some() {
+                "polkit"
+            } else {
+                "pin"
+            }
+        }
+
+        fn passkey_types(&self) -> Vec<PasskeyType> {
+            vec![FIDO2_KEY]
+        }
+
+        fn confirm(&self, window: &Window, reason: &str) -> Result<(), AppError> {
+            let connection = match self.connection() {
+                Some(connection) => connection,
+                None => return confirm_with_pin(window, reason),
+            };
+
+            let checked = match connection.unique_name() {
+                Some(name) => Self::check_authorization(connection, name.as_str()),
+                None => Err(zbus::Error::Failure("No unique bus name".to_string())),
+            };
+            match checked {
+                Ok((authorized, details)) => outcome(authorized, &details),
+                Err(_) => confirm_with_pin(window, reason),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::{Arc, Mutex};
+        use zbus::zvariant::OwnedValue;
+
+        /// A subject's kind and its "name" property.
+        type Subject = (String, Option<String>);
+        /// CheckAuthorization's reply: authorized, challenge, details.
+        type AuthorizationResult = (bool, bool, HashMap<String, String>);
+
+        /// Answers CheckAuthorization like polkit, recording each subject.
+        struct MockAuthority {
+            authorized: bool,
+            details: HashMap<String, String>,
+            subjects: Arc<Mutex<Vec<Subject>>>,
+        }
+
+        #[zbus::interface(name = "org.freedesktop.PolicyKit1.Authority")]
+        impl MockAuthority {
+            fn check_authorization(
+                &self,
+                subject: (String, HashMap<String, OwnedValue>),
+                action_id: String,
+                _details: HashMap<String, String>,
+                _flags: u32,
+                _cancellation_id: String,
+            ) -> zbus::fdo::Result<(AuthorizationResult,)> {
+                if action_id != ACTION_ID {
+                    return Err(zbus::fdo::Error::AccessDenied(action_id));
+                }
+                let (kind, properties) = subject;
+                let name = properties
+                    .get("name")
+                    .and_then(|name| <&str>::try_from(name).ok())
+                    .map(str::to_string);
+                self.subjects.lock().unwrap().push((kind, name));
+                Ok(((self.authorized, false, self.details.clone()),))
+            }
+        }
+
+        /// Runs `check_authorization` against a mock authority over a
+        /// peer-to-peer connection, returning the outcome and the subjects seen.
+        async fn check_against(
+            authorized: bool,
+            details: HashMap<String, String>,
+        ) -> (Result<(), AppError>, Vec<Subject>) {
+            let subjects = Arc::new(Mutex::new(Vec::new()));
+            let authority = MockAuthority {
+                authorized,
+                details,
+                subjects: subjects.clone(),
+            };
+            let (server, client) = tokio::net::UnixStream::pair().unwrap();
+            let server = zbus::connection::Builder::unix_stream(server)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/org/freedesktop/PolicyKit1/Authority", authority)
+                .unwrap()
+                .build();
+            let client = zbus::connection::Builder::unix_stream(client).p2p().build();
+            let (_server, client) = tokio::try_join!(server, client).unwrap();
+            let client = Connection::from(client);
+
+            let (authorized, details) =
+                tokio::task::spawn_blocking(move || Polkit::check_authorization(&client, ":1.42"))
+                    .await
+                    .unwrap()
+                    .unwrap();
+            let subjects = subjects.lock().unwrap().clone();
+            (outcome(authorized, &details), subjects)
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn an_authorized_user_is_confirmed() {
+            let (result, subjects) = check_against(true, HashMap::new()).await;
+            assert!(result.is_ok());
+            assert_eq!(
+                subjects,
+                [("system-bus-name".to_string(), Some(":1.42".to_string()))]
+            );
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn a_refusal_is_denied_and_a_dismissal_cancelled() {
+            let (result, _) = check_against(false, HashMap::new()).await;
+            assert!(matches!(result, Err(AppError::AuthDenied)));
+            let dismissed = HashMap::from([("polkit.dismissed".to_string(), "true".to_string())]);
+            let (result, _) = check_against(false, dismissed).await;
+            assert!(matches!(result, Err(AppError::AuthCancelled)));
+        }
+    }
+}
+
+/// Refused confirmations and wrong passphrases since the last success, kept for
+/// the life of the app.
+#[derive(Default)]
+pub struct AuthAttempts {
+    inner: Mutex<Attempts>,
+}
+
+#[derive(Default)]
+struct Attempts {
+    failures: u32,
+    last_failure: Option<Instant>,
+    locked_until: Option<Instant>,
+}
+
+/// How long to wait before trying again after `failures` refusals in a row:
+/// nothing before the first, then `BASE_BACKOFF` doubling up to `MAX_BACKOFF`.
+pub fn backoff_delay(failures: u32) -> Duration {
+    if failures == 0 {
+        return Duration::ZERO;
+    }
+    let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+    BASE_BACKOFF
+        .checked_mul(factor)
+        .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+}
+
+impl AuthAttempts {
+    /// The wait still owed before the next attempt, or the lockout error.
+    fn before_attempt(&self) -> Result<Duration, AppError> {
+        let mut attempts = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        match attempts.locked_until {
+            Some(until) if until > now => {
+                return Err(AppError::AuthLockedOut {
+                    retry_after_secs: (until - now).as_secs().max(1),
+                })
+            }
+            Some(_) => *attempts = Attempts::default(),
+            None => {}
+        }
+        Ok(attempts.last_failure.map_or(Duration::ZERO, |last| {
+            backoff_delay(attempts.failures).saturating_sub(now - last)
+        }))
+    }
+
+    /// Counts a refusal; returns the lockout's length if this one triggered it.
+    fn failed(&self) -> Option<Duration> {
+        let mut attempts = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        attempts.failures += 1;
+        attempts.last_failure = Some(now);
+        if attempts.failures >= MAX_FAILURES {
+            attempts.locked_until = Some(now + LOCKOUT);
+            return Some(LOCKOUT);
+        }
+        None
+    }
+
+    fn succeeded(&self) {
+        *self.inner.lock().unwrap_or_else(PoisonError::into_inner) = Attempts::default();
+    }
+
+    /// Counts an attempt's result. A refusal or wrong passphrase that triggers the
+    /// lockout comes back as `AUTH_LOCKED_OUT`; a dismissed prompt isn't counted.
+    fn settle(&self, result: Result<(), AppError>) -> Result<(), AppError> {
+        match result {
+            Ok(()) => self.succeeded(),
+            Err(AppError::AuthDenied) | Err(AppError::WrongPassphrase) => {
+                if let Some(lockout) = self.failed() {
+                    return Err(AppError::AuthLockedOut {
+                        retry_after_secs: lockout.as_secs(),
+                    });
+                }
+            }
+            Err(_) => {}
+        }
+        result
+    }
+}
+
+/// Runs `attempt`, a confirmation or passphrase check, under the shared limit.
+/// After a refusal or wrong passphrase the next attempt waits `backoff_delay`;
+/// after `MAX_FAILURES` in a row attempts fail with `AUTH_LOCKED_OUT` for five
+/// minutes and "auth:locked-out" is emitted. A dismissed prompt doesn't count; a
+/// success clears the count and unlocks the app (see `state::unlock`).
+pub async fn rate_limited(
+    app: &AppHandle,
+    attempt: impl Future<Output = Result<(), AppError>>,
+) -> Result<(), AppError> {
+    let wait = app.state::<AuthAttempts>().before_attempt()?;
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+    let result = app.state::<AuthAttempts>().settle(attempt.await);
+    match &result {
+        Ok(()) => crate::state::unlock(app),
+        Err(AppError::AuthLockedOut { retry_after_secs }) => {
+            let _ = app.emit_all(
+                LOCKED_OUT_EVENT,
+                serde_json::json!({ "retry_after_secs": retry_after_secs }),
+            );
+        }
+        Err(_) => {}
+    }
+    result
+}
+
+/// Runs the provider's blocking confirmation off the async runtime, under
+/// `rate_limited`.
+pub async fn confirm_presence(
+    provider: SharedAuthProvider,
+    window: Window,
+    reason: String,
+) -> Result<(), AppError> {
+    let app = window.app_handle();
+    rate_limited(&app, async move {
+        tauri::async_runtime::spawn_blocking(move || provider.confirm(&window, &reason))
+            .await
+            .map_err(|e| AppError::AuthUnavailable(format!("Confirmation task failed: {}", e)))?
+    })
+    .await
+}
+
+/// `confirm_presence` for commands that hand values out of the app. Where there
+/// is no confirmation backend the CLI's passkey is tested instead, which counts
+/// as a confirmation; without a passkey the backend's error is returned.
+pub async fn confirm_export(
+    app: &AppHandle,
+    provider: SharedAuthProvider,
+    window: Window,
+    reason: String,
+) -> Result<(), AppError> {
+    match confirm_presence(provider, window, reason).await {
+        Err(AppError::AuthUnavailable(_))
+            if crate::cached_status(app, false).await?.passkey_enabled =>
+        {
+            crate::passkeys::test_touchid().await?;
+            crate::state::unlock(app);
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+#[command]
+pub fn list_supported_passkey_types(provider: State<'_, SharedAuthProvider>) -> Vec<PasskeyType> {
+    provider.passkey_types()
+}
+
+/// Asks for a fresh confirmation before a reveal or copy, independent of the CLI.
+#[command]
+pub async fn confirm_user_presence(
+    provider: State<'_, SharedAuthProvider>,
+    window: Window,
+    reason: String,
+) -> Result<(), AppError> {
+    confirm_presence(provider.inner().clone(), window, reason).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let delays: Vec<u64> = (0..8).map(|n| backoff_delay(n).as_secs()).collect();
+        assert_eq!(delays, [0, 1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn refusals_lead_to_a_lockout() {
+        let attempts = AuthAttempts::default();
+        for failures in 1..MAX_FAILURES {
+            assert!(matches!(
+                attempts.settle(Err(AppError::AuthDenied)),
+                Err(AppError::AuthDenied)
+            ));
+            let wait = attempts.before_attempt().unwrap();
+            assert!(wait > Duration::ZERO && wait <= backoff_delay(failures));
+        }
+        assert!(matches!(
+            attempts.settle(Err(AppError::AuthDenied)),
+            Err(AppError::AuthLockedOut {
+                retry_after_secs: 300
+            })
+        ));
+        assert!(matches!(
+            attempts.before_attempt(),
+            Err(AppError::AuthLockedOut { .. })
+        ));
+    }
+
+    #[test]
+    fn wrong_passphrases_back_off_and_lock_out() {
+        let attempts = AuthAttempts::default();
+        let mut waits = Vec::new();
+        for _ in 1..MAX_FAILURES {
+            assert!(matches!(
+                attempts.settle(Err(AppError::WrongPassphrase)),
+                Err(AppError::WrongPassphrase)
+            ));
+            waits.push(attempts.before_attempt().unwrap());
+        }
+        assert!(
+            waits.windows(2).all(|pair| pair[0] < pair[1]),
+            "{:?}",
+            waits
+        );
+        assert!(matches!(
+            attempts.settle(Err(AppError::WrongPassphrase)),
+            Err(AppError::AuthLockedOut { .. })
+        ));
+        assert!(matches!(
+            attempts.before_attempt(),
+            Err(AppError::AuthLockedOut { .. })
+        ));
+    }
+
+    #[test]
+    fn cancelling_isnt_a_refusal() {
+        let attempts = AuthAttempts::default();
+        for _ in 0..MAX_FAILURES + 1 {
+            let error = attempts.settle(Err(AppError::AuthCancelled)).unwrap_err();
+            assert_eq!(error.code(), "AUTH_CANCELLED");
+        }
+        assert_eq!(attempts.before_attempt().unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn a_success_clears_the_count() {
+        let attempts = AuthAttempts::default();
+        for _ in 0..MAX_FAILURES - 1 {
+            let _ = attempts.settle(Err(AppError::AuthDenied));
+        }
+        attempts.settle(Ok(())).unwrap();
+        assert_eq!(attempts.before_attempt().unwrap(), Duration::ZERO);
+        assert!(matches!(
+            attempts.settle(Err(AppError::AuthDenied)),
+            Err(AppError::AuthDenied)
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn a_dismissed_polkit_dialog_is_a_cancel() {
+        use std::collections::HashMap;
+
+        let mut details = HashMap::new();
+        assert!(polkit::outcome(true, &details).is_ok());
+        assert!(matches!(
+            polkit::outcome(false, &details),
+            Err(AppError::AuthDenied)
+        ));
+        details.insert("polkit.dismissed".to_string(), "true".to_string());
+        assert!(matches!(
+            polkit::outcome(false, &details),
+            Err(AppError::AuthCancelled)
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::state::{self, VaultState};
+use crate::{cli, hygiene, settings};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use tauri::api::http::{Client, ClientBuilder, HttpRequestBuilder, ResponseType};
+use tauri::{command, AppHandle, State, Window};
+use zeroize::Zeroizing;
+
+/// Pwned Passwords range search: takes the first five hex digits of a SHA-1 and
+/// returns every known suffix under it with its breach count.
+const RANGE_API: &str = "https://api.pwnedpasswords.com/range/";
+const PREFIX_LEN: usize = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Gap kept between range requests, so a large vault doesn't hammer the API.
+const REQUEST_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Serialize)]
+pub struct BreachCheck {
+    pub name: String,
+    /// Times the value appears in known breaches; 0 when it doesn't. `None`
+    /// when the check failed, with `error` saying why.
+    pub breach_count: Option<u64>,
+    pub error: Option<AppError>,
+}
+
+/// The value's SHA-1 in upper-case hex, the form the range API uses.
+fn sha1_hex(value: &[u8]) -> Zeroizing<String> {
+    let mut digest = Sha1::digest(value);
+    let mut hex = Zeroizing::new(String::with_capacity(40));
+    for byte in digest.iter() {
+        let _ = write!(hex, "{:02X}", byte);
+    }
+    digest.as_mut_slice().fill(0);
+    hex
+}
+
+/// The count next to `suffix` in a range response (`SUFFIX:COUNT` lines), or 0.
+/// Padding entries the API adds have a count of 0 too.
+pub fn count_in_range(body: &str, suffix: &str) -> u64 {
+    body.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// The request for the suffixes under `prefix`. Only the prefix is sent;
+/// `Add-Padding` keeps the response size from hinting at which prefix was asked
+/// for.
+fn range_request(prefix: &str) -> Result<HttpRequestBuilder, tauri::api::Error> {
+    Ok(
+        HttpRequestBuilder::new("GET", format!("{}{}", RANGE_API, prefix))?
+            .header("Add-Padding", "true")?
+            .header("User-Agent", "VibeSafe")?
+            .timeout(REQUEST_TIMEOUT)
+            .response_type(ResponseType::Text),
+    )
+}
+
+async fn fetch_range(client: &Client, prefix: &str) -> Result<String, AppError> {
+    let failed =
+        |e: tauri::api::Error| AppError::Cli(format!("Failed to query the breach API: {}", e));
+    let request = range_request(prefix).map_err(failed)?;
+    let response = client.send(request).await.map_err(failed)?;
+    let status = response.status();
+    let body = response.bytes().await.map_err(failed)?.data;
+    if !status.is_success() {
+        return Err(AppError::Cli(format!(
+            "The breach API answered {}",
+            status.as_u16()
+        )));
+    }
+    String::from_utf8(body).map_err(|_| {
+        AppError::UnparseableCliOutput("The breach API's answer isn't text".to_string())
+    })
+}
+
+/// Looks each secret (all of them, or `names`) up in Have I Been Pwned's
+/// Pwned Passwords. Only the first five characters of each value's SHA-1 are
+/// sent; the rest of the hash is compared here, so neither values nor full
+/// hashes leave the machine. Off unless the `breach_check_enabled` setting is
+/// on, and needs a confirmation since every value is decrypted. A network
+/// failure is reported on that secret and the run goes on. Counts are added to
+/// the vault audit report.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn check_breaches(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    names: Option<Vec<String>>,
+) -> Result<Vec<BreachCheck>, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    if !settings::load_settings(&app)?.breach_check_enabled {
+        return Err(AppError::InvalidInput(
+            "Breach checking is off. Turn on breach_check_enabled in settings first.".to_string(),
+        ));
+    }
+    let names = match names {
+        Some(names) => {
+            for name in &names {
+                crate::names::check_secret_name(name)?;
+            }
+            names
+        }
+        None => crate::list_secrets()
+            .await?
+            .into_iter()
+            .map(|secret| secret.name)
+            .collect(),
+    };
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let reason = format!("Check {} secret(s) against known breaches", names.len());
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let client = ClientBuilder::new()
+        .max_redirections(0)
+        .build()
+        .map_err(|e| AppError::Cli(format!("Failed to start the HTTP client: {}", e)))?;
+    // Secrets sharing a prefix share one request.
+    let mut ranges: HashMap<String, String> = HashMap::new();
+    let mut last_request: Option<Instant> = None;
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        let hash = match cli::get_secret(&name).await {
+            Ok(stdout) => sha1_hex(&stdout[..]),
+            Err(error) => {
+                results.push(BreachCheck {
+                    name,
+                    breach_count: None,
+                    error: Some(error),
+                });
+                continue;
+            }
+        };
+        let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+        if !ranges.contains_key(prefix) {
+            if let Some(wait) =
+                last_request.and_then(|last| REQUEST_INTERVAL.checked_sub(last.elapsed()))
+            {
+                tokio::time::sleep(wait).await;
+            }
+            last_request = Some(Instant::now());
+            match fetch_range(&client, prefix).await {
+                Ok(body) => {
+                    ranges.insert(prefix.to_string(), body);
+                }
+                Err(error) => {
+                    results.push(BreachCheck {
+                        name,
+                        breach_count: None,
+                        error: Some(error),
+                    });
+                    continue;
+                }
+            }
+        }
+        results.push(BreachCheck {
+            breach_count: Some(count_in_range(&ranges[prefix], suffix)),
+            name,
+            error: None,
+        });
+    }
+
+    hygiene::record_breaches(
+        &app,
+        results
+            .iter()
+            .filter_map(|result| Some((result.name.clone(), result.breach_count?))),
+    );
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_five_hash_digits_are_sent() {
+        let hash = sha1_hex(b"password");
+        assert_eq!(*hash, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+        let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+
+        let request = range_request(prefix).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(
+            request.url.as_str(),
+            "https://api.pwnedpasswords.com/range/5BAA6"
+        );
+        assert!(request.query.is_none());
+        assert!(request.body.is_none());
+        assert!(!format!("{:?}", request).contains(suffix));
+    }
+
+    #[test]
+    fn counts_come_from_the_matching_suffix() {
+        let body = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                    1E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n\
+                    1E4C9B93F3F0682250B6CF8331B7EE68FD9:0\r\n";
+        assert_eq!(
+            count_in_range(body, "1E4C9B93F3F0682250B6CF8331B7EE68FD8"),
+            9545824
+        );
+        assert_eq!(
+            count_in_range(body, "1e4c9b93f3f0682250b6cf8331b7ee68fd8"),
+            9545824
+        );
+        // A padding entry and a suffix that isn't there both count as unbreached.
+        assert_eq!(
+            count_in_range(body, "1E4C9B93F3F0682250B6CF8331B7EE68FD9"),
+            0
+        );
+        assert_eq!(count_in_range(body, "FFFFF"), 0);
+        assert_eq!(count_in_range("", "1E4C9"), 0);
+    }
+}
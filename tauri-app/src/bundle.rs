@@ -0,0 +1,660 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::cache::StoreCache;
+use crate::error::AppError;
+use crate::hooks::{self, HookEvent};
+use crate::state::{self, VaultState};
+use crate::{cli, names};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{AeadCore, AeadInPlace, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Manager, State, Window};
+use zeroize::Zeroizing;
+
+const FORMAT: &str = "vibesafe-share-bundle";
+const FORMAT_VERSION: u32 = 1;
+const MAX_EXPIRY_HOURS: u32 = 30 * 24;
+const MIN_PASSPHRASE_CHARS: usize = 12;
+/// argon2id at 64 MiB, 3 passes, 1 lane: RFC 9106's second recommended setting.
+const KDF_ALGORITHM: &str = "argon2id";
+const KDF_MEMORY_KIB: u32 = 64 * 1024;
+const KDF_ITERATIONS: u32 = 3;
+const KDF_PARALLELISM: u32 = 1;
+/// A bundle asking for more than these is refused instead of derived.
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 10;
+const MAX_KDF_PARALLELISM: u32 = 16;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+/// Poly1305 tag appended to the ciphertext.
+const TAG_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+const AUDIT_REASON: &str = "Added to a share bundle";
+/// Returned with every bundle, for the UI to show as is.
+const EXPIRY_NOTE: &str = "The expiry is checked by VibeSafe, not enforced by the encryption. \
+                           Anyone with the file and the passphrase can still decrypt it with \
+                           other tools after it expires.";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Kdf {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+/// The file as written. Every field but `ciphertext` is authenticated as
+/// associated data, so editing the expiry breaks decryption.
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleFile {
+    format: String,
+    version: u32,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    kdf: Kdf,
+    /// SHA-256 of the second half of the derived key, which tells a wrong
+    /// passphrase apart from a damaged file.
+    key_check: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl BundleFile {
+    fn associated_data(&self) -> Vec<u8> {
+        [
+            self.format.clone(),
+            self.version.to_string(),
+            self.created_at.timestamp().to_string(),
+            self.expires_at.timestamp().to_string(),
+            self.kdf.algorithm.clone(),
+            self.kdf.memory_kib.to_string(),
+            self.kdf.iterations.to_string(),
+            self.kdf.parallelism.to_string(),
+            self.kdf.salt.clone(),
+            self.key_check.clone(),
+        ]
+        .join("\n")
+        .into_bytes()
+    }
+}
+
+/// The decrypted contents. The expiry is repeated inside the ciphertext.
+struct Payload {
+    expires_at: i64,
+    secrets: Vec<(String, Zeroizing<Vec<u8>>)>,
+}
+
+/// Lays the payload out as `expires_at` (i64), the entry count (u32), then
+/// each name (u16 length) and value (u32 length), all big-endian, in a buffer
+/// with room for the tag so encrypting in place never reallocates it.
+fn encode_payload(expires_at: i64, secrets: &[(String, Zeroizing<Vec<u8>>)]) -> Zeroizing<Vec<u8>> {
+    let size = 12
+        + secrets
+            .iter()
+            .map(|(name, value)| 6 + name.len() + value.len())
+            .sum::<usize>();
+    let mut out = Zeroizing::new(Vec::with_capacity(size + TAG_SIZE));
+    out.extend_from_slice(&expires_at.to_be_bytes());
+    out.extend_from_slice(&(secrets.len() as u32).to_be_bytes());
+    for (name, value) in secrets {
+        out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        out.extend_from_slice(value);
+    }
+    out
+}
+
+fn decode_payload(bytes: &[u8]) -> Result<Payload, AppError> {
+    let truncated = || AppError::CorruptShare("the contents are cut short".to_string());
+    let mut rest = bytes;
+    let mut take = |len: usize| -> Result<&[u8], AppError> {
+        if rest.len() < len {
+            return Err(truncated());
+        }
+        let (head, tail) = rest.split_at(len);
+        rest = tail;
+        Ok(head)
+    };
+
+    let mut expires_at = [0; 8];
+    expires_at.copy_from_slice(take(8)?);
+    let mut count = [0; 4];
+    count.copy_from_slice(take(4)?);
+    let mut secrets = Vec::new();
+    for _ in 0..u32::from_be_bytes(count) {
+        let mut len = [0; 2];
+        len.copy_from_slice(take(2)?);
+        let name = String::from_utf8(take(u16::from_be_bytes(len) as usize)?.to_vec())
+            .map_err(|_| AppError::CorruptShare("a secret name isn't text".to_string()))?;
+        let mut len = [0; 4];
+        len.copy_from_slice(take(4)?);
+        let value = Zeroizing::new(take(u32::from_be_bytes(len) as usize)?.to_vec());
+        secrets.push((name, value));
+    }
+    Ok(Payload {
+        expires_at: i64::from_be_bytes(expires_at),
+        secrets,
+    })
+}
+
+/// Runs argon2id off the async runtime. The first half of the output is the
+/// key; the second half is hashed into the key check.
+async fn derive_key(
+    passphrase: SecretString,
+    salt: Vec<u8>,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<(Zeroizing<[u8; KEY_LEN]>, String), AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let params = Params::new(memory_kib, iterations, parallelism, Some(2 * KEY_LEN))
+            .map_err(|e| AppError::CorruptShare(format!("the key settings are invalid: {}", e)))?;
+        let mut output = Zeroizing::new([0; 2 * KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.expose_secret().as_bytes(), &salt, &mut *output)
+            .map_err(|e| AppError::CorruptShare(format!("the key settings are invalid: {}", e)))?;
+        let mut key = Zeroizing::new([0; KEY_LEN]);
+        key.copy_from_slice(&output[..KEY_LEN]);
+        let check = Sha256::digest(&output[KEY_LEN..])
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok((key, check))
+    })
+    .await
+    .map_err(|e| AppError::Cli(format!("Key derivation failed: {}", e)))?
+}
+
+fn check_expiry(expires_at: DateTime<Utc>) -> Result<(), AppError> {
+    if Utc::now() > expires_at {
+        return Err(AppError::ShareExpired {
+            expired_at: expires_at.to_rfc3339(),
+        });
+    }
+    Ok(())
+}
+
+/// Reads and decrypts the bundle at `path`. An expired bundle is refused before
+/// the key is derived; the expiry inside the ciphertext has to agree.
+async fn open(path: &str, passphrase: SecretString) -> Result<(BundleFile, Payload), AppError> {
+    let path = PathBuf::from(path);
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(AppError::InvalidInput(format!(
+                "{} doesn't exist",
+                path.display()
+            )))
+        }
+        Err(e) => {
+            return Err(AppError::Cli(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let file: BundleFile = serde_json::from_slice(&contents)
+        .map_err(|_| AppError::CorruptShare("it isn't a VibeSafe share bundle".to_string()))?;
+    if file.format != FORMAT {
+        return Err(AppError::CorruptShare(
+            "it isn't a VibeSafe share bundle".to_string(),
+        ));
+    }
+    if file.version != FORMAT_VERSION {
+        return Err(AppError::CorruptShare(format!(
+            "format version {} isn't supported",
+            file.version
+        )));
+    }
+    check_expiry(file.expires_at)?;
+    if file.kdf.algorithm != KDF_ALGORITHM
+        || file.kdf.memory_kib > MAX_KDF_MEMORY_KIB
+        || file.kdf.iterations > MAX_KDF_ITERATIONS
+        || file.kdf.parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err(AppError::CorruptShare(
+            "the key settings are unsupported".to_string(),
+        ));
+    }
+
+    let corrupt = |what: &str| AppError::CorruptShare(format!("the {} isn't base64", what));
+    let salt = BASE64.decode(&file.kdf.salt).map_err(|_| corrupt("salt"))?;
+    let nonce = BASE64
+        .decode(&file.nonce)
+        .ok()
+        .filter(|nonce| nonce.len() == NONCE_SIZE)
+        .ok_or_else(|| corrupt("nonce"))?;
+    let ciphertext = BASE64
+        .decode(&file.ciphertext)
+        .map_err(|_| corrupt("ciphertext"))?;
+    let (key, check) = derive_key(
+        passphrase,
+        salt,
+        file.kdf.memory_kib,
+        file.kdf.iterations,
+        file.kdf.parallelism,
+    )
+    .await?;
+    if check != file.key_check {
+        return Err(AppError::WrongPassphrase);
+    }
+
+    let mut buffer = Zeroizing::new(ciphertext);
+    XChaCha20Poly1305::new(Key::from_slice(&*key))
+        .decrypt_in_place(
+            XNonce::from_slice(&nonce),
+            &file.associated_data(),
+            &mut *buffer,
+        )
+        .map_err(|_| {
+            AppError::CorruptShare(
+                "it fails authentication, so it was altered after it was written".to_string(),
+            )
+        })?;
+    let payload = decode_payload(&buffer)?;
+    if payload.expires_at != file.expires_at.timestamp() {
+        return Err(AppError::CorruptShare(
+            "the expiry inside doesn't match the file's".to_string(),
+        ));
+    }
+    Ok((file, payload))
+}
+
+/// Encrypts `secrets` into a bundle. `memory_kib` is only lowered by tests.
+async fn seal(
+    secrets: Vec<(String, Zeroizing<Vec<u8>>)>,
+    passphrase: SecretString,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    memory_kib: u32,
+) -> Result<BundleFile, AppError> {
+    let mut buffer = encode_payload(expires_at.timestamp(), &secrets);
+    drop(secrets);
+
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let (key, key_check) = derive_key(
+        passphrase,
+        salt.to_vec(),
+        memory_kib,
+        KDF_ITERATIONS,
+        KDF_PARALLELISM,
+    )
+    .await?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut file = BundleFile {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        created_at,
+        expires_at,
+        kdf: Kdf {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+            salt: BASE64.encode(salt),
+        },
+        key_check,
+        nonce: BASE64.encode(nonce),
+        ciphertext: String::new(),
+    };
+    XChaCha20Poly1305::new(Key::from_slice(&*key))
+        .encrypt_in_place(&nonce, &file.associated_data(), &mut *buffer)
+        .map_err(|_| AppError::Cli("Failed to encrypt the bundle".to_string()))?;
+    file.ciphertext = BASE64.encode(&*buffer);
+    Ok(file)
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleInfo {
+    pub path: PathBuf,
+    pub secrets: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// What the expiry does and doesn't guarantee.
+    pub expiry_note: &'static str,
+}
+
+/// Writes the listed secrets to `path` as one file encrypted with
+/// XChaCha20-Poly1305 under a key derived from `passphrase` by argon2id, for
+/// someone without VibeSafe. The expiry, `expires_in_hours` from now (at most
+/// 30 days), is stored inside the ciphertext and bound to the header; past it,
+/// `open_share_bundle` refuses the file. Needs a fresh confirmation; the access
+/// log gets each name.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn create_share_bundle(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    mut names: Vec<String>,
+    passphrase: String,
+    expires_in_hours: u32,
+    path: String,
+) -> Result<BundleInfo, AppError> {
+    let passphrase = SecretString::new(passphrase);
+    if names.is_empty() {
+        return Err(AppError::InvalidInput("No secrets to share".to_string()));
+    }
+    for name in &names {
+        names::check_secret_name(name)?;
+    }
+    if passphrase.expose_secret().chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(AppError::InvalidInput(format!(
+            "The passphrase needs at least {} characters",
+            MIN_PASSPHRASE_CHARS
+        )));
+    }
+    if expires_in_hours == 0 || expires_in_hours > MAX_EXPIRY_HOURS {
+        return Err(AppError::InvalidInput(format!(
+            "The expiry must be between 1 and {} hours",
+            MAX_EXPIRY_HOURS
+        )));
+    }
+    state::require(&app, &[VaultState::Ready]).await?;
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    let path = PathBuf::from(path);
+    let reason = format!("Put {} into {}", names.join(", "), path.display());
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let mut secrets = Vec::with_capacity(names.len());
+    for name in &names {
+        let stdout = cli::get_secret(name).await?;
+        let value = stdout;
+        secrets.push((name.clone(), value));
+    }
+    let created_at = Utc::now().trunc_subsecs(0);
+    let expires_at = created_at + Duration::hours(i64::from(expires_in_hours));
+    let file = seal(secrets, passphrase, created_at, expires_at, KDF_MEMORY_KIB).await?;
+
+    let contents = serde_json::to_vec_pretty(&file)
+        .map_err(|e| AppError::Cli(format!("Failed to serialize the bundle: {}", e)))?;
+    atomic_write(&path, &contents)
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))?;
+    for name in &names {
+        audit::record(&app, name, AccessAction::Read, Some(AUDIT_REASON))?;
+    }
+
+    Ok(BundleInfo {
+        path,
+        secrets: names,
+        created_at,
+        expires_at,
+        expiry_note: EXPIRY_NOTE,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleEntry {
+    pub name: String,
+    /// A secret with this name is already in the vault.
+    pub exists: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleContents {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub expiry_note: &'static str,
+    pub secrets: Vec<BundleEntry>,
+}
+
+/// Decrypts a bundle from `create_share_bundle` and lists what it holds, for
+/// choosing what to import. No values are returned. Fails with
+/// `SHARE_EXPIRED` past the expiry, `WRONG_PASSPHRASE`, or `CORRUPT_SHARE` when
+/// the file was altered.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn open_share_bundle(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<BundleContents, AppError> {
+    let passphrase = SecretString::new(passphrase);
+    state::require(&app, &[VaultState::Ready]).await?;
+    let (file, payload) = open(&path, passphrase).await?;
+    let existing: HashSet<String> = crate::cached_secrets(&app, false)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    Ok(BundleContents {
+        created_at: file.created_at,
+        expires_at: file.expires_at,
+        expiry_note: EXPIRY_NOTE,
+        secrets: payload
+            .secrets
+            .into_iter()
+            .map(|(name, _)| BundleEntry {
+                exists: existing.contains(&name),
+                name,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleImportOutcome {
+    Added,
+    /// The vault already had the same value under that name.
+    Unchanged,
+    /// The vault has a different value under that name; it was left alone.
+    Conflict,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleImport {
+    pub name: String,
+    pub stored_as: String,
+    pub outcome: BundleImportOutcome,
+    pub error: Option<AppError>,
+}
+
+async fn import_one(
+    name: &str,
+    value: &[u8],
+    exists: bool,
+) -> Result<BundleImportOutcome, AppError> {
+    names::check_secret_name(name)?;
+    if exists {
+        let stored = cli::get_secret(name).await?;
+        return Ok(if &stored[..] == value {
+            BundleImportOutcome::Unchanged
+        } else {
+            BundleImportOutcome::Conflict
+        });
+    }
+    if value.contains(&b'\n') {
+        return Err(AppError::InvalidInput(
+            "Multi-line values can't be passed to the CLI".to_string(),
+        ));
+    }
+    let mut input = Zeroizing::new(Vec::with_capacity(value.len() + 1));
+    input.extend_from_slice(value);
+    input.push(b'\n');
+    cli::add_with_stdin(name, &input).await?;
+    Ok(BundleImportOutcome::Added)
+}
+
+/// Adds secrets from a bundle: all of them under their own names, or only the
+/// ones in `names`, which maps a bundle name to the name to store it as. A name
+/// already in the vault is never overwritten; it comes back as `unchanged` or
+/// `conflict`, and can be imported again under another name.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn import_share_bundle(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+    names: Option<HashMap<String, String>>,
+) -> Result<Vec<BundleImport>, AppError> {
+    let passphrase = SecretString::new(passphrase);
+    state::require(&app, &[VaultState::Ready]).await?;
+    let (_, payload) = open(&path, passphrase).await?;
+    let existing: HashSet<String> = crate::cached_secrets(&app, true)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+
+    let mut results = Vec::new();
+    for (name, value) in &payload.secrets {
+        let stored_as = match &names {
+            Some(names) => match names.get(name) {
+                Some(stored_as) => stored_as.clone(),
+                None => continue,
+            },
+            None => name.clone(),
+        };
+        let (outcome, error) =
+            match import_one(&stored_as, value, existing.contains(&stored_as)).await {
+                Ok(outcome) => (outcome, None),
+                Err(error) => (BundleImportOutcome::Failed, Some(error)),
+            };
+        if let BundleImportOutcome::Added = outcome {
+            hooks::fire(&app, HookEvent::Added, &stored_as, "import_share_bundle");
+        }
+        results.push(BundleImport {
+            name: name.clone(),
+            stored_as,
+            outcome,
+            error,
+        });
+    }
+    app.state::<StoreCache>().invalidate();
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery";
+
+    /// Writes a bundle holding `API_KEY` with a cheap KDF and returns its path.
+    async fn write_bundle(dir: &tempfile::TempDir, expires_at: DateTime<Utc>) -> String {
+        let secrets = vec![(
+            "API_KEY".to_string(),
+            Zeroizing::new(b"sk-\xff\x00".to_vec()),
+        )];
+        let created_at = (expires_at - Duration::hours(1)).trunc_subsecs(0);
+        let file = seal(
+            secrets,
+            SecretString::new(PASSPHRASE.to_string()),
+            created_at,
+            expires_at.trunc_subsecs(0),
+            1024,
+        )
+        .await
+        .unwrap();
+        let path = dir.path().join("share.vsbundle");
+        fs::write(&path, serde_json::to_vec(&file).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    async fn open_with(path: &str, passphrase: &str) -> Result<Payload, AppError> {
+        open(path, SecretString::new(passphrase.to_string()))
+            .await
+            .map(|(_, payload)| payload)
+    }
+
+    /// Rewrites the bundle at `path` through `edit`.
+    fn edit(path: &str, edit: impl FnOnce(&mut BundleFile)) {
+        let mut file: BundleFile = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+        edit(&mut file);
+        fs::write(path, serde_json::to_vec(&file).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bundles_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(&dir, Utc::now() + Duration::hours(1)).await;
+        let payload = open_with(&path, PASSPHRASE).await.unwrap();
+        assert_eq!(payload.secrets.len(), 1);
+        assert_eq!(payload.secrets[0].0, "API_KEY");
+        assert_eq!(&payload.secrets[0].1[..], b"sk-\xff\x00");
+    }
+
+    #[tokio::test]
+    async fn expired_bundles_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(&dir, Utc::now() - Duration::minutes(1)).await;
+        assert!(matches!(
+            open_with(&path, PASSPHRASE).await,
+            Err(AppError::ShareExpired { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn a_wrong_passphrase_is_told_apart_from_damage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(&dir, Utc::now() + Duration::hours(1)).await;
+        assert!(matches!(
+            open_with(&path, "incorrect horse battery").await,
+            Err(AppError::WrongPassphrase)
+        ));
+    }
+
+    #[tokio::test]
+    async fn tampering_fails_authentication() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(&dir, Utc::now() + Duration::hours(1)).await;
+        edit(&path, |file| {
+            let mut ciphertext = BASE64.decode(&file.ciphertext).unwrap();
+            ciphertext[0] ^= 1;
+            file.ciphertext = BASE64.encode(ciphertext);
+        });
+        assert!(matches!(
+            open_with(&path, PASSPHRASE).await,
+            Err(AppError::CorruptShare(_))
+        ));
+
+        let path = write_bundle(&dir, Utc::now() + Duration::hours(1)).await;
+        edit(&path, |file| file.expires_at += Duration::days(30));
+        assert!(matches!(
+            open_with(&path, PASSPHRASE).await,
+            Err(AppError::CorruptShare(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn costly_key_settings_are_refused_before_deriving() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(&dir, Utc::now() + Duration::hours(1)).await;
+        let settings: [fn(&mut Kdf); 3] = [
+            |kdf| kdf.memory_kib = MAX_KDF_MEMORY_KIB + 1,
+            |kdf| kdf.iterations = MAX_KDF_ITERATIONS + 1,
+            |kdf| kdf.parallelism = MAX_KDF_PARALLELISM + 1,
+        ];
+        for setting in settings {
+            let original = fs::read(&path).unwrap();
+            edit(&path, |file| setting(&mut file.kdf));
+            assert!(matches!(
+                open_with(&path, PASSPHRASE).await,
+                Err(AppError::CorruptShare(_))
+            ));
+            fs::write(&path, original).unwrap();
+        }
+    }
+}
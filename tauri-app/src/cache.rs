@@ -0,0 +1,230 @@
+use crate::error::AppError;
+use crate::{SecretInfo, VibeSafeStatus};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Age under which a cached value is served without running the CLI.
+const FRESH_FOR: Duration = Duration::from_secs(5);
+/// Age under which a stale value is still served while a background refresh runs.
+const USABLE_FOR: Duration = Duration::from_secs(60);
+
+pub enum Lookup<T> {
+    Fresh(T),
+    Stale(T),
+    Miss,
+}
+
+/// One cached CLI result. `invalidate` bumps a generation counter so a fetch that
+/// started before a mutation can't store its outdated result afterwards.
+pub struct TtlCache<T> {
+    entry: Mutex<Option<(Instant, T)>>,
+    generation: AtomicU64,
+    refreshing: AtomicBool,
+}
+
+impl<T> Default for TtlCache<T> {
+    fn default() -> Self {
+        Self {
+            entry: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            refreshing: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<T: Clone> TtlCache<T> {
+    pub fn lookup(&self) -> Lookup<T> {
+        let entry = self.entry.lock().unwrap_or_else(PoisonError::into_inner);
+        match &*entry {
+            Some((stored_at, value)) if stored_at.elapsed() < FRESH_FOR => {
+                Lookup::Fresh(value.clone())
+            }
+            Some((stored_at, value)) if stored_at.elapsed() < USABLE_FOR => {
+                Lookup::Stale(value.clone())
+            }
+            _ => Lookup::Miss,
+        }
+    }
+
+    /// True when there is nothing usable to serve, not even a stale value.
+    pub fn is_cold(&self) -> bool {
+        match &*self.entry.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some((stored_at, _)) => stored_at.elapsed() >= USABLE_FOR,
+            None => true,
+        }
+    }
+
+    /// Read before fetching and passed back to `store`.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn store(&self, generation: u64, value: T) {
+        let mut entry = self.entry.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.generation.load(Ordering::SeqCst) == generation {
+            *entry = Some((Instant::now(), value));
+        }
+    }
+
+    pub fn invalidate(&self) {
+        let mut entry = self.entry.lock().unwrap_or_else(PoisonError::into_inner);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *entry = None;
+    }
+
+    /// Returns false when a background refresh is already running.
+    fn try_begin_refresh(&self) -> bool {
+        !self.refreshing.swap(true, Ordering::SeqCst)
+    }
+
+    fn end_refresh(&self) {
+        self.refreshing.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Last parsed status and secret list, kept in managed state so frontend polls
+/// don't spawn the CLI (and a Touch ID prompt) every time.
+#[derive(Default)]
+pub struct StoreCache {
+    pub status: TtlCache<VibeSafeStatus>,
+    pub secrets: TtlCache<Vec<SecretInfo>>,
+}
+
+impl StoreCache {
+    /// Called by every command that changes the store.
+    pub fn invalidate(&self) {
+        self.status.invalidate();
+        self.secrets.invalidate();
+    }
+}
+
+/// Serves the value `select` picks from the cache when it is fresh. A stale value
+/// is served as well, with a refresh started in the background; otherwise, or
+/// with `force_refresh`, `fetch` runs and its result is cached.
+pub async fn cached<T, Fut>(
+    app: &AppHandle,
+    select: fn(&StoreCache) -> &TtlCache<T>,
+    force_refresh: bool,
+    fetch: impl Fn() -> Fut + Send + 'static,
+) -> Result<T, AppError>
+where
+    T: Clone + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+{
+    let state = app.state::<StoreCache>();
+    let cache = select(&state);
+
+    if !force_refresh {
+        match cache.lookup() {
+            Lookup::Fresh(value) => return Ok(value),
+            Lookup::Stale(value) => {
+                if cache.try_begin_refresh() {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let state = app.state::<StoreCache>();
+                        let cache = select(&state);
+                        let generation = cache.generation();
+                        if let Ok(value) = fetch().await {
+                            cache.store(generation, value);
+                        }
+                        cache.end_refresh();
+                    });
+                }
+                return Ok(value);
+            }
+            Lookup::Miss => {}
+        }
+    }
+
+    let generation = cache.generation();
+    let value = fetch().await?;
+    cache.store(generation, value.clone());
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(cache: &TtlCache<u32>) -> Option<u32> {
+        match cache.lookup() {
+            Lookup::Fresh(value) => Some(value),
+            Lookup::Stale(_) | Lookup::Miss => None,
+        }
+    }
+
+    /// Backdates the entry by `age`.
+    fn age(cache: &TtlCache<u32>, age: Duration) {
+        let mut entry = cache.entry.lock().unwrap();
+        if let Some((stored_at, _)) = entry.as_mut() {
+            *stored_at = Instant::now().checked_sub(age).unwrap();
+        }
+    }
+
+    #[test]
+    fn stored_values_are_served_until_invalidated() {
+        let cache = TtlCache::default();
+        assert!(cache.is_cold());
+        cache.store(cache.generation(), 7);
+        assert_eq!(value(&cache), Some(7));
+        assert!(!cache.is_cold());
+
+        cache.invalidate();
+        assert!(matches!(cache.lookup(), Lookup::Miss));
+        assert!(cache.is_cold());
+    }
+
+    #[test]
+    fn fetches_that_started_before_an_invalidation_are_dropped() {
+        let cache = TtlCache::default();
+        let before = cache.generation();
+        cache.invalidate();
+        cache.store(before, 1);
+        assert!(matches!(cache.lookup(), Lookup::Miss));
+
+        cache.store(cache.generation(), 2);
+        assert_eq!(value(&cache), Some(2));
+    }
+
+    #[test]
+    fn values_go_stale_then_unusable() {
+        let cache = TtlCache::default();
+        cache.store(cache.generation(), 3);
+        age(&cache, FRESH_FOR);
+        assert!(matches!(cache.lookup(), Lookup::Stale(3)));
+        assert!(!cache.is_cold());
+        age(&cache, USABLE_FOR);
+        assert!(matches!(cache.lookup(), Lookup::Miss));
+        assert!(cache.is_cold());
+    }
+
+    #[test]
+    fn one_background_refresh_at_a_time() {
+        let cache = TtlCache::<u32>::default();
+        assert!(cache.try_begin_refresh());
+        assert!(!cache.try_begin_refresh());
+        cache.end_refresh();
+        assert!(cache.try_begin_refresh());
+    }
+
+    #[test]
+    fn mutations_invalidate_status_and_list() {
+        let cache = StoreCache::default();
+        let status = serde_json::from_value::<VibeSafeStatus>(serde_json::json!({
+            "initialized": true,
+            "key_exists": true,
+            "passkey_enabled": false,
+            "secrets_count": 0,
+            "claude_integration": false,
+        }))
+        .unwrap();
+        cache.status.store(cache.status.generation(), status);
+        cache.secrets.store(cache.secrets.generation(), Vec::new());
+        cache.invalidate();
+        assert!(cache.status.is_cold());
+        assert!(cache.secrets.is_cold());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::error::AppError;
+use crate::state::{self, VaultState};
+use crate::{cli, names};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle};
+
+/// Claude Desktop's config, in its folder under the platform config directory.
+const CONFIG_FILE: &str = "claude_desktop_config.json";
+/// Key of our entry under `mcpServers`.
+const SERVER_NAME: &str = "vibesafe";
+/// Comma-separated names the server may hand out, passed in the entry's `env`.
+const ALLOWED_ENV: &str = "VIBESAFE_MCP_ALLOWED";
+const AUDIT_REASON: &str = "Claude integration";
+
+fn config_path() -> Result<PathBuf, AppError> {
+    dirs::config_dir()
+        .map(|dir| dir.join("Claude").join(CONFIG_FILE))
+        .ok_or(AppError::AppDirUnavailable("Claude config"))
+}
+
+/// The config as a JSON object; empty when the file doesn't exist yet. A file
+/// that isn't a JSON object is an error rather than something to overwrite.
+fn read_config(path: &Path) -> Result<Map<String, Value>, AppError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(e) => {
+            return Err(AppError::Cli(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(Value::Object(config)) => Ok(config),
+        _ => Err(AppError::InvalidInput(format!(
+            "{} isn't a valid Claude config; fix or remove it first",
+            path.display()
+        ))),
+    }
+}
+
+fn write_config(path: &Path, config: Map<String, Value>) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let contents = serde_json::to_vec_pretty(&Value::Object(config))
+        .map_err(|e| AppError::Cli(format!("Failed to serialize the Claude config: {}", e)))?;
+    atomic_write(path, &contents)
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+fn our_entry(config: &Map<String, Value>) -> Option<&Value> {
+    config.get("mcpServers")?.get(SERVER_NAME)
+}
+
+fn exposed_secrets(entry: Option<&Value>) -> BTreeSet<String> {
+    entry
+        .and_then(|entry| entry.get("env")?.get(ALLOWED_ENV)?.as_str())
+        .map(|list| {
+            list.split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Logs names that became (un)available to Claude, so exposure shows up next to
+/// reads and copies.
+fn record_changes(
+    app: &AppHandle,
+    before: &BTreeSet<String>,
+    after: &BTreeSet<String>,
+) -> Result<(), AppError> {
+    for name in after.difference(before) {
+        audit::record(app, name, AccessAction::Expose, Some(AUDIT_REASON))?;
+    }
+    for name in before.difference(after) {
+        audit::record(app, name, AccessAction::Unexpose, Some(AUDIT_REASON))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClaudeIntegration {
+    /// Our MCP server entry is present in the config.
+    pub enabled: bool,
+    pub exposed_secrets: Vec<String>,
+    pub config_path: PathBuf,
+}
+
+/// Reads Claude Desktop's config; nothing is decrypted.
+#[command]
+pub fn get_claude_integration_status() -> Result<ClaudeIntegration, AppError> {
+    let path = config_path()?;
+    let config = read_config(&path)?;
+    let entry = our_entry(&config);
+    Ok(ClaudeIntegration {
+        enabled: entry.is_some(),
+        exposed_secrets: exposed_secrets(entry).into_iter().collect(),
+        config_path: path,
+    })
+}
+
+/// Adds or updates our MCP server entry so Claude can read `allowed_secrets`.
+/// Other servers and settings in the file are kept as they are. Names added to or
+/// dropped from the list are written to the access log.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn enable_claude_integration(
+    app: AppHandle,
+    allowed_secrets: Vec<String>,
+) -> Result<ClaudeIntegration, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let existing: HashSet<String> = crate::cached_secrets(&app, false)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    for name in &allowed_secrets {
+        names::check_secret_name(name)?;
+        if !existing.contains(name) {
+            return Err(AppError::InvalidInput(format!("No secret named {}", name)));
+        }
+    }
+    let cli = cli::locate_cli()?;
+
+    let path = config_path()?;
+    let mut config = read_config(&path)?;
+    let before = exposed_secrets(our_entry(&config));
+    let after: BTreeSet<String> = allowed_secrets.into_iter().collect();
+
+    let servers = config
+        .entry("mcpServers")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let servers = match servers {
+        Value::Object(servers) => servers,
+        _ => {
+            return Err(AppError::InvalidInput(format!(
+                "mcpServers in {} isn't an object",
+                path.display()
+            )))
+        }
+    };
+    servers.insert(
+        SERVER_NAME.to_string(),
+        json!({
+            "command": cli.path,
+            "args": ["mcp"],
+            "env": { ALLOWED_ENV: after.iter().cloned().collect::<Vec<_>>().join(",") },
+        }),
+    );
+
+    record_changes(&app, &before, &after)?;
+    write_config(&path, config)?;
+    Ok(ClaudeIntegration {
+        enabled: true,
+        exposed_secrets: after.into_iter().collect(),
+        config_path: path,
+    })
+}
+
+/// Removes our MCP server entry, leaving the rest of the config alone.
+#[command]
+pub fn disable_claude_integration(app: AppHandle) -> Result<(), AppError> {
+    let path = config_path()?;
+    let mut config = read_config(&path)?;
+    let before = exposed_secrets(our_entry(&config));
+    let removed = config
+        .get_mut("mcpServers")
+        .and_then(Value::as_object_mut)
+        .and_then(|servers| servers.remove(SERVER_NAME))
+        .is_some();
+    if !removed {
+        return Ok(());
+    }
+
+    record_changes(&app, &before, &BTreeSet::new())?;
+    write_config(&path, config)
+}
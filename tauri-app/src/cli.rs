@@ -0,0 +1,1176 @@
+use crate::cloud_sync;
+use crate::error::AppError;
+use crate::paths;
+use crate::settings::AppSettings;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+use zeroize::Zeroizing;
+
+const CLI_NAME: &str = "vibesafe";
+
+/// Stderr markers the CLI prints when no key pair has been created yet.
+const NOT_INITIALIZED_MARKERS: &[&str] = &["No key pair found", "Private key file not found"];
+/// Stderr markers for a Touch ID / passkey prompt that was dismissed or rejected.
+const AUTH_CANCELLED_MARKER: &str = "Authentication cancelled";
+const AUTH_FAILED_MARKER: &str = "Authentication failed";
+/// End of the error cryptography raises when the key's passphrase is wrong
+/// ("Bad decrypt. Incorrect password?").
+const WRONG_PASSPHRASE_MARKER: &str = "Incorrect password";
+/// Output of a prompt the CLI hit with no terminal attached. click prints the
+/// question and "Aborted!" when a confirm reads EOF; getpass fails with EOFError.
+const PROMPT_MARKERS: &[&str] = &[
+    "[y/N]",
+    "[Y/n]",
+    "Aborted!",
+    "Enter secret value for",
+    "Enter passphrase",
+    "EOFError",
+];
+
+/// Lines of the text `status` output printed by CLIs without `--json`, as
+/// (set, unset) pairs. Output with neither line of a pair isn't what the app
+/// knows, e.g. a translated CLI, and is an error rather than read as unset.
+const KEY_PAIR_MARKERS: (&str, &str) = ("Key pair initialized", "No key pair found");
+const PASSKEY_MARKERS: (&str, &str) = (
+    "Passkey protection: ENABLED",
+    "Passkey protection: DISABLED",
+);
+const CLAUDE_MARKERS: (&str, &str) = (
+    "Claude Code integration: CONFIGURED",
+    "Claude Code integration: NOT CONFIGURED",
+);
+const SECRETS_COUNT_MARKER: &str = "Secrets stored:";
+
+/// Pins the CLI to untranslated messages so the markers above always match.
+/// Python still writes UTF-8, which the CLI's status symbols need.
+const LOCALE_ENV: &[(&str, &str)] = &[
+    ("LC_ALL", "C"),
+    ("LANG", "C"),
+    ("PYTHONIOENCODING", "utf-8"),
+];
+
+/// The only variables the CLI inherits from the app; the rest of the GUI's
+/// environment (which may hold tokens or keys) is cleared. The locale comes
+/// from `LOCALE_ENV`.
+#[cfg(not(windows))]
+const PASSTHROUGH_ENV: &[&str] = &["PATH", "HOME", "VIBESAFE_HOME"];
+/// Python won't start without `SYSTEMROOT`, and `Path.home()` and temp files
+/// need the profile and temp directories.
+#[cfg(windows)]
+const PASSTHROUGH_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "VIBESAFE_HOME",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "HOMEDRIVE",
+    "HOMEPATH",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "TEMP",
+    "TMP",
+];
+
+/// CLI verbs whose second argument is a subcommand rather than user data.
+const SUBCOMMAND_GROUPS: &[&str] = &["passkey", "claude"];
+pub const REDACTED: &str = "<redacted>";
+const CLI_OUTPUT_EVENT: &str = "cli:output";
+/// Words at least this long made only of base64/hex characters are masked in
+/// streamed output; they could be key material or an encoded value.
+const MIN_ENCODED_WORD: usize = 32;
+
+/// Details of the most recent failed CLI run, for the debug panel.
+#[derive(Debug, Clone, Serialize)]
+pub struct CliFailure {
+    /// `None` when the CLI couldn't be started or was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+    pub binary_path: PathBuf,
+    /// Verbs, subcommands and flags only; names and values are replaced.
+    pub args_redacted: Vec<String>,
+}
+
+static LAST_FAILURE: Lazy<Mutex<Option<CliFailure>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn last_failure() -> Option<CliFailure> {
+    LAST_FAILURE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+fn redact_args(args: &[&str]) -> Vec<String> {
+    args.iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let is_subcommand = i == 1 && SUBCOMMAND_GROUPS.contains(&args[0]);
+            if i == 0 || is_subcommand || arg.starts_with('-') {
+                arg.to_string()
+            } else {
+                REDACTED.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Stands for one of the caller's arguments in `ACTIONS`.
+const ARG: &str = "{}";
+
+/// The argument list each app action runs the CLI with, `ARG` marking where the
+/// caller's arguments go in order. Commands build their arguments here so
+/// `preview` shows exactly what they run.
+const ACTIONS: &[(&str, &[&str])] = &[
+    ("status", &["status", "--json"]),
+    ("list", &["list", "--json"]),
+    ("get", &["get", ARG]),
+    // The value goes to stdin, answering the CLI's value prompt.
+    ("add", &["add", ARG]),
+    // The value goes to stdin, for CLIs with an explicit flag for it.
+    ("add_stdin", &["add", ARG, "--stdin"]),
+    ("delete", &["delete", ARG, "--yes"]),
+    ("init", &["init"]),
+    ("enable_passkey", &["passkey", "enable", "--type", ARG]),
+    ("list_passkeys", &["passkey", "list", "--json"]),
+    ("remove_passkey", &["passkey", "remove", ARG, "--yes"]),
+    ("test_passkey", &["passkey", "test"]),
+];
+
+/// The CLI arguments for `action`, with `args` filled in.
+pub fn action_args<'a>(action: &str, args: &[&'a str]) -> Result<Vec<&'a str>, AppError> {
+    let template = ACTIONS
+        .iter()
+        .find(|(name, _)| *name == action)
+        .map(|(_, template)| *template)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown action '{}'", action)))?;
+    let expected = template.iter().filter(|word| **word == ARG).count();
+    if args.len() != expected {
+        return Err(AppError::InvalidInput(format!(
+            "{} takes {} argument(s), got {}",
+            action,
+            expected,
+            args.len()
+        )));
+    }
+    let mut args = args.iter();
+    Ok(template
+        .iter()
+        .map(|word| match *word {
+            ARG => args.next().copied().unwrap_or_default(),
+            word => word,
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandPreview {
+    pub binary_path: PathBuf,
+    pub source: CliSource,
+    /// Verbs, subcommands and flags only; names and values are replaced.
+    pub args_redacted: Vec<String>,
+    /// The whole thing on one line, quoted for a POSIX shell, for bug reports.
+    pub command_line: String,
+}
+
+fn shell_quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=".contains(c))
+    {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// What `action` would run: the binary `locate_cli` picks and the arguments from
+/// `action_args`, redacted like `last_failure`.
+pub fn preview(action: &str, args: &[&str]) -> Result<CommandPreview, AppError> {
+    let location = locate_cli()?;
+    let args_redacted = redact_args(&action_args(action, args)?);
+    let command_line = std::iter::once(location.path.to_string_lossy().into_owned())
+        .chain(args_redacted.iter().cloned())
+        .map(|word| shell_quote(&word))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(CommandPreview {
+        binary_path: location.path,
+        source: location.source,
+        args_redacted,
+        command_line,
+    })
+}
+
+fn record_failure(binary: &Path, args: &[&str], exit_code: Option<i32>, stderr: String) {
+    *LAST_FAILURE.lock().unwrap_or_else(PoisonError::into_inner) = Some(CliFailure {
+        exit_code,
+        stderr,
+        binary_path: binary.to_path_buf(),
+        args_redacted: redact_args(args),
+    });
+}
+
+/// Location of the bundled CLI. Tauri places sidecars next to the app executable.
+pub fn sidecar_path() -> Result<PathBuf, String> {
+    let exe = tauri::utils::platform::current_exe()
+        .map_err(|e| format!("Failed to locate app executable: {}", e))?;
+    let dir = exe
+        .parent()
+        .ok_or_else(|| "Failed to locate app directory".to_string())?;
+    Ok(dir.join(format!("{}{}", CLI_NAME, env::consts::EXE_SUFFIX)))
+}
+
+/// First `vibesafe` executable on PATH.
+fn system_path() -> Option<PathBuf> {
+    let name = format!("{}{}", CLI_NAME, env::consts::EXE_SUFFIX);
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .map(|dir| dir.join(&name))
+        .find(|path| path.is_file())
+}
+
+/// Mirrors the `prefer_bundled_cli` setting; see `apply_settings`.
+static PREFER_BUNDLED: AtomicBool = AtomicBool::new(true);
+
+/// Picks up the settings the CLI wrapper depends on. Called at startup and
+/// whenever settings are saved.
+pub fn apply_settings(settings: &AppSettings) {
+    PREFER_BUNDLED.store(settings.prefer_bundled_cli, Ordering::SeqCst);
+}
+
+/// A fake CLI installed by a test; see `test_support::fake_cli`.
+#[cfg(test)]
+static TEST_CLI: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+#[cfg(test)]
+pub fn set_test_cli(path: Option<PathBuf>) {
+    *TEST_CLI.lock().unwrap_or_else(PoisonError::into_inner) = path;
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CliSource {
+    Bundled,
+    System,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CliLocation {
+    pub path: PathBuf,
+    pub source: CliSource,
+}
+
+/// Finds the CLI to run. With `prefer_bundled_cli` (the default) the sidecar is
+/// used and PATH is only a fallback when it's missing; without it PATH comes first.
+pub fn locate_cli() -> Result<CliLocation, AppError> {
+    #[cfg(test)]
+    if let Some(path) = TEST_CLI
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+    {
+        return Ok(CliLocation {
+            path,
+            source: CliSource::System,
+        });
+    }
+    let bundled = sidecar_path()
+        .ok()
+        .filter(|path| path.is_file())
+        .map(|path| CliLocation {
+            path,
+            source: CliSource::Bundled,
+        });
+    let system = || {
+        system_path().map(|path| CliLocation {
+            path,
+            source: CliSource::System,
+        })
+    };
+
+    let found = if PREFER_BUNDLED.load(Ordering::SeqCst) {
+        bundled.or_else(system)
+    } else {
+        system().or(bundled)
+    };
+    found.ok_or(AppError::CliNotFound)
+}
+
+/// Whether `args` make the CLI rewrite store files.
+fn writes_store(args: &[&str]) -> bool {
+    matches!(
+        args,
+        ["add", ..] | ["delete", ..] | ["rotate", ..] | ["passkey", "enable" | "remove", ..]
+    )
+}
+
+/// Stops a write before the CLI trips over a store file cloud sync hasn't
+/// downloaded.
+fn check_store_writable(args: &[&str]) -> Result<(), AppError> {
+    if writes_store(args) {
+        cloud_sync::require_downloaded()?;
+    }
+    Ok(())
+}
+
+/// Runs the CLI with `args` and returns its stdout. A non-zero exit is
+/// reported with the CLI's stderr.
+pub async fn run_vibesafe(args: &[&str]) -> Result<Vec<u8>, AppError> {
+    check_store_writable(args)?;
+    let binary = locate_cli()?.path;
+    let output = command(&binary, args).output().await;
+    check_output(&binary, args, output)
+}
+
+/// Like `run_vibesafe`, but writes `input` to the CLI's stdin. Used for values,
+/// which must never appear in the argument list.
+pub async fn run_vibesafe_with_stdin(args: &[&str], input: &[u8]) -> Result<Vec<u8>, AppError> {
+    check_store_writable(args)?;
+    let binary = locate_cli()?.path;
+    let mut child = command(&binary, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_failure(&binary, args, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Err(error) = stdin.write_all(input).await {
+            // The CLI may have read part of the input; stop it before it acts on that,
+            // then reap it so its outcome can be reported.
+            drop(stdin);
+            let _ = child.start_kill();
+            let outcome = child.wait_with_output().await;
+            return Err(stdin_write_failure(&binary, args, error, outcome));
+        }
+        // Dropping stdin closes it so the CLI sees the end of the input.
+    }
+    check_output(&binary, args, child.wait_with_output().await)
+}
+
+fn stdin_write_failure(
+    binary: &Path,
+    args: &[&str],
+    error: io::Error,
+    outcome: io::Result<Output>,
+) -> AppError {
+    let (exit_code, stderr) = match outcome {
+        Ok(output) => (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ),
+        Err(e) => (None, e.to_string()),
+    };
+    record_failure(binary, args, exit_code, stderr);
+
+    let verb = args.first().copied().unwrap_or_default();
+    let outcome = match exit_code {
+        // It read a complete line before the pipe broke, so the change may have happened.
+        Some(0) => "the CLI still reported success".to_string(),
+        Some(code) => format!("the CLI exited with status {}", code),
+        None => "the CLI was stopped".to_string(),
+    };
+    AppError::StdinWriteFailed {
+        exit_code,
+        message: format!(
+            "Failed to send input to vibesafe {}: {}; {}",
+            verb, error, outcome
+        ),
+    }
+}
+
+fn std_command(binary: &Path, args: &[&str]) -> std::process::Command {
+    let mut command = std::process::Command::new(binary);
+    command
+        .args(args)
+        .env_clear()
+        .envs(
+            PASSTHROUGH_ENV
+                .iter()
+                .filter_map(|name| Some((name, env::var_os(name)?))),
+        )
+        .envs(LOCALE_ENV.iter().copied());
+    if let Some(dir) = paths::store_override() {
+        command.env("VIBESAFE_HOME", dir);
+    }
+    command
+}
+
+/// Async counterpart of `std_command`. The process is killed if its handle is
+/// dropped before it exits (an early return or a cancelled future), and tokio
+/// reaps it, so no run can leave a CLI behind.
+fn command(binary: &Path, args: &[&str]) -> Command {
+    let mut command = Command::from(std_command(binary, args));
+    command.kill_on_drop(true);
+    command
+}
+
+fn spawn_failure(binary: &Path, args: &[&str], error: io::Error) -> AppError {
+    record_failure(binary, args, None, error.to_string());
+    let verb = args.first().copied().unwrap_or_default();
+    AppError::Cli(format!("Failed to execute vibesafe {}: {}", verb, error))
+}
+
+/// The line where the CLI stopped to ask for input, if it did.
+fn interactive_prompt(stdout: &[u8], stderr: &[u8]) -> Option<String> {
+    for stream in [stdout, stderr] {
+        let text = String::from_utf8_lossy(stream);
+        let prompt = text
+            .lines()
+            .find(|line| PROMPT_MARKERS.iter().any(|marker| line.contains(marker)));
+        if let Some(prompt) = prompt {
+            return Some(prompt.trim().to_string());
+        }
+    }
+    None
+}
+
+fn check_output(
+    binary: &Path,
+    args: &[&str],
+    output: io::Result<Output>,
+) -> Result<Vec<u8>, AppError> {
+    let output = output.map_err(|e| spawn_failure(binary, args, e))?;
+
+    if output.status.success() {
+        return Ok(output.stdout);
+    }
+
+    let error = String::from_utf8_lossy(&output.stderr);
+    record_failure(binary, args, output.status.code(), error.to_string());
+    // Checked before prompts: the passphrase prompt is on stderr too.
+    if error.contains(WRONG_PASSPHRASE_MARKER) {
+        return Err(AppError::WrongPassphrase);
+    }
+    if let Some(prompt) = interactive_prompt(&output.stdout, &output.stderr) {
+        return Err(AppError::NeedsInteraction(prompt));
+    }
+    if NOT_INITIALIZED_MARKERS
+        .iter()
+        .any(|marker| error.contains(marker))
+    {
+        return Err(AppError::NotInitialized);
+    }
+    if error.contains(AUTH_CANCELLED_MARKER) {
+        return Err(AppError::AuthCancelled);
+    }
+    if error.contains(AUTH_FAILED_MARKER) {
+        return Err(AppError::AuthDenied);
+    }
+    Err(AppError::CliExit {
+        exit_code: output.status.code(),
+        message: format!("VibeSafe error: {}", error),
+    })
+}
+
+/// Blocking: runs the CLI and hands its stdout to `consume` as it is produced, so
+/// large output is never buffered whole. Stderr is drained on another thread
+/// meanwhile, so a CLI writing a lot of it can't stall. A failed exit takes
+/// precedence over whatever `consume` returned.
+pub fn run_vibesafe_streaming<T>(
+    args: &[&str],
+    consume: impl FnOnce(&mut dyn Read) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let binary = locate_cli()?.path;
+    let mut child = std_command(&binary, args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_failure(&binary, args, e))?;
+
+    let stderr = child.stderr.take().map(|mut stderr| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer);
+            buffer
+        })
+    });
+    // stdout is dropped before waiting, so a CLI still writing exits instead of blocking.
+    let consumed = match child.stdout.take() {
+        Some(mut stdout) => consume(&mut stdout),
+        None => Err(AppError::Cli("CLI output wasn't captured".to_string())),
+    };
+    let output = child.wait().map(|status| Output {
+        status,
+        stdout: Vec::new(),
+        stderr: stderr
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default(),
+    });
+    check_output(&binary, args, output)?;
+    consumed
+}
+
+/// Masks words that look like encoded data before a CLI line leaves the wrapper.
+pub fn sanitize_line(line: &str) -> String {
+    line.split(' ')
+        .map(|word| {
+            let encoded = word.len() >= MIN_ENCODED_WORD
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+/=_-".contains(c));
+            if encoded {
+                REDACTED
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CliOutputLine {
+    command: String,
+    line: String,
+}
+
+/// For long operations: emits each stdout line as a "cli:output" event as soon as
+/// the CLI prints it (sanitized), and hands it to `on_line` for callers that track
+/// the operation's stages, then reports success or failure as usual.
+pub async fn run_vibesafe_with_progress(
+    app: &AppHandle,
+    args: &[&str],
+    mut on_line: impl FnMut(&str),
+) -> Result<(), AppError> {
+    check_store_writable(args)?;
+    let binary = locate_cli()?.path;
+    let command_name = args.first().copied().unwrap_or_default().to_string();
+    let mut child = command(&binary, args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_failure(&binary, args, e))?;
+
+    // Drained alongside stdout, so a CLI writing a lot of it can't stall.
+    let stderr = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer).await;
+            buffer
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| AppError::Cli(format!("Failed to read CLI output: {}", e)))?
+        {
+            let line = sanitize_line(&line);
+            on_line(&line);
+            let _ = app.emit_all(
+                CLI_OUTPUT_EVENT,
+                CliOutputLine {
+                    command: command_name.clone(),
+                    line,
+                },
+            );
+        }
+    }
+
+    let status = child.wait().await;
+    let stderr = match stderr {
+        Some(reader) => reader.await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let output = status.map(|status| Output {
+        status,
+        stdout: Vec::new(),
+        stderr,
+    });
+    check_output(&binary, args, output).map(|_| ())
+}
+
+type SharedRun = Arc<OnceCell<Result<Zeroizing<Vec<u8>>, AppError>>>;
+
+/// Read runs currently in progress, keyed by their arguments.
+static IN_FLIGHT: Lazy<Mutex<HashMap<Vec<String>, SharedRun>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// `run_vibesafe` for reads: concurrent calls with the same `args` share one CLI
+/// run and all get its result, errors included. Mutations must not use this.
+pub async fn run_vibesafe_shared(args: &[&str]) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    let key: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let run = IN_FLIGHT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(key.clone())
+        .or_default()
+        .clone();
+
+    let result = run
+        .get_or_init(|| async { run_vibesafe(args).await.map(Zeroizing::new) })
+        .await
+        .clone();
+
+    // The first caller back clears the entry, so anyone arriving later starts a new run.
+    let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+    if in_flight
+        .get(&key)
+        .is_some_and(|current| Arc::ptr_eq(current, &run))
+    {
+        in_flight.remove(&key);
+    }
+    result
+}
+
+/// Runs `status --json` and `list --json` side by side and returns both outputs.
+/// An error names which of the two calls failed.
+pub async fn status_and_list() -> Result<(Zeroizing<Vec<u8>>, Zeroizing<Vec<u8>>), AppError> {
+    let (status_args, list_args) = (action_args("status", &[])?, action_args("list", &[])?);
+    let status =
+        tauri::async_runtime::spawn(async move { run_vibesafe_shared(&status_args).await });
+    let list = tauri::async_runtime::spawn(async move { run_vibesafe_shared(&list_args).await });
+    let joined = |result: tauri::Result<Result<Zeroizing<Vec<u8>>, AppError>>, what: &str| {
+        result
+            .map_err(|e| AppError::Cli(e.to_string()))
+            .and_then(|output| output)
+            .map_err(|e| e.context(what))
+    };
+
+    let status = joined(status.await, "vibesafe status");
+    let list = joined(list.await, "vibesafe list");
+    Ok((status?, list?))
+}
+
+/// Decrypts one secret through the CLI. The buffer is wiped when dropped.
+pub async fn get_secret(name: &str) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    run_vibesafe_shared(&action_args("get", &[name])?).await
+}
+
+/// Decrypts `name` with `passphrase` answering the CLI's passphrase prompt, to
+/// check the passphrase; the value is wiped unread. Fails with `WrongPassphrase`
+/// when the key doesn't open.
+pub async fn check_passphrase(name: &str, passphrase: &str) -> Result<(), AppError> {
+    let input = Zeroizing::new(format!("{}\n", passphrase));
+    run_vibesafe_with_stdin(&action_args("get", &[name])?, input.as_bytes())
+        .await
+        .map(|value| drop(Zeroizing::new(value)))
+}
+
+/// Parses JSON the CLI printed for `what`. Output we can't read usually means the
+/// CLI changed shape, so the error names the CLI version instead of guessing.
+pub async fn parse_json<T: DeserializeOwned>(stdout: &[u8], what: &str) -> Result<T, AppError> {
+    match serde_json::from_slice(stdout) {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            let version = cli_version()
+                .await
+                .map(|version| version.to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            Err(AppError::UnparseableCliOutput(format!(
+                "Couldn't read the {} output of VibeSafe CLI {}: {}",
+                what, version, e
+            )))
+        }
+    }
+}
+
+/// Parses the CLI's `status` output: JSON, or the text older CLIs print instead,
+/// read through `status_from_text`.
+pub async fn parse_status<T: DeserializeOwned>(stdout: &[u8]) -> Result<T, AppError> {
+    if serde_json::from_slice::<serde_json::Value>(stdout).is_ok() {
+        return parse_json(stdout, "status").await;
+    }
+    let version = cli_version()
+        .await
+        .map(|version| version.to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let status = status_from_text(&String::from_utf8_lossy(stdout), &version)?;
+    parse_json(status.to_string().as_bytes(), "status").await
+}
+
+/// Reads text `status` output into the shape `status --json` prints. Fails with
+/// `UnparseableCliOutput`, naming CLI `version`, when a marker pair is missing.
+fn status_from_text(text: &str, version: &str) -> Result<serde_json::Value, AppError> {
+    let unreadable = |what: &str| {
+        AppError::UnparseableCliOutput(format!(
+            "Couldn't find the {} in the status output of VibeSafe CLI {}. The CLI's \
+             messages may be translated; the app reads them in English.",
+            what, version
+        ))
+    };
+    let flag = |(set, unset): (&str, &str), what: &str| {
+        // The unset line can contain the set one ("NOT CONFIGURED"), so it goes first.
+        if text.contains(unset) {
+            Ok(false)
+        } else if text.contains(set) {
+            Ok(true)
+        } else {
+            Err(unreadable(what))
+        }
+    };
+    let initialized = flag(KEY_PAIR_MARKERS, "key pair status")?;
+    let passkey_enabled = flag(PASSKEY_MARKERS, "passkey status")?;
+    let claude_integration = flag(CLAUDE_MARKERS, "Claude integration status")?;
+    let secrets_count: u32 = text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(SECRETS_COUNT_MARKER))
+        .and_then(|count| count.trim().parse().ok())
+        .ok_or_else(|| unreadable("secret count"))?;
+    Ok(serde_json::json!({
+        "initialized": initialized,
+        "key_exists": initialized,
+        "passkey_enabled": passkey_enabled,
+        "secrets_count": secrets_count,
+        "claude_integration": claude_integration,
+    }))
+}
+
+/// How the CLI's `add` can be given a value without it appearing in the
+/// argument list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StdinAdd {
+    /// `add NAME --stdin`.
+    Flag,
+    /// `add NAME` prompts for the value, and the prompt reads piped stdin.
+    Prompt,
+    /// The value can only be an argument.
+    Unsupported,
+}
+
+/// The last probe result, for the CLI binary it was made against.
+static STDIN_ADD: Lazy<Mutex<Option<(PathBuf, StdinAdd)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Reads `add --help`: a `--stdin` option, or a usage line where the value is
+/// optional (`NAME [VALUE]`) and so prompted for.
+fn parse_add_help(help: &str) -> StdinAdd {
+    if help.contains("--stdin") {
+        return StdinAdd::Flag;
+    }
+    let usage = help
+        .lines()
+        .find(|line| line.trim_start().starts_with("Usage:"))
+        .unwrap_or_default();
+    if usage.contains(" add ") && !usage.contains(" VALUE") {
+        StdinAdd::Prompt
+    } else {
+        StdinAdd::Unsupported
+    }
+}
+
+/// Whether `input` has a line break other than its final newline. Python's line
+/// reading treats a lone `\r` as one too.
+fn spans_lines(input: &[u8]) -> bool {
+    let value = input.strip_suffix(b"\n").unwrap_or(input);
+    value.contains(&b'\n') || value.contains(&b'\r')
+}
+
+/// Whether the CLI can take an added value on stdin. Probed once per CLI binary.
+pub async fn stdin_add_support() -> Result<StdinAdd, AppError> {
+    let binary = locate_cli()?.path;
+    if let Some((probed, support)) = STDIN_ADD
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        if *probed == binary {
+            return Ok(*support);
+        }
+    }
+    let stdout = run_vibesafe(&["add", "--help"]).await?;
+    let support = parse_add_help(&String::from_utf8_lossy(&stdout));
+    *STDIN_ADD.lock().unwrap_or_else(PoisonError::into_inner) = Some((binary, support));
+    Ok(support)
+}
+
+/// Adds secret `name` with `input` (the value and a newline) on stdin, the way
+/// this CLI supports. Fails with `UnsupportedStdin` rather than passing the value
+/// as an argument. `--stdin` reads to the end of input, so any value fits; the
+/// prompt reads one line, so a value with a line break before the final newline
+/// fails with `MultilineValue` rather than being cut short.
+pub async fn add_with_stdin(name: &str, input: &[u8]) -> Result<Vec<u8>, AppError> {
+    let action = match stdin_add_support().await? {
+        StdinAdd::Flag => "add_stdin",
+        StdinAdd::Prompt if spans_lines(input) => return Err(AppError::MultilineValue),
+        StdinAdd::Prompt => "add",
+        StdinAdd::Unsupported => return Err(AppError::UnsupportedStdin),
+    };
+    run_vibesafe_with_stdin(&action_args(action, &[name])?, input).await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CliVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl CliVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses the last word of `vibesafe --version` output ("vibesafe, version 1.0.0").
+    pub fn parse(text: &str) -> Option<Self> {
+        let word = text.split_whitespace().last()?.trim_start_matches('v');
+        let mut parts = word.split('.').map(|part| part.parse::<u32>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for CliVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+pub async fn cli_version() -> Result<CliVersion, AppError> {
+    cli_version_at(&locate_cli()?.path).await
+}
+
+/// Version reported by the CLI at `binary`.
+async fn cli_version_at(binary: &Path) -> Result<CliVersion, AppError> {
+    let args = ["--version"];
+    let output = command(binary, &args).output().await;
+    let stdout = check_output(binary, &args, output)?;
+    let text = String::from_utf8_lossy(&stdout);
+    CliVersion::parse(&text)
+        .ok_or_else(|| AppError::Cli(format!("Unrecognized CLI version output: {}", text.trim())))
+}
+
+/// Versions of the bundled and the PATH CLI side by side. `None` means that CLI
+/// is missing or didn't report a version.
+#[derive(Debug, Serialize)]
+pub struct CliVersions {
+    pub bundled: Option<String>,
+    pub system: Option<String>,
+    /// Both exist and report different versions.
+    pub mismatch: bool,
+}
+
+pub async fn compare_versions() -> CliVersions {
+    let bundled = match sidecar_path().ok().filter(|path| path.is_file()) {
+        Some(path) => cli_version_at(&path).await.ok(),
+        None => None,
+    };
+    let system = match system_path() {
+        Some(path) => cli_version_at(&path).await.ok(),
+        None => None,
+    };
+
+    CliVersions {
+        mismatch: matches!((bundled, system), (Some(a), Some(b)) if a != b),
+        bundled: bundled.map(|version| version.to_string()),
+        system: system.map(|version| version.to_string()),
+    }
+}
+
+/// Fails with `UnsupportedCli` when the CLI is older than `required` for `feature`.
+pub async fn require_version(required: CliVersion, feature: &str) -> Result<(), AppError> {
+    let found = cli_version().await?;
+    if found < required {
+        return Err(AppError::UnsupportedCli(format!(
+            "{} requires VibeSafe CLI {} or newer (found {})",
+            feature, required, found
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn the_cli_only_sees_the_allowlist() {
+        // Cargo sets CARGO_MANIFEST_DIR for the test process; it isn't allowlisted.
+        assert!(env::var_os("CARGO_MANIFEST_DIR").is_some());
+        let _fake = crate::test_support::fake_cli(
+            r#"printf '%s|%s|%s' "${CARGO_MANIFEST_DIR-unset}" "${HOME-unset}" "$LC_ALL""#,
+        );
+        let output = run_vibesafe(&["status"]).await;
+        let home = env::var("HOME").unwrap_or_else(|_| "unset".to_string());
+        assert_eq!(
+            String::from_utf8(output.unwrap()).unwrap(),
+            format!("unset|{}|C", home)
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn the_passphrase_answers_the_prompt() {
+        let _fake = crate::test_support::fake_cli(
+            r#"printf 'Enter passphrase for private key: ' >&2
+read -r passphrase
+[ "$passphrase" = hunter2 ] && printf value && exit 0
+printf '\nValueError: Bad decrypt. Incorrect password?\n' >&2
+exit 1"#,
+        );
+        check_passphrase("API_KEY", "hunter2").await.unwrap();
+        assert!(matches!(
+            check_passphrase("API_KEY", "hunter3").await,
+            Err(AppError::WrongPassphrase)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn concurrent_identical_reads_share_one_run() {
+        let fake = crate::test_support::fake_cli("echo run >> runs; sleep 0.3; printf '[]'");
+        let args = action_args("list", &[]).unwrap();
+        let reads: Vec<_> = (0..5)
+            .map(|_| {
+                let args = args.clone();
+                tokio::spawn(async move { run_vibesafe_shared(&args).await })
+            })
+            .collect();
+        for read in reads {
+            assert_eq!(&read.await.unwrap().unwrap()[..], b"[]");
+        }
+        let runs = || {
+            std::fs::read_to_string(fake.path("runs"))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        assert_eq!(runs(), 1);
+
+        // Once it's done, the next read runs the CLI again.
+        run_vibesafe_shared(&args).await.unwrap();
+        assert_eq!(runs(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn previews_match_what_actions_run() {
+        let fake = crate::test_support::fake_cli(r#"printf '%s\n' "$@""#);
+        for (action, template) in ACTIONS {
+            let args: Vec<String> = (0..template.iter().filter(|word| **word == ARG).count())
+                .map(|i| format!("user value {}", i))
+                .collect();
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let preview = preview(action, &args).unwrap();
+            let stdout = run_vibesafe(&action_args(action, &args).unwrap())
+                .await
+                .unwrap();
+            let argv: Vec<&str> = std::str::from_utf8(&stdout).unwrap().lines().collect();
+            assert_eq!(preview.binary_path, fake.path("vibesafe"), "{}", action);
+            assert_eq!(preview.args_redacted, redact_args(&argv), "{}", action);
+            assert_eq!(argv.len(), template.len(), "{}", action);
+            assert!(!preview.command_line.contains("user value"), "{}", action);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn streaming_runs_survive_a_full_stderr_pipe() {
+        // Far more than a pipe buffer, written before any stdout.
+        let _fake = crate::test_support::fake_cli(
+            "head -c 1000000 /dev/zero | tr '\\0' x >&2; echo done; exit 3",
+        );
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let result = run_vibesafe_streaming(&["list"], |stdout| {
+                let mut text = String::new();
+                stdout.read_to_string(&mut text).ok();
+                Ok(text)
+            });
+            let _ = sender.send(result);
+        });
+        let result = receiver
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .expect("the run stalled");
+        match result {
+            Err(AppError::CliExit { exit_code, message }) => {
+                assert_eq!(exit_code, Some(3));
+                assert!(message.len() > 1_000_000);
+            }
+            other => panic!(
+                "expected the exit to be reported, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    #[test]
+    fn long_encoded_words_are_masked() {
+        let key = "c2stbGl2ZS0wMTIzNDU2Nzg5YWJjZGVmZ2hpams=";
+        let hex = "0123456789abcdef0123456789abcdef";
+        assert_eq!(
+            sanitize_line(&format!("Encrypted {} with {}", key, hex)),
+            format!("Encrypted {} with {}", REDACTED, REDACTED)
+        );
+        // One short of the threshold, and long words with other characters.
+        let short = &hex[..MIN_ENCODED_WORD - 1];
+        assert_eq!(sanitize_line(short), short);
+        let path = "/home/ana/.vibesafe/secrets.json.backup-2024";
+        assert_eq!(sanitize_line(path), path);
+        assert_eq!(
+            sanitize_line("✓ Secret  'API_KEY' added"),
+            "✓ Secret  'API_KEY' added"
+        );
+    }
+
+    #[test]
+    fn add_help_tells_how_values_are_taken() {
+        let flag = "Usage: vibesafe add [OPTIONS] NAME\n\nOptions:\n  --stdin  Read the value from stdin\n";
+        let prompt =
+            "Usage: vibesafe add [OPTIONS] NAME\n\n  Add a secret. The value is prompted for.\n";
+        let argument = "Usage: vibesafe add [OPTIONS] NAME VALUE\n";
+        assert_eq!(parse_add_help(flag), StdinAdd::Flag);
+        assert_eq!(parse_add_help(prompt), StdinAdd::Prompt);
+        assert_eq!(parse_add_help(argument), StdinAdd::Unsupported);
+        assert_eq!(parse_add_help(""), StdinAdd::Unsupported);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdin_support_is_probed_once_per_binary() {
+        let fake = crate::test_support::fake_cli(
+            r#"case "$2" in
+  --help) echo probe >> probes; echo "Usage: vibesafe add NAME VALUE" ;;
+  *) echo add >> adds ;;
+esac"#,
+        );
+        assert_eq!(stdin_add_support().await.unwrap(), StdinAdd::Unsupported);
+        assert!(matches!(
+            add_with_stdin("API_KEY", b"value\n").await,
+            Err(AppError::UnsupportedStdin)
+        ));
+        let probes = std::fs::read_to_string(fake.path("probes")).unwrap();
+        assert_eq!(probes.lines().count(), 1);
+        // The value was never passed some other way.
+        assert!(!fake.path("adds").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn multi_line_values_need_the_stdin_flag() {
+        let pem = b"-----BEGIN KEY-----\nAAAA\n-----END KEY-----\n\n";
+        let fake = crate::test_support::fake_cli(
+            r#"case "$2" in
+  --help) echo "Usage: vibesafe add [OPTIONS] NAME" ;;
+  *) cat > added ;;
+esac"#,
+        );
+        assert!(matches!(
+            add_with_stdin("PEM", pem).await,
+            Err(AppError::MultilineValue)
+        ));
+        assert!(matches!(
+            add_with_stdin("CR", b"one\rtwo\n").await,
+            Err(AppError::MultilineValue)
+        ));
+        assert!(!fake.path("added").exists());
+        add_with_stdin("TOKEN", b"one line\n").await.unwrap();
+        drop(fake);
+
+        let fake = crate::test_support::fake_cli(
+            r#"case "$2" in
+  --help) echo "Usage: vibesafe add [OPTIONS] NAME --stdin" ;;
+  *) cat > added ;;
+esac"#,
+        );
+        add_with_stdin("PEM", pem).await.unwrap();
+        assert_eq!(std::fs::read(fake.path("added")).unwrap(), pem);
+    }
+
+    #[test]
+    fn text_status_is_read_in_english() {
+        let status = status_from_text(include_str!("fixtures/status-en.txt"), "1.2.3").unwrap();
+        assert_eq!(
+            status,
+            serde_json::json!({
+                "initialized": true,
+                "key_exists": true,
+                "passkey_enabled": true,
+                "secrets_count": 3,
+                "claude_integration": false,
+            })
+        );
+        let status = status_from_text(
+            include_str!("fixtures/status-en-uninitialized.txt"),
+            "1.2.3",
+        )
+        .unwrap();
+        assert_eq!(status["initialized"], false);
+        assert_eq!(status["passkey_enabled"], false);
+        assert_eq!(status["secrets_count"], 0);
+    }
+
+    #[test]
+    fn localized_text_status_is_an_error_not_uninitialized() {
+        let english = include_str!("fixtures/status-en.txt");
+        let fixtures = [
+            ("de", include_str!("fixtures/status-de.txt").to_string()),
+            ("fr", include_str!("fixtures/status-fr.txt").to_string()),
+            ("ja", include_str!("fixtures/status-ja.txt").to_string()),
+            // Only some messages translated.
+            (
+                "partial",
+                english.replace("Passkey protection: ENABLED", "Passkey-Schutz: AKTIVIERT"),
+            ),
+        ];
+        for (locale, text) in fixtures {
+            match status_from_text(&text, "1.2.3") {
+                Err(AppError::UnparseableCliOutput(message)) => {
+                    assert!(message.contains("1.2.3"), "{}: {}", locale, message)
+                }
+                other => panic!("{}: {:?}", locale, other),
+            }
+        }
+    }
+
+    #[test]
+    fn versions_parse_from_version_output() {
+        let parse = |text| CliVersion::parse(text).map(|version| version.to_string());
+        assert_eq!(parse("vibesafe, version 1.2.3\n").as_deref(), Some("1.2.3"));
+        assert_eq!(parse("vibesafe v2.0").as_deref(), Some("2.0.0"));
+        assert_eq!(parse("3").as_deref(), Some("3.0.0"));
+        assert_eq!(parse("vibesafe, version 1.2.beta"), None);
+        assert_eq!(parse("vibesafe, version"), None);
+        assert_eq!(parse(""), None);
+        assert!(CliVersion::new(1, 10, 0) > CliVersion::new(1, 9, 9));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn features_require_a_new_enough_cli() {
+        let _fake = crate::test_support::fake_cli("echo 'vibesafe, version 1.4.2'");
+        assert!(require_version(CliVersion::new(1, 4, 2), "Sharing")
+            .await
+            .is_ok());
+        assert!(require_version(CliVersion::new(1, 0, 0), "Sharing")
+            .await
+            .is_ok());
+        match require_version(CliVersion::new(1, 5, 0), "Sharing").await {
+            Err(AppError::UnsupportedCli(message)) => {
+                assert!(
+                    message.contains("1.5.0") && message.contains("1.4.2"),
+                    "{}",
+                    message
+                )
+            }
+            other => panic!("expected UnsupportedCli, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::atomic;
+use crate::cache::StoreCache;
+use crate::error::AppError;
+use crate::paths;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{command, State};
+
+#[derive(Debug, Clone, Copy)]
+enum ValueKind {
+    Bool,
+    String,
+}
+
+impl ValueKind {
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            ValueKind::Bool => value.is_boolean(),
+            ValueKind::String => value.is_string(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ValueKind::Bool => "boolean",
+            ValueKind::String => "string",
+        }
+    }
+}
+
+struct ConfigKey {
+    name: &'static str,
+    kind: ValueKind,
+    /// Keys tied to how the private key is stored are only changed by the CLI's
+    /// own commands; editing them by hand can lock the user out of their secrets.
+    editable: bool,
+}
+
+/// Keys the CLI reads from ~/.vibesafe/config.json. None of them hold key material.
+const KNOWN_KEYS: &[ConfigKey] = &[
+    ConfigKey {
+        name: "passkey_enabled",
+        kind: ValueKind::Bool,
+        editable: false,
+    },
+    ConfigKey {
+        name: "passkey_type",
+        kind: ValueKind::String,
+        editable: false,
+    },
+    ConfigKey {
+        name: "key_encrypted",
+        kind: ValueKind::Bool,
+        editable: false,
+    },
+    ConfigKey {
+        name: "claude_configured",
+        kind: ValueKind::Bool,
+        editable: true,
+    },
+];
+
+#[derive(Debug, Serialize)]
+pub struct CliConfigEntry {
+    key: &'static str,
+    /// `null` when the key isn't set.
+    value: Value,
+    editable: bool,
+}
+
+fn config_path() -> Result<PathBuf, AppError> {
+    Ok(paths::store_dir()?.join("config.json"))
+}
+
+/// Whether the CLI encrypted the private key with a passphrase.
+pub fn key_encrypted() -> Result<bool, AppError> {
+    Ok(read_config()?
+        .get("key_encrypted")
+        .and_then(Value::as_bool)
+        .unwrap_or(false))
+}
+
+pub fn read_config() -> Result<Map<String, Value>, AppError> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(Map::new());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| AppError::Cli(format!("Failed to read CLI config: {}", e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| AppError::Cli(format!("Failed to parse CLI config: {}", e)))
+}
+
+/// Replaced atomically, like the CLI does, so the CLI never reads a half-written file.
+fn write_config(config: &Map<String, Value>) -> Result<(), AppError> {
+    atomic::atomic_write_json(&config_path()?, config)
+        .map_err(|e| AppError::Cli(format!("Failed to write CLI config: {}", e)))
+}
+
+/// The CLI's known config keys and their values. Unknown keys are left out.
+#[command]
+pub fn get_cli_config() -> Result<Vec<CliConfigEntry>, AppError> {
+    let config = read_config()?;
+    Ok(KNOWN_KEYS
+        .iter()
+        .map(|key| CliConfigEntry {
+            key: key.name,
+            value: config.get(key.name).cloned().unwrap_or(Value::Null),
+            editable: key.editable,
+        })
+        .collect())
+}
+
+#[command]
+pub fn set_cli_config(
+    cache: State<'_, StoreCache>,
+    key: String,
+    value: Value,
+) -> Result<(), AppError> {
+    let known = KNOWN_KEYS
+        .iter()
+        .find(|known| known.name == key)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown CLI config key '{}'", key)))?;
+    if !known.editable {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' is managed by the VibeSafe CLI and can't be edited here",
+            key
+        )));
+    }
+    if !known.kind.accepts(&value) {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' must be a {}",
+            key,
+            known.kind.name()
+        )));
+    }
+
+    let mut config = read_config()?;
+    config.insert(key, value);
+    write_config(&config)?;
+    cache.invalidate();
+    Ok(())
+}
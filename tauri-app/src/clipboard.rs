@@ -0,0 +1,279 @@
+use crate::error::AppError;
+use crate::settings::{self, ClipboardClearStrategy};
+use serde::Serialize;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// How long a copied secret stays on the clipboard.
+pub const CLEAR_AFTER: Duration = Duration::from_secs(30);
+/// Emitted with `PinChanged` whenever a pinned copy starts or ends, so the UI can
+/// show that auto-clear is off.
+const PIN_CHANGED_EVENT: &str = "clipboard:pin-changed";
+
+/// What the app last put on the clipboard.
+#[derive(Default)]
+pub struct ClipboardState {
+    /// Bumped on every write so a pending auto-clear can tell it was superseded.
+    generation: AtomicU64,
+    /// Token of the current pinned copy, which is never cleared automatically.
+    pin: Mutex<Option<String>>,
+    /// The latest write was a copied value that hasn't been cleared.
+    holds_secret: AtomicBool,
+    /// Kept open between writes: on X11 the owner has to stay alive to serve
+    /// the content, and dropping it would hand the value to a clipboard manager.
+    clipboard: Mutex<Option<arboard::Clipboard>>,
+}
+
+impl ClipboardState {
+    /// Records a write of `text`; returns its generation and whether it ended a
+    /// pinned copy.
+    fn record_write(&self, text: &str, concealed: bool) -> (u64, bool) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.holds_secret
+            .store(concealed && !text.is_empty(), Ordering::SeqCst);
+        let unpinned = self
+            .pin
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .is_some();
+        (generation, unpinned)
+    }
+
+    /// Whether `generation` is still the latest write, after waiting `delay`.
+    async fn unchanged_after(&self, generation: u64, delay: Duration) -> bool {
+        tokio::time::sleep(delay).await;
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PinChanged {
+    pinned: bool,
+}
+
+/// Puts `text` on the clipboard. With `concealed`, the same write carries the
+/// hints clipboard history tools honor to skip an entry: the nspasteboard.org
+/// concealed type on macOS, the monitor-processing and clipboard-history
+/// formats on Windows, and `x-kde-passwordManagerHint` on Linux.
+fn set_text(state: &ClipboardState, text: &str, concealed: bool) -> Result<(), arboard::Error> {
+    let mut clipboard = state
+        .clipboard
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if clipboard.is_none() {
+        *clipboard = Some(arboard::Clipboard::new()?);
+    }
+    let set = clipboard.as_mut().expect("opened above").set();
+    if !concealed {
+        return set.text(text);
+    }
+    #[cfg(target_os = "macos")]
+    let set = {
+        use arboard::SetExtApple;
+        set.exclude_from_history()
+    };
+    #[cfg(windows)]
+    let set = {
+        use arboard::SetExtWindows;
+        set.exclude_from_monitoring().exclude_from_history()
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let set = {
+        use arboard::SetExtLinux;
+        set.exclude_from_history()
+    };
+    set.text(text)
+}
+
+/// Writes `text`, ending any pinned copy since its value is gone. Returns the new
+/// generation. Everything but a copied name is `concealed`.
+fn write(app: &AppHandle, text: &str, concealed: bool) -> Result<u64, AppError> {
+    let state = app.state::<ClipboardState>();
+    set_text(&state, text, concealed)
+        .map_err(|e| AppError::Cli(format!("Failed to copy to clipboard: {}", e)))?;
+
+    let (generation, unpinned) = state.record_write(text, concealed);
+    if unpinned {
+        let _ = app.emit_all(PIN_CHANGED_EVENT, PinChanged { pinned: false });
+    }
+    Ok(generation)
+}
+
+/// Empties the clipboard through the OS clipboard API, which removes the entry
+/// rather than adding an empty one. False when no tool for it ran successfully.
+fn native_clear() -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.args([
+            "-l",
+            "JavaScript",
+            "-e",
+            "ObjC.import('AppKit'); $.NSPasteboard.generalPasteboard.clearContents",
+        ]);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-STA",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.Clipboard]::Clear()",
+        ]);
+        command
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new("wl-copy");
+        command.arg("--clear");
+        command
+    } else {
+        let mut command = Command::new("xsel");
+        command.args(["--clipboard", "--clear"]);
+        command
+    };
+    command.status().is_ok_and(|status| status.success())
+}
+
+/// Removes a copied value the way the `clipboard_clear_strategy` setting asks.
+/// Every strategy ends with an empty string written through `write`, so pins and
+/// pending auto-clears are settled the same way.
+fn clear(app: &AppHandle) -> Result<(), AppError> {
+    let strategy = settings::load_settings(app)
+        .map(|settings| settings.clipboard_clear_strategy)
+        .unwrap_or(ClipboardClearStrategy::Empty);
+    if strategy == ClipboardClearStrategy::OverwriteRandom {
+        let junk = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        write(app, &junk, true)?;
+    }
+    write(app, "", true)?;
+    if strategy == ClipboardClearStrategy::NativeClear {
+        // The empty string is already there if the OS tool is missing.
+        native_clear();
+    }
+    Ok(())
+}
+
+/// Clears the clipboard if it still holds a value the app copied, which also
+/// cancels the pending auto-clear and ends a pin.
+pub fn clear_secret(app: &AppHandle) -> Result<(), AppError> {
+    if app
+        .state::<ClipboardState>()
+        .holds_secret
+        .load(Ordering::SeqCst)
+    {
+        clear(app)?;
+    }
+    Ok(())
+}
+
+/// Puts `text` on the clipboard, concealed from history tools, and clears it
+/// after `CLEAR_AFTER` unless something else was copied in the meantime.
+pub fn copy_with_auto_clear(app: &AppHandle, text: &str) -> Result<(), AppError> {
+    let generation = write(app, text, true)?;
+    clear_later(app, generation);
+    Ok(())
+}
+
+/// Clears the clipboard after `CLEAR_AFTER` if `generation` is still the latest write.
+fn clear_later(app: &AppHandle, generation: u64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let unchanged = app
+            .state::<ClipboardState>()
+            .unchanged_after(generation, CLEAR_AFTER)
+            .await;
+        if unchanged {
+            let _ = tauri::async_runtime::spawn_blocking(move || clear(&app)).await;
+        }
+    });
+}
+
+/// Puts `text` on the clipboard, concealed from history tools, with no
+/// auto-clear. Returns the token `unpin_clipboard` needs to clear it.
+pub fn copy_pinned(app: &AppHandle, text: &str) -> Result<String, AppError> {
+    write(app, text, true)?;
+
+    let token = Uuid::new_v4().to_string();
+    *app.state::<ClipboardState>()
+        .pin
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(token.clone());
+    let _ = app.emit_all(PIN_CHANGED_EVENT, PinChanged { pinned: true });
+    Ok(token)
+}
+
+/// Copies a secret's name. Like every write it supersedes a pending auto-clear, so
+/// a value copied earlier can't wipe the name. The name is only cleared itself
+/// when the `clear_copied_names` setting is on.
+#[command]
+pub fn copy_secret_name(app: AppHandle, name: String) -> Result<(), AppError> {
+    let generation = write(&app, &name, false)?;
+    if settings::load_settings(&app)?.clear_copied_names {
+        clear_later(&app, generation);
+    }
+    Ok(())
+}
+
+/// Clears a pinned copy now. Fails if `token` isn't the current pin, e.g. because
+/// something else has been copied since.
+#[command]
+pub fn unpin_clipboard(
+    app: AppHandle,
+    clipboard: State<'_, ClipboardState>,
+    token: String,
+) -> Result<(), AppError> {
+    let pinned = clipboard
+        .pin
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_deref()
+        == Some(token.as_str());
+    if !pinned {
+        return Err(AppError::InvalidInput(
+            "That copy is no longer pinned".to_string(),
+        ));
+    }
+    clear(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn an_untouched_copy_is_cleared_after_the_delay() {
+        let state = ClipboardState::default();
+        let (generation, _) = state.record_write("sk-123", true);
+        assert!(state.holds_secret.load(Ordering::SeqCst));
+        assert!(state.unchanged_after(generation, DELAY).await);
+    }
+
+    #[tokio::test]
+    async fn a_later_copy_cancels_the_clear() {
+        let state = ClipboardState::default();
+        let (generation, _) = state.record_write("sk-123", true);
+        let (unchanged, _) = tokio::join!(state.unchanged_after(generation, DELAY), async {
+            tokio::time::sleep(DELAY / 5).await;
+            state.record_write("OPENAI_KEY", false)
+        });
+        assert!(!unchanged);
+        // A copied name isn't a secret, so locking leaves it alone.
+        assert!(!state.holds_secret.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn clearing_and_copying_end_a_pin() {
+        let state = ClipboardState::default();
+        *state.pin.lock().unwrap() = Some("token".to_string());
+        let (_, unpinned) = state.record_write("", true);
+        assert!(unpinned);
+        assert!(!state.holds_secret.load(Ordering::SeqCst));
+        let (_, unpinned) = state.record_write("sk-123", true);
+        assert!(!unpinned);
+    }
+}
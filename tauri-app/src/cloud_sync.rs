@@ -0,0 +1,213 @@
+use crate::error::AppError;
+use crate::paths;
+use serde::Serialize;
+use std::path::Path;
+
+/// The store files the CLI reads and rewrites.
+const STORE_FILES: &[&str] = &["private.pem", "public.pem", "secrets.json", "config.json"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SyncProvider {
+    #[serde(rename = "icloud_drive")]
+    ICloudDrive,
+    #[serde(rename = "dropbox")]
+    Dropbox,
+    #[serde(rename = "onedrive")]
+    OneDrive,
+    #[serde(rename = "google_drive")]
+    GoogleDrive,
+}
+
+impl SyncProvider {
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncProvider::ICloudDrive => "iCloud Drive",
+            SyncProvider::Dropbox => "Dropbox",
+            SyncProvider::OneDrive => "OneDrive",
+            SyncProvider::GoogleDrive => "Google Drive",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncStatus {
+    pub provider: Option<SyncProvider>,
+    /// Store files that are cloud placeholders whose contents aren't on disk.
+    pub not_downloaded: Vec<String>,
+    pub warning: Option<String>,
+}
+
+/// The sync client whose folder `path` is in, from its directory names. Covers
+/// the folders the clients create themselves, including macOS's
+/// `~/Library/CloudStorage/<Provider>-<account>` and business variants such as
+/// "Dropbox (Acme)" or "OneDrive - Acme".
+pub fn classify(path: &Path) -> Option<SyncProvider> {
+    path.components().find_map(|component| {
+        let name = component.as_os_str().to_string_lossy().to_lowercase();
+        if name == "mobile documents" || name == "icloud drive" || name == "iclouddrive" {
+            Some(SyncProvider::ICloudDrive)
+        } else if name.starts_with("dropbox") {
+            Some(SyncProvider::Dropbox)
+        } else if name.starts_with("onedrive") {
+            Some(SyncProvider::OneDrive)
+        } else if name == "google drive" || name.starts_with("googledrive") {
+            Some(SyncProvider::GoogleDrive)
+        } else {
+            None
+        }
+    })
+}
+
+/// macOS marks evicted iCloud (and File Provider) files `SF_DATALESS`; older
+/// iCloud versions replace them with a hidden `.<name>.icloud` stub instead.
+#[cfg(target_os = "macos")]
+fn is_placeholder(dir: &Path, file: &str) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    match std::fs::symlink_metadata(dir.join(file)) {
+        Ok(metadata) => metadata.st_flags() & SF_DATALESS != 0,
+        Err(_) => dir.join(format!(".{}.icloud", file)).exists(),
+    }
+}
+
+/// Cloud Files placeholders (OneDrive, Dropbox, iCloud for Windows) carry the
+/// recall attributes until their contents are fetched.
+#[cfg(windows)]
+fn is_placeholder(dir: &Path, file: &str) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+    std::fs::symlink_metadata(dir.join(file)).map_or(false, |metadata| {
+        metadata.file_attributes()
+            & (FILE_ATTRIBUTE_OFFLINE
+                | FILE_ATTRIBUTE_RECALL_ON_OPEN
+                | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+            != 0
+    })
+}
+
+/// Linux sync clients keep full copies on disk.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn is_placeholder(_dir: &Path, _file: &str) -> bool {
+    false
+}
+
+/// Where the store is synced to, if anywhere, and which of its files aren't
+/// downloaded. `~/.vibesafe` is resolved first, so a symlink into a synced
+/// folder counts.
+pub fn status() -> SyncStatus {
+    let store = match paths::store_dir() {
+        Ok(store) => store.canonicalize().unwrap_or(store),
+        Err(_) => {
+            return SyncStatus {
+                provider: None,
+                not_downloaded: Vec::new(),
+                warning: None,
+            }
+        }
+    };
+    let provider = classify(&store);
+    let not_downloaded: Vec<String> = STORE_FILES
+        .iter()
+        .filter(|file| is_placeholder(&store, file))
+        .map(|file| file.to_string())
+        .collect();
+
+    let mut warning = provider.map(|provider| {
+        format!(
+            "The VibeSafe store ({}) is in {}. Sync can replace a file while the CLI is \
+             writing it and corrupt the store. Move the folder out of {} or exclude it from \
+             sync.",
+            store.display(),
+            provider.label(),
+            provider.label()
+        )
+    });
+    if !not_downloaded.is_empty() {
+        let download = format!(
+            "{} isn't downloaded to this machine, so changes are blocked. Mark the folder to \
+             always keep its files on this device and wait for the download to finish.",
+            not_downloaded.join(", ")
+        );
+        warning = Some(match warning {
+            Some(warning) => format!("{} {}", warning, download),
+            None => download,
+        });
+    }
+    SyncStatus {
+        provider,
+        not_downloaded,
+        warning,
+    }
+}
+
+/// Fails with `STORE_NOT_DOWNLOADED` when a store file is a cloud placeholder,
+/// before the CLI tries to rewrite it.
+pub fn require_downloaded() -> Result<(), AppError> {
+    let not_downloaded = status().not_downloaded;
+    if not_downloaded.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::StoreNotDownloaded {
+            files: not_downloaded,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_folders_are_recognized_by_name() {
+        let cases = [
+            (
+                "/Users/ana/Library/Mobile Documents/com~apple~CloudDocs/vault",
+                Some(SyncProvider::ICloudDrive),
+            ),
+            (
+                "C:\\Users\\ana\\iCloudDrive\\vault",
+                Some(SyncProvider::ICloudDrive),
+            ),
+            ("/home/ana/Dropbox/vault", Some(SyncProvider::Dropbox)),
+            (
+                "/home/ana/Dropbox (Acme)/vault",
+                Some(SyncProvider::Dropbox),
+            ),
+            (
+                "/Users/ana/Library/CloudStorage/Dropbox-Personal/vault",
+                Some(SyncProvider::Dropbox),
+            ),
+            (
+                "C:\\Users\\ana\\OneDrive - Acme\\vault",
+                Some(SyncProvider::OneDrive),
+            ),
+            (
+                "/Users/ana/Library/CloudStorage/OneDrive-Personal/vault",
+                Some(SyncProvider::OneDrive),
+            ),
+            (
+                "G:\\My Drive\\Google Drive\\vault",
+                Some(SyncProvider::GoogleDrive),
+            ),
+            (
+                "/Users/ana/Library/CloudStorage/GoogleDrive-ana@example.com/vault",
+                Some(SyncProvider::GoogleDrive),
+            ),
+            ("/home/ana/.vibesafe", None),
+            ("/home/ana/notes-about-dropbox/vault", None),
+        ];
+        for (path, provider) in cases {
+            // Windows separators are components only on Windows.
+            let path = if cfg!(windows) {
+                path.to_string()
+            } else {
+                path.replace('\\', "/")
+            };
+            assert_eq!(classify(Path::new(&path)), provider, "{}", path);
+        }
+    }
+}
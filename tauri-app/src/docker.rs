@@ -0,0 +1,275 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::shred::{self, TempFiles};
+use crate::{cli, names, projects, runner};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager, State, Window};
+use zeroize::Zeroizing;
+
+const ENV_FILE_REASON: &str = "Exported to a Docker env file";
+const COMPOSE_REASON: &str = "Exported for docker compose";
+
+/// One `VAR=value` line in the format `docker run --env-file` reads. Docker takes
+/// everything after the first `=` literally, up to the end of the line: quotes
+/// are kept as part of the value, so nothing is quoted or escaped here, and
+/// spaces and further `=` signs need no special handling. A line break can't be
+/// represented at all, so such values are refused, as is text that isn't UTF-8.
+pub fn env_file_line(name: &str, value: &[u8]) -> Result<String, AppError> {
+    let value = std::str::from_utf8(value).map_err(|_| {
+        AppError::InvalidInput(format!(
+            "{} isn't text, so it can't go in a Docker env file",
+            name
+        ))
+    })?;
+    if value.contains(['\n', '\r']) {
+        return Err(AppError::InvalidInput(format!(
+            "{} contains a line break, which Docker env files can't represent. Use docker secrets instead.",
+            name
+        )));
+    }
+    Ok(format!("{}={}\n", name, value))
+}
+
+fn check_variable(name: &str) -> Result<(), AppError> {
+    if runner::is_env_name(name) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "'{}' can't be used as an environment variable name",
+            name
+        )))
+    }
+}
+
+fn write_failed(path: &Path, e: std::io::Error) -> AppError {
+    AppError::Cli(format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Writes `names` to `path` as a file for `docker run --env-file` (owner-only
+/// permissions). Needs a fresh confirmation; the access log gets each name.
+#[command]
+pub async fn export_docker_env(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    names: Vec<String>,
+    path: String,
+) -> Result<(), AppError> {
+    if names.is_empty() {
+        return Err(AppError::InvalidInput("No secrets selected".to_string()));
+    }
+    for name in &names {
+        names::check_secret_name(name)?;
+        check_variable(name)?;
+    }
+    let reason = format!("Export {} secret(s) to a Docker env file", names.len());
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let mut contents = Zeroizing::new(String::new());
+    for name in &names {
+        contents.push_str(&env_file_line(name, &cli::get_secret(name).await?)?);
+    }
+    for name in &names {
+        audit::record(&app, name, AccessAction::Read, Some(ENV_FILE_REASON))?;
+    }
+    let path = Path::new(&path);
+    atomic_write(path, contents.as_bytes()).map_err(|e| write_failed(path, e))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComposeMode {
+    /// The service loads an env file.
+    Environment,
+    /// Each value is a docker secret, mounted at `/run/secrets/<VAR>`.
+    Secrets,
+}
+
+/// A double-quoted YAML scalar.
+fn yaml_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a compose override at `path` giving `service` the secrets of the
+/// registered `project`, under the project's variable names. The values go to
+/// files in a private (0700) temp directory that is shredded when the app quits;
+/// the override only refers to them, through `env_file` or as docker secrets
+/// depending on `mode`. Returns the temp directory. Needs a fresh confirmation;
+/// the access log gets each name.
+#[command]
+pub async fn generate_compose_override(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    project: String,
+    service: String,
+    path: String,
+    mode: ComposeMode,
+) -> Result<PathBuf, AppError> {
+    if service.is_empty()
+        || !service
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+    {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' isn't a compose service name",
+            service
+        )));
+    }
+    let env = projects::get_project_env(app.clone(), project.clone()).await?;
+    if !env.broken.is_empty() {
+        let missing: Vec<&str> = env.broken.iter().map(|e| e.secret.as_str()).collect();
+        return Err(AppError::InvalidInput(format!(
+            "Project {} refers to secrets that no longer exist: {}",
+            project,
+            missing.join(", ")
+        )));
+    }
+    if env.env.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "Project {} has no secrets",
+            project
+        )));
+    }
+    for variable in env.env.keys() {
+        check_variable(variable)?;
+    }
+    let reason = format!("Export {} secret(s) for docker compose", env.env.len());
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let mut values: BTreeMap<&str, Zeroizing<Vec<u8>>> = BTreeMap::new();
+    for (variable, secret) in &env.env {
+        values.insert(variable, cli::get_secret(secret).await?);
+    }
+    let dir = shred::private_temp_dir("vibesafe-compose")
+        .map_err(|e| AppError::Cli(format!("Failed to create a temp directory: {}", e)))?;
+    app.state::<TempFiles>().register(dir.clone());
+
+    let mut service_entry = String::new();
+    let mut top_level = String::new();
+    match mode {
+        ComposeMode::Environment => {
+            let mut contents = Zeroizing::new(String::new());
+            for (variable, value) in &values {
+                contents.push_str(&env_file_line(variable, value)?);
+            }
+            let env_file = dir.join(format!("{}.env", service));
+            atomic_write(&env_file, contents.as_bytes()).map_err(|e| write_failed(&env_file, e))?;
+            service_entry.push_str(&format!(
+                "    env_file:\n      - {}\n",
+                yaml_string(&env_file.to_string_lossy())
+            ));
+        }
+        ComposeMode::Secrets => {
+            service_entry.push_str("    secrets:\n");
+            top_level.push_str("secrets:\n");
+            for (variable, value) in &values {
+                let file = dir.join(variable);
+                atomic_write(&file, value).map_err(|e| write_failed(&file, e))?;
+                service_entry.push_str(&format!("      - {}\n", variable));
+                top_level.push_str(&format!(
+                    "  {}:\n    file: {}\n",
+                    variable,
+                    yaml_string(&file.to_string_lossy())
+                ));
+            }
+        }
+    }
+
+    for secret in env.env.values() {
+        audit::record(&app, secret, AccessAction::Read, Some(COMPOSE_REASON))?;
+    }
+    let contents = format!(
+        "# Generated by VibeSafe. The files it refers to are deleted when VibeSafe quits.\nservices:\n  {}:\n{}{}",
+        yaml_string(&service),
+        service_entry,
+        top_level
+    );
+    let path = Path::new(&path);
+    atomic_write(path, contents.as_bytes()).map_err(|e| write_failed(path, e))?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn env_file_lines_keep_the_value_literal() {
+        assert_eq!(
+            env_file_line("A", b"\"quoted\" = 'x' $HOME #c").unwrap(),
+            "A=\"quoted\" = 'x' $HOME #c\n"
+        );
+        assert_eq!(env_file_line("A", b"").unwrap(), "A=\n");
+    }
+
+    #[test]
+    fn env_file_lines_refuse_line_breaks_and_binary() {
+        assert!(env_file_line("A", b"one\ntwo").is_err());
+        assert!(env_file_line("A", b"one\rtwo").is_err());
+        assert!(env_file_line("A", b"\xff").is_err());
+    }
+
+    #[test]
+    fn variables_must_be_env_names() {
+        assert!(check_variable("API_KEY").is_ok());
+        assert!(check_variable("API-KEY").is_err());
+        assert!(check_variable("1KEY").is_err());
+    }
+
+    const YAML_CASES: &[(&str, &str)] = &[
+        ("/tmp/a b/x.env", "\"/tmp/a b/x.env\""),
+        ("say \"hi\"", "\"say \\\"hi\\\"\""),
+        ("C:\\dir\\", "\"C:\\\\dir\\\\\""),
+        ("tab\there\nnl", "\"tab\\x09here\\x0anl\""),
+        ("# not: a {comment}", "\"# not: a {comment}\""),
+        ("ünï", "\"ünï\""),
+    ];
+
+    #[test]
+    fn yaml_strings_escape_quotes_backslashes_and_controls() {
+        for (text, quoted) in YAML_CASES {
+            assert_eq!(yaml_string(text), *quoted);
+        }
+    }
+
+    /// Loads each string with PyYAML, where it's installed, and checks it reads
+    /// back unchanged.
+    #[test]
+    fn yaml_parsers_read_back_the_exact_string() {
+        let installed = Command::new("python3")
+            .args(["-c", "import yaml"])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !installed {
+            return;
+        }
+        for (text, _) in YAML_CASES {
+            let output = Command::new("python3")
+                .args([
+                    "-c",
+                    "import sys, yaml; sys.stdout.write(yaml.safe_load(sys.argv[1])['v'])",
+                ])
+                .arg(format!("v: {}", yaml_string(text)))
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{:?}", text);
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), *text);
+        }
+    }
+}
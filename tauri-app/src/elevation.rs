@@ -0,0 +1,143 @@
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::passkeys;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::api::dialog::blocking::ask;
+use tauri::{command, AppHandle, State, Window};
+use uuid::Uuid;
+
+/// How long an elevation token stays valid after the confirmation.
+const ELEVATION_TTL: Duration = Duration::from_secs(60);
+
+/// Commands that need a fresh confirmation on top of the usual checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// `rotate_keypair`: re-encrypts the whole store.
+    RotateKeypair,
+    /// `migrate_env_file`: shreds the original `.env`.
+    MigrateEnvFile,
+    /// `restore_snapshot`: replaces the store with an earlier copy.
+    RestoreSnapshot,
+}
+
+impl Operation {
+    fn describe(self) -> &'static str {
+        match self {
+            Operation::RotateKeypair => "rotate the VibeSafe key pair",
+            Operation::MigrateEnvFile => "import a .env file and delete the original",
+            Operation::RestoreSnapshot => "roll the store back to a snapshot",
+        }
+    }
+}
+
+/// Outstanding tokens from `require_elevation`, each good for one call of the
+/// operation it was issued for.
+#[derive(Default)]
+pub struct Elevations(Mutex<HashMap<String, (Operation, Instant)>>);
+
+impl Elevations {
+    /// Consumes `token`. It must have been issued for `operation` within the last
+    /// minute; a token presented for the wrong operation is burnt as well.
+    pub fn redeem(&self, token: &str, operation: Operation) -> Result<(), AppError> {
+        let mut tokens = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        tokens.retain(|_, (_, issued_at)| issued_at.elapsed() < ELEVATION_TTL);
+        match tokens.remove(token) {
+            Some((issued_for, _)) if issued_for == operation => Ok(()),
+            _ => Err(AppError::ElevationRequired),
+        }
+    }
+
+    /// A new single-use token for `operation`.
+    fn issue(&self, operation: Operation) -> String {
+        let token = Uuid::new_v4().to_string();
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(token.clone(), (operation, Instant::now()));
+        token
+    }
+}
+
+/// Asks the user to authenticate again and returns a single-use token for
+/// `operation`, valid for 60 seconds. Uses the platform backend; where there is
+/// none the CLI's passkey is tested instead, and without a passkey a native
+/// confirmation dialog is the best available check.
+#[command]
+pub async fn require_elevation(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    elevations: State<'_, Elevations>,
+    operation: Operation,
+) -> Result<String, AppError> {
+    let reason = format!("Confirm to {}", operation.describe());
+    match auth::confirm_presence(provider.inner().clone(), window.clone(), reason.clone()).await {
+        Err(AppError::AuthUnavailable(_)) => {
+            if crate::cached_status(&app, false).await?.passkey_enabled {
+                passkeys::test_touchid().await?;
+            } else {
+                let confirmed = tauri::async_runtime::spawn_blocking(move || {
+                    ask(Some(&window), "Confirm", reason)
+                })
+                .await
+                .map_err(|e| {
+                    AppError::AuthUnavailable(format!("Confirmation task failed: {}", e))
+                })?;
+                if !confirmed {
+                    return Err(AppError::AuthCancelled);
+                }
+            }
+        }
+        other => other?,
+    }
+
+    Ok(elevations.issue(operation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_single_use() {
+        let elevations = Elevations::default();
+        let token = elevations.issue(Operation::RotateKeypair);
+        elevations.redeem(&token, Operation::RotateKeypair).unwrap();
+        assert!(matches!(
+            elevations.redeem(&token, Operation::RotateKeypair),
+            Err(AppError::ElevationRequired)
+        ));
+    }
+
+    #[test]
+    fn a_token_for_another_operation_is_burnt() {
+        let elevations = Elevations::default();
+        let token = elevations.issue(Operation::MigrateEnvFile);
+        assert!(elevations
+            .redeem(&token, Operation::RestoreSnapshot)
+            .is_err());
+        assert!(elevations
+            .redeem(&token, Operation::MigrateEnvFile)
+            .is_err());
+    }
+
+    #[test]
+    fn unknown_and_expired_tokens_are_refused() {
+        let elevations = Elevations::default();
+        assert!(elevations.redeem("", Operation::RotateKeypair).is_err());
+
+        let token = elevations.issue(Operation::RotateKeypair);
+        let issued_at = Instant::now().checked_sub(ELEVATION_TTL);
+        if let Some(issued_at) = issued_at {
+            elevations.0.lock().unwrap().get_mut(&token).unwrap().1 = issued_at;
+            assert!(matches!(
+                elevations.redeem(&token, Operation::RotateKeypair),
+                Err(AppError::ElevationRequired)
+            ));
+        }
+    }
+}
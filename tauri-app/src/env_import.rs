@@ -0,0 +1,467 @@
+use crate::atomic::atomic_write;
+use crate::cache::StoreCache;
+use crate::elevation::{Elevations, Operation};
+use crate::error::AppError;
+use crate::hooks::{self, HookEvent};
+use crate::projects::ProjectSecret;
+use crate::state::{self, VaultState};
+use crate::{cli, metadata, names, shred};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+/// How far below the chosen directory `.env` files are looked for.
+const MAX_SCAN_DEPTH: usize = 4;
+/// Directories that hold dependencies or VCS data rather than project config.
+const SKIPPED_DIRS: &[&str] = &["node_modules", ".git", "target", ".venv", "venv"];
+
+/// One `KEY=value` line from a `.env` file.
+pub struct EnvEntry {
+    pub key: String,
+    pub value: Zeroizing<String>,
+}
+
+/// Parses dotenv syntax without evaluating it: blank lines and `#` comments are
+/// skipped, an `export ` prefix is dropped, single quotes are literal, double
+/// quotes understand `\n`, `\"` and `\\`, and unquoted values end at ` #`.
+/// Interpolation (`${OTHER}`, `$OTHER`) is kept as written, since expanding it
+/// would need the rest of the environment. Lines without `=` are ignored.
+pub fn parse_env(contents: &str) -> Vec<EnvEntry> {
+    let mut entries = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let (key, raw) = match line.split_once('=') {
+            Some((key, raw)) => (key.trim(), raw.trim()),
+            None => continue,
+        };
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            continue;
+        }
+        entries.push(EnvEntry {
+            key: key.to_string(),
+            value: parse_value(raw),
+        });
+    }
+    entries
+}
+
+fn parse_value(raw: &str) -> Zeroizing<String> {
+    let mut value = Zeroizing::new(String::with_capacity(raw.len()));
+    if let Some(rest) = raw.strip_prefix('\'') {
+        value.push_str(rest.split('\'').next().unwrap_or(rest));
+    } else if let Some(rest) = raw.strip_prefix('"') {
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some(other) => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                    None => value.push('\\'),
+                },
+                other => value.push(other),
+            }
+        }
+    } else {
+        let end = raw.find(" #").unwrap_or(raw.len());
+        value.push_str(raw[..end].trim_end());
+    }
+    value
+}
+
+fn is_env_file(name: &str) -> bool {
+    name == ".env" || name.starts_with(".env.")
+}
+
+fn find_env_files(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Symlinks are not followed, so a link back up the tree can't loop.
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+        if file_type.is_dir() {
+            if depth < MAX_SCAN_DEPTH && !SKIPPED_DIRS.contains(&name.as_str()) {
+                find_env_files(&entry.path(), depth + 1, found);
+            }
+        } else if file_type.is_file() && is_env_file(&name) {
+            found.push(entry.path());
+        }
+    }
+}
+
+/// The vault name an imported key gets: the project name in upper case with
+/// anything the CLI doesn't allow replaced by `_`, then the key.
+fn vault_name(project: Option<&str>, key: &str) -> String {
+    match project.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(project) => {
+            let prefix: String = project
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("{}_{}", prefix, key)
+        }
+        None => key.to_string(),
+    }
+}
+
+fn read_env_file(path: &Path) -> Result<Vec<EnvEntry>, AppError> {
+    let contents =
+        Zeroizing::new(fs::read_to_string(path).map_err(|e| {
+            AppError::InvalidInput(format!("Can't read {}: {}", path.display(), e))
+        })?);
+    Ok(parse_env(&contents))
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvKey {
+    pub key: String,
+    /// The name it would be imported as.
+    pub secret: String,
+    /// The vault already has `secret` with a different value.
+    pub conflict: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvFile {
+    pub path: PathBuf,
+    pub keys: Vec<EnvKey>,
+}
+
+/// Lists `.env`, `.env.local` and `.env.*` files under `path` with the keys each
+/// defines. Files are only read; values stay in the backend. Keys whose vault
+/// name (prefixed with `project` when given) already exists are decrypted to
+/// compare, so this may prompt for the passkey.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn scan_project_for_env_files(
+    app: AppHandle,
+    path: String,
+    project: Option<String>,
+) -> Result<Vec<EnvFile>, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "{} isn't a directory",
+            path
+        )));
+    }
+    let mut paths = Vec::new();
+    find_env_files(root, 0, &mut paths);
+    paths.sort();
+
+    let existing: HashSet<String> = crate::cached_secrets(&app, false)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    let mut files = Vec::new();
+    for path in paths {
+        let mut keys = Vec::new();
+        for entry in read_env_file(&path)? {
+            let secret = vault_name(project.as_deref(), &entry.key);
+            let conflict = if existing.contains(&secret) {
+                let stored = cli::get_secret(&secret).await?;
+                &stored[..] != entry.value.as_bytes()
+            } else {
+                false
+            };
+            keys.push(EnvKey {
+                key: entry.key,
+                secret,
+                conflict,
+            });
+        }
+        files.push(EnvFile { path, keys });
+    }
+    Ok(files)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportFailure {
+    pub key: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OriginalFile {
+    /// Left as it was, because something wasn't imported.
+    Kept,
+    /// Replaced with comments pointing at VibeSafe.
+    Rewritten,
+    Deleted,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrateReport {
+    /// Vault names that were added.
+    pub imported: Vec<String>,
+    /// Already in the vault with the same value.
+    pub unchanged: Vec<String>,
+    /// In the vault with a different value; left alone.
+    pub conflicts: Vec<String>,
+    pub failed: Vec<ImportFailure>,
+    pub original: OriginalFile,
+}
+
+fn pointer_file(report: &MigrateReport) -> String {
+    let mut contents = format!(
+        "# Moved to VibeSafe on {}. Values are no longer stored here.\n\
+         # Read them with `vibesafe get NAME`:\n",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    for name in report.imported.iter().chain(&report.unchanged) {
+        contents.push_str(&format!("#   {}\n", name));
+    }
+    contents
+}
+
+/// Imports every entry of the `.env` file at `path`, prefixing names with
+/// `project` when given; if that is a registered project its mapping gains the
+/// imported secrets under their original variable names. Existing secrets are
+/// never overwritten. Once everything is in the vault the original is shredded
+/// (`delete_original`) or shredded and replaced with a comment-only file that
+/// points at VibeSafe; if anything wasn't imported it is kept. Needs a token from
+/// `require_elevation` for `migrate_env_file`.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn migrate_env_file(
+    app: AppHandle,
+    elevations: State<'_, Elevations>,
+    path: String,
+    project: Option<String>,
+    delete_original: bool,
+    elevation_token: String,
+) -> Result<MigrateReport, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    elevations.redeem(&elevation_token, Operation::MigrateEnvFile)?;
+    let path = PathBuf::from(path);
+    if !path
+        .file_name()
+        .is_some_and(|name| is_env_file(&name.to_string_lossy()))
+    {
+        return Err(AppError::InvalidInput(format!(
+            "{} isn't a .env file",
+            path.display()
+        )));
+    }
+    let entries = read_env_file(&path)?;
+    let existing: HashSet<String> = crate::cached_secrets(&app, true)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+
+    let mut report = MigrateReport {
+        imported: Vec::new(),
+        unchanged: Vec::new(),
+        conflicts: Vec::new(),
+        failed: Vec::new(),
+        original: OriginalFile::Kept,
+    };
+    let mut mapped = Vec::new();
+    for entry in entries {
+        let secret = vault_name(project.as_deref(), &entry.key);
+        let result = import_entry(&secret, &entry, existing.contains(&secret)).await;
+        match result {
+            Ok(Imported::Added) => report.imported.push(secret.clone()),
+            Ok(Imported::Unchanged) => report.unchanged.push(secret.clone()),
+            Ok(Imported::Conflict) => {
+                report.conflicts.push(secret);
+                continue;
+            }
+            Err(error) => {
+                report.failed.push(ImportFailure {
+                    key: entry.key,
+                    error: error.to_string(),
+                });
+                continue;
+            }
+        }
+        mapped.push(ProjectSecret {
+            secret,
+            env_name: entry.key,
+        });
+    }
+    app.state::<StoreCache>().invalidate();
+    for name in &report.imported {
+        hooks::fire(&app, HookEvent::Added, name, "migrate_env_file");
+    }
+
+    if let Some(project) = &project {
+        metadata::update(&app, |metadata| {
+            if let Some(project) = metadata.projects.get_mut(project) {
+                for entry in mapped {
+                    if !project.secrets.iter().any(|s| s.secret == entry.secret) {
+                        project.secrets.push(entry);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    if report.conflicts.is_empty() && report.failed.is_empty() {
+        shred::secure_delete(&path)
+            .map_err(|e| AppError::Cli(format!("Failed to delete {}: {}", path.display(), e)))?;
+        report.original = if delete_original {
+            OriginalFile::Deleted
+        } else {
+            atomic_write(&path, pointer_file(&report).as_bytes()).map_err(|e| {
+                AppError::Cli(format!("Failed to rewrite {}: {}", path.display(), e))
+            })?;
+            OriginalFile::Rewritten
+        };
+    }
+    Ok(report)
+}
+
+enum Imported {
+    Added,
+    Unchanged,
+    Conflict,
+}
+
+async fn import_entry(secret: &str, entry: &EnvEntry, exists: bool) -> Result<Imported, AppError> {
+    names::check_secret_name(secret)?;
+    if exists {
+        let stored = cli::get_secret(secret).await?;
+        return Ok(if &stored[..] == entry.value.as_bytes() {
+            Imported::Unchanged
+        } else {
+            Imported::Conflict
+        });
+    }
+    if entry.value.contains('\n') {
+        return Err(AppError::InvalidInput(
+            "Multi-line values can't be passed to the CLI".to_string(),
+        ));
+    }
+    let mut input = Zeroizing::new(Vec::with_capacity(entry.value.len() + 1));
+    input.extend_from_slice(entry.value.as_bytes());
+    input.push(b'\n');
+    cli::add_with_stdin(secret, &input).await?;
+    Ok(Imported::Added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(contents: &str) -> Vec<(String, String)> {
+        parse_env(contents)
+            .into_iter()
+            .map(|entry| (entry.key, entry.value.to_string()))
+            .collect()
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn comments_blank_lines_and_export_are_skipped() {
+        let contents = "# database\n\n  export DB_URL=postgres://db\nexport  PORT=5432\n\
+                        NOT A LINE\n=empty\nBAD KEY=1\n";
+        assert_eq!(
+            parsed(contents),
+            [pair("DB_URL", "postgres://db"), pair("PORT", "5432")]
+        );
+    }
+
+    #[test]
+    fn unquoted_values_end_at_an_inline_comment() {
+        assert_eq!(
+            parsed("A=value # note\nB=pa#ss\nC=  spaced  \nD=\nE=x=y\n"),
+            [
+                pair("A", "value"),
+                pair("B", "pa#ss"),
+                pair("C", "spaced"),
+                pair("D", ""),
+                pair("E", "x=y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        assert_eq!(
+            parsed("A='a \\n # b' # note\nB='${HOME}'\nC='unterminated\n"),
+            [
+                pair("A", "a \\n # b"),
+                pair("B", "${HOME}"),
+                pair("C", "unterminated"),
+            ]
+        );
+    }
+
+    #[test]
+    fn double_quotes_understand_escapes() {
+        assert_eq!(
+            parsed(
+                r#"A="line\nnext" # note
+B="say \"hi\" \\ \t"
+C="$OTHER # kept"
+"#
+            ),
+            [
+                pair("A", "line\nnext"),
+                pair("B", "say \"hi\" \\ \\t"),
+                pair("C", "$OTHER # kept"),
+            ]
+        );
+    }
+
+    #[test]
+    fn vault_names_carry_the_project_prefix() {
+        assert_eq!(vault_name(None, "API_KEY"), "API_KEY");
+        assert_eq!(vault_name(Some("  "), "API_KEY"), "API_KEY");
+        assert_eq!(
+            vault_name(Some("my-app.v2"), "API_KEY"),
+            "MY_APP_V2_API_KEY"
+        );
+    }
+
+    #[test]
+    fn env_files_are_found_below_the_skipped_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let app = root.path().join("app");
+        fs::create_dir_all(app.join("node_modules")).unwrap();
+        for file in [".env", ".env.local", "env", ".envrc"] {
+            fs::write(app.join(file), "").unwrap();
+        }
+        fs::write(app.join("node_modules").join(".env"), "").unwrap();
+
+        let mut found = Vec::new();
+        find_env_files(root.path(), 0, &mut found);
+        found.sort();
+        assert_eq!(found, [app.join(".env"), app.join(".env.local")]);
+    }
+}
@@ -0,0 +1,708 @@
+use crate::state::VaultState;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::Value;
+use std::fmt;
+
+/// One field two writers changed differently, with the value each side saw.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct KeyConflict {
+    pub key: String,
+    /// `null` when the caller's base revision is no longer known.
+    pub base: Value,
+    pub ours: Value,
+    pub theirs: Value,
+}
+
+/// Errors returned to the frontend as `{ code, message, details, error }` so it can
+/// branch on `code`. `details` is `null` unless the variant carries structured
+/// data; `error` repeats `message` for callers that read the old string-only shape.
+#[derive(Debug, Clone)]
+pub enum AppError {
+    /// No key pair exists yet; the UI should offer `vibesafe_init`.
+    NotInitialized,
+    /// Neither a bundled CLI nor one on PATH exists.
+    CliNotFound,
+    /// The CLI could not be run or failed for a reason without a more specific variant.
+    Cli(String),
+    /// The CLI exited unsuccessfully. `exit_code` is `None` when it was killed by a signal.
+    CliExit {
+        exit_code: Option<i32>,
+        message: String,
+    },
+    /// Writing to the CLI's stdin failed. The CLI was stopped and reaped; `exit_code`
+    /// is how it ended (`None` when it was killed).
+    StdinWriteFailed {
+        exit_code: Option<i32>,
+        message: String,
+    },
+    /// The CLI's output didn't have the expected shape.
+    UnparseableCliOutput(String),
+    /// A command argument failed validation.
+    InvalidInput(String),
+    /// The secret name is empty.
+    EmptyName,
+    /// The secret name is longer than the CLI accepts, `max` characters.
+    NameTooLong { max: usize },
+    /// The secret name has characters other than letters, digits, `_` and `-`.
+    InvalidNameCharacters,
+    /// The secret name collides with something the CLI or an integration uses;
+    /// carries why.
+    ReservedName(String),
+    /// The platform couldn't resolve one of the app's directories; carries which one.
+    AppDirUnavailable(&'static str),
+    /// The installed CLI is too old for the requested operation.
+    UnsupportedCli(String),
+    /// The CLI can't take a value on stdin; the app won't pass it as an argument.
+    UnsupportedStdin,
+    /// The value spans several lines and the CLI only reads one from its prompt.
+    MultilineValue,
+    /// The CLI stopped at an interactive prompt the app can't answer; carries the prompt.
+    NeedsInteraction(String),
+    /// The user dismissed the confirmation prompt.
+    AuthCancelled,
+    /// Authentication was attempted and refused.
+    AuthDenied,
+    /// Too many refused confirmations in a row; none are tried until the cooldown
+    /// ends, `retry_after_secs` from now.
+    AuthLockedOut { retry_after_secs: u64 },
+    /// The secret is marked read-only and the caller didn't override it; carries its name.
+    ReadOnlySecret(String),
+    /// No biometric or PIN hardware is present. Only Windows Hello reports it.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    AuthHardwareAbsent,
+    /// The hardware exists but the user hasn't set it up (e.g. no Windows Hello PIN).
+    #[cfg_attr(not(windows), allow(dead_code))]
+    AuthNotConfigured,
+    /// The confirmation backend is missing or failed for another reason.
+    AuthUnavailable(String),
+    /// The command isn't valid in the app's current state.
+    InvalidState {
+        current: VaultState,
+        required: Vec<VaultState>,
+    },
+    /// A file changed since the caller loaded it and the edits overlap. `revision`
+    /// is the one on disk; merge and retry against it.
+    ConflictDetected {
+        revision: u64,
+        conflicts: Vec<KeyConflict>,
+    },
+    /// A destructive command was called without a valid token from `require_elevation`.
+    ElevationRequired,
+    /// No enrolled passkey has the given id.
+    PasskeyNotFound(String),
+    /// Removing this passkey would leave the vault without one; needs explicit confirmation.
+    LastPasskey,
+    /// GitHub rejected the token, or it lacks access to the repository's secrets.
+    GitHubForbidden(String),
+    /// The repository doesn't exist or the token can't see it.
+    GitHubNotFound(String),
+    /// GitHub's rate limit was hit. `reset_at` is when it lifts, in Unix seconds.
+    GitHubRateLimited { reset_at: Option<u64> },
+    /// Any other unsuccessful GitHub API response.
+    GitHubApi { status: u16, message: String },
+    /// The value is longer than a QR code can hold.
+    TooLargeForQr { size: usize, max: usize },
+    /// The screen is being recorded or shared; `app` is the capturing app if known.
+    ScreenRecording { app: Option<String> },
+    /// No ssh-agent is reachable; carries why.
+    SshAgentUnavailable(String),
+    /// A shared-secret file was encrypted to a different key than this vault's
+    /// sharing key.
+    WrongRecipient { recipient_fingerprint: String },
+    /// A shared-secret file is malformed or fails authentication; carries why.
+    CorruptShare(String),
+    /// The passphrase doesn't open the share bundle.
+    WrongPassphrase,
+    /// The share bundle's expiry (RFC 3339) has passed; the app won't open it.
+    ShareExpired { expired_at: String },
+    /// Store files are cloud-sync placeholders whose contents aren't on disk.
+    StoreNotDownloaded { files: Vec<String> },
+}
+
+impl AppError {
+    /// Prefixes free-text messages with the step that failed. Typed errors pass
+    /// through unchanged so the frontend can still branch on their code.
+    pub fn context(self, what: &str) -> Self {
+        match self {
+            AppError::Cli(message) => AppError::Cli(format!("{}: {}", what, message)),
+            AppError::CliExit { exit_code, message } => AppError::CliExit {
+                exit_code,
+                message: format!("{}: {}", what, message),
+            },
+            AppError::UnparseableCliOutput(message) => {
+                AppError::UnparseableCliOutput(format!("{}: {}", what, message))
+            }
+            other => other,
+        }
+    }
+
+    /// Structured data for the frontend beyond the message.
+    fn details(&self) -> Option<Value> {
+        match self {
+            AppError::CliExit { exit_code, .. } | AppError::StdinWriteFailed { exit_code, .. } => {
+                Some(serde_json::json!({ "exit_code": exit_code }))
+            }
+            AppError::AppDirUnavailable(directory) => {
+                Some(serde_json::json!({ "directory": directory }))
+            }
+            AppError::InvalidState { current, required } => {
+                Some(serde_json::json!({ "current": current, "required": required }))
+            }
+            AppError::ConflictDetected {
+                revision,
+                conflicts,
+            } => Some(serde_json::json!({ "revision": revision, "conflicts": conflicts })),
+            AppError::GitHubRateLimited { reset_at } => {
+                Some(serde_json::json!({ "reset_at": reset_at }))
+            }
+            AppError::GitHubApi { status, .. } => Some(serde_json::json!({ "status": status })),
+            AppError::NameTooLong { max } => Some(serde_json::json!({ "max": max })),
+            AppError::TooLargeForQr { size, max } => {
+                Some(serde_json::json!({ "size": size, "max": max }))
+            }
+            AppError::ScreenRecording { app } => Some(serde_json::json!({ "app": app })),
+            AppError::AuthLockedOut { retry_after_secs } => {
+                Some(serde_json::json!({ "retry_after_secs": retry_after_secs }))
+            }
+            AppError::WrongRecipient {
+                recipient_fingerprint,
+            } => Some(serde_json::json!({ "recipient_fingerprint": recipient_fingerprint })),
+            AppError::ShareExpired { expired_at } => {
+                Some(serde_json::json!({ "expired_at": expired_at }))
+            }
+            AppError::StoreNotDownloaded { files } => Some(serde_json::json!({ "files": files })),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotInitialized => "NOT_INITIALIZED",
+            AppError::CliNotFound => "CLI_NOT_FOUND",
+            AppError::Cli(_) | AppError::CliExit { .. } => "CLI_ERROR",
+            AppError::StdinWriteFailed { .. } => "STDIN_WRITE_FAILED",
+            AppError::UnparseableCliOutput(_) => "UNPARSEABLE_CLI_OUTPUT",
+            AppError::InvalidInput(_) => "INVALID_INPUT",
+            AppError::EmptyName => "EMPTY_NAME",
+            AppError::NameTooLong { .. } => "NAME_TOO_LONG",
+            AppError::InvalidNameCharacters => "INVALID_NAME_CHARACTERS",
+            AppError::ReservedName(_) => "RESERVED_NAME",
+            AppError::AppDirUnavailable(_) => "APP_DIR_UNAVAILABLE",
+            AppError::UnsupportedCli(_) => "UNSUPPORTED_CLI",
+            AppError::UnsupportedStdin => "UNSUPPORTED_STDIN",
+            AppError::MultilineValue => "MULTILINE_VALUE",
+            AppError::NeedsInteraction(_) => "NEEDS_INTERACTION",
+            AppError::AuthCancelled => "AUTH_CANCELLED",
+            AppError::AuthDenied => "AUTH_DENIED",
+            AppError::AuthLockedOut { .. } => "AUTH_LOCKED_OUT",
+            AppError::ReadOnlySecret(_) => "READ_ONLY_SECRET",
+            AppError::AuthHardwareAbsent => "AUTH_HARDWARE_ABSENT",
+            AppError::AuthNotConfigured => "AUTH_NOT_CONFIGURED",
+            AppError::AuthUnavailable(_) => "AUTH_UNAVAILABLE",
+            AppError::InvalidState { .. } => "INVALID_STATE",
+            AppError::ConflictDetected { .. } => "CONFLICT_DETECTED",
+            AppError::ElevationRequired => "ELEVATION_REQUIRED",
+            AppError::PasskeyNotFound(_) => "PASSKEY_NOT_FOUND",
+            AppError::LastPasskey => "LAST_PASSKEY",
+            AppError::GitHubForbidden(_) => "GITHUB_FORBIDDEN",
+            AppError::GitHubNotFound(_) => "GITHUB_NOT_FOUND",
+            AppError::GitHubRateLimited { .. } => "GITHUB_RATE_LIMITED",
+            AppError::GitHubApi { .. } => "GITHUB_API_ERROR",
+            AppError::TooLargeForQr { .. } => "TOO_LARGE_FOR_QR",
+            AppError::ScreenRecording { .. } => "SCREEN_RECORDING",
+            AppError::SshAgentUnavailable(_) => "SSH_AGENT_UNAVAILABLE",
+            AppError::WrongRecipient { .. } => "WRONG_RECIPIENT",
+            AppError::CorruptShare(_) => "CORRUPT_SHARE",
+            AppError::WrongPassphrase => "WRONG_PASSPHRASE",
+            AppError::ShareExpired { .. } => "SHARE_EXPIRED",
+            AppError::StoreNotDownloaded { .. } => "STORE_NOT_DOWNLOADED",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotInitialized => {
+                write!(f, "VibeSafe is not initialized. Run initialization first.")
+            }
+            AppError::CliNotFound => write!(
+                f,
+                "VibeSafe CLI not found: it isn't bundled with the app or on PATH"
+            ),
+            AppError::AppDirUnavailable(directory) => {
+                write!(f, "Failed to resolve the app {} directory", directory)
+            }
+            AppError::UnsupportedStdin => write!(
+                f,
+                "This VibeSafe CLI can't read a value from stdin. The app won't put the value on \
+                 the command line instead, where other processes and shell history could see it. \
+                 Update the CLI to add secrets from the app."
+            ),
+            AppError::MultilineValue => write!(
+                f,
+                "This VibeSafe CLI reads the value from its prompt, which stops at the first line \
+                 break. Multi-line values need a CLI whose `add` takes --stdin, which reads the \
+                 value to the end of input."
+            ),
+            AppError::EmptyName => write!(f, "Enter a secret name"),
+            AppError::NameTooLong { max } => {
+                write!(f, "Secret names can be at most {} characters", max)
+            }
+            AppError::InvalidNameCharacters => write!(
+                f,
+                "Use only letters, numbers, underscore and hyphen in secret names"
+            ),
+            AppError::Cli(message)
+            | AppError::CliExit { message, .. }
+            | AppError::StdinWriteFailed { message, .. }
+            | AppError::UnparseableCliOutput(message)
+            | AppError::InvalidInput(message)
+            | AppError::ReservedName(message)
+            | AppError::UnsupportedCli(message)
+            | AppError::GitHubForbidden(message)
+            | AppError::GitHubNotFound(message) => write!(f, "{}", message),
+            AppError::NeedsInteraction(prompt) => write!(
+                f,
+                "The VibeSafe CLI asked for input the app can't provide: {}. Run it in a terminal.",
+                prompt
+            ),
+            AppError::AuthCancelled => write!(f, "Authentication was cancelled"),
+            AppError::AuthDenied => write!(f, "Authentication was denied"),
+            AppError::AuthLockedOut { retry_after_secs } => write!(
+                f,
+                "Too many failed attempts. Try again in {} seconds.",
+                retry_after_secs
+            ),
+            AppError::ReadOnlySecret(name) => write!(
+                f,
+                "{} is read-only. Override it explicitly to change or delete it.",
+                name
+            ),
+            AppError::AuthHardwareAbsent => {
+                write!(f, "No biometric or security hardware was found")
+            }
+            AppError::AuthNotConfigured => write!(
+                f,
+                "Biometric sign-in isn't set up for this user. Set it up in system settings first."
+            ),
+            AppError::AuthUnavailable(message) => write!(f, "{}", message),
+            AppError::InvalidState { current, required } => {
+                let required: Vec<String> = required.iter().map(|s| s.to_string()).collect();
+                write!(
+                    f,
+                    "This needs VibeSafe to be {} but it is {}",
+                    required.join(" or "),
+                    current
+                )
+            }
+            AppError::ConflictDetected { conflicts, .. } => {
+                let keys: Vec<&str> = conflicts.iter().map(|c| c.key.as_str()).collect();
+                write!(
+                    f,
+                    "Settings were changed elsewhere: {}. Reload and try again.",
+                    keys.join(", ")
+                )
+            }
+            AppError::ElevationRequired => write!(
+                f,
+                "This needs a fresh confirmation. Confirm again and retry within a minute."
+            ),
+            AppError::PasskeyNotFound(id) => write!(f, "No passkey with id '{}'", id),
+            AppError::LastPasskey => write!(
+                f,
+                "This is the last registered passkey. Removing it turns off passkey protection."
+            ),
+            AppError::GitHubRateLimited { .. } => write!(
+                f,
+                "GitHub's API rate limit was reached. Try again once it resets."
+            ),
+            AppError::GitHubApi { status, message } => {
+                write!(f, "GitHub returned {}: {}", status, message)
+            }
+            AppError::TooLargeForQr { size, max } => write!(
+                f,
+                "The value is {} bytes; a QR code holds at most {}",
+                size, max
+            ),
+            AppError::ScreenRecording { app } => match app {
+                Some(app) => write!(f, "{} is capturing the screen. Stop it and try again.", app),
+                None => write!(f, "The screen is being captured. Stop it and try again."),
+            },
+            AppError::SshAgentUnavailable(reason) => {
+                write!(f, "No ssh-agent is running: {}", reason)
+            }
+            AppError::WrongRecipient {
+                recipient_fingerprint,
+            } => write!(
+                f,
+                "This file was encrypted for another key ({}), not this vault's",
+                recipient_fingerprint
+            ),
+            AppError::CorruptShare(reason) => {
+                write!(f, "The shared secret file is damaged: {}", reason)
+            }
+            AppError::WrongPassphrase => write!(f, "The passphrase is wrong"),
+            AppError::ShareExpired { expired_at } => {
+                write!(f, "This share bundle expired at {}", expired_at)
+            }
+            AppError::StoreNotDownloaded { files } => write!(
+                f,
+                "{} in the VibeSafe store isn't downloaded from cloud sync. Download it and try \
+                 again.",
+                files.join(", ")
+            ),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.to_string();
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &message)?;
+        state.serialize_field("details", &self.details())?;
+        state.serialize_field("error", &message)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(error: AppError, code: &str, message: &str, details: Value) {
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "code": code, "message": message, "details": details, "error": message }),
+            "{:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn cli_errors() {
+        snapshot(
+            AppError::NotInitialized,
+            "NOT_INITIALIZED",
+            "VibeSafe is not initialized. Run initialization first.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::CliNotFound,
+            "CLI_NOT_FOUND",
+            "VibeSafe CLI not found: it isn't bundled with the app or on PATH",
+            Value::Null,
+        );
+        snapshot(
+            AppError::Cli("boom".to_string()),
+            "CLI_ERROR",
+            "boom",
+            Value::Null,
+        );
+        snapshot(
+            AppError::CliExit {
+                exit_code: Some(2),
+                message: "bad".to_string(),
+            },
+            "CLI_ERROR",
+            "bad",
+            json!({ "exit_code": 2 }),
+        );
+        snapshot(
+            AppError::StdinWriteFailed {
+                exit_code: None,
+                message: "pipe".to_string(),
+            },
+            "STDIN_WRITE_FAILED",
+            "pipe",
+            json!({ "exit_code": null }),
+        );
+        snapshot(
+            AppError::UnparseableCliOutput("list".to_string()),
+            "UNPARSEABLE_CLI_OUTPUT",
+            "list",
+            Value::Null,
+        );
+        snapshot(
+            AppError::UnsupportedCli("too old".to_string()),
+            "UNSUPPORTED_CLI",
+            "too old",
+            Value::Null,
+        );
+        snapshot(
+            AppError::UnsupportedStdin,
+            "UNSUPPORTED_STDIN",
+            "This VibeSafe CLI can't read a value from stdin. The app won't put the value on \
+             the command line instead, where other processes and shell history could see it. \
+             Update the CLI to add secrets from the app.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::MultilineValue,
+            "MULTILINE_VALUE",
+            "This VibeSafe CLI reads the value from its prompt, which stops at the first line \
+             break. Multi-line values need a CLI whose `add` takes --stdin, which reads the \
+             value to the end of input.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::NeedsInteraction("Continue?".to_string()),
+            "NEEDS_INTERACTION",
+            "The VibeSafe CLI asked for input the app can't provide: Continue?. Run it in a \
+             terminal.",
+            Value::Null,
+        );
+    }
+
+    #[test]
+    fn input_and_state_errors() {
+        snapshot(
+            AppError::InvalidInput("nope".to_string()),
+            "INVALID_INPUT",
+            "nope",
+            Value::Null,
+        );
+        let name_error = |name: &str| crate::names::check_secret_name(name).unwrap_err();
+        snapshot(
+            name_error(""),
+            "EMPTY_NAME",
+            "Enter a secret name",
+            Value::Null,
+        );
+        snapshot(
+            name_error(&"A".repeat(101)),
+            "NAME_TOO_LONG",
+            "Secret names can be at most 100 characters",
+            json!({ "max": 100 }),
+        );
+        snapshot(
+            name_error("API KEY"),
+            "INVALID_NAME_CHARACTERS",
+            "Use only letters, numbers, underscore and hyphen in secret names",
+            Value::Null,
+        );
+        snapshot(
+            name_error("PATH"),
+            "RESERVED_NAME",
+            "PATH is an environment variable integrations would overwrite",
+            Value::Null,
+        );
+        snapshot(
+            AppError::AppDirUnavailable("config"),
+            "APP_DIR_UNAVAILABLE",
+            "Failed to resolve the app config directory",
+            json!({ "directory": "config" }),
+        );
+        snapshot(
+            AppError::ReadOnlySecret("KEY".to_string()),
+            "READ_ONLY_SECRET",
+            "KEY is read-only. Override it explicitly to change or delete it.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::InvalidState {
+                current: VaultState::Locked,
+                required: vec![VaultState::Ready, VaultState::Uninitialized],
+            },
+            "INVALID_STATE",
+            "This needs VibeSafe to be Ready or Uninitialized but it is Locked",
+            json!({ "current": "Locked", "required": ["Ready", "Uninitialized"] }),
+        );
+        snapshot(
+            AppError::ConflictDetected {
+                revision: 7,
+                conflicts: vec![KeyConflict {
+                    key: "theme".to_string(),
+                    base: json!("system"),
+                    ours: json!("dark"),
+                    theirs: json!("light"),
+                }],
+            },
+            "CONFLICT_DETECTED",
+            "Settings were changed elsewhere: theme. Reload and try again.",
+            json!({
+                "revision": 7,
+                "conflicts": [
+                    { "key": "theme", "base": "system", "ours": "dark", "theirs": "light" }
+                ],
+            }),
+        );
+        snapshot(
+            AppError::StoreNotDownloaded {
+                files: vec!["secrets.json".to_string()],
+            },
+            "STORE_NOT_DOWNLOADED",
+            "secrets.json in the VibeSafe store isn't downloaded from cloud sync. Download it \
+             and try again.",
+            json!({ "files": ["secrets.json"] }),
+        );
+    }
+
+    #[test]
+    fn auth_errors() {
+        snapshot(
+            AppError::AuthCancelled,
+            "AUTH_CANCELLED",
+            "Authentication was cancelled",
+            Value::Null,
+        );
+        snapshot(
+            AppError::AuthDenied,
+            "AUTH_DENIED",
+            "Authentication was denied",
+            Value::Null,
+        );
+        snapshot(
+            AppError::AuthLockedOut {
+                retry_after_secs: 300,
+            },
+            "AUTH_LOCKED_OUT",
+            "Too many failed attempts. Try again in 300 seconds.",
+            json!({ "retry_after_secs": 300 }),
+        );
+        snapshot(
+            AppError::AuthHardwareAbsent,
+            "AUTH_HARDWARE_ABSENT",
+            "No biometric or security hardware was found",
+            Value::Null,
+        );
+        snapshot(
+            AppError::AuthNotConfigured,
+            "AUTH_NOT_CONFIGURED",
+            "Biometric sign-in isn't set up for this user. Set it up in system settings first.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::AuthUnavailable("no polkit".to_string()),
+            "AUTH_UNAVAILABLE",
+            "no polkit",
+            Value::Null,
+        );
+        snapshot(
+            AppError::ElevationRequired,
+            "ELEVATION_REQUIRED",
+            "This needs a fresh confirmation. Confirm again and retry within a minute.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::PasskeyNotFound("pk-1".to_string()),
+            "PASSKEY_NOT_FOUND",
+            "No passkey with id 'pk-1'",
+            Value::Null,
+        );
+        snapshot(
+            AppError::LastPasskey,
+            "LAST_PASSKEY",
+            "This is the last registered passkey. Removing it turns off passkey protection.",
+            Value::Null,
+        );
+        snapshot(
+            AppError::ScreenRecording {
+                app: Some("OBS".to_string()),
+            },
+            "SCREEN_RECORDING",
+            "OBS is capturing the screen. Stop it and try again.",
+            json!({ "app": "OBS" }),
+        );
+        snapshot(
+            AppError::ScreenRecording { app: None },
+            "SCREEN_RECORDING",
+            "The screen is being captured. Stop it and try again.",
+            json!({ "app": null }),
+        );
+    }
+
+    #[test]
+    fn integration_errors() {
+        snapshot(
+            AppError::GitHubForbidden("bad token".to_string()),
+            "GITHUB_FORBIDDEN",
+            "bad token",
+            Value::Null,
+        );
+        snapshot(
+            AppError::GitHubNotFound("no repo".to_string()),
+            "GITHUB_NOT_FOUND",
+            "no repo",
+            Value::Null,
+        );
+        snapshot(
+            AppError::GitHubRateLimited {
+                reset_at: Some(1_700_000_000),
+            },
+            "GITHUB_RATE_LIMITED",
+            "GitHub's API rate limit was reached. Try again once it resets.",
+            json!({ "reset_at": 1_700_000_000u64 }),
+        );
+        snapshot(
+            AppError::GitHubApi {
+                status: 502,
+                message: "Bad Gateway".to_string(),
+            },
+            "GITHUB_API_ERROR",
+            "GitHub returned 502: Bad Gateway",
+            json!({ "status": 502 }),
+        );
+        snapshot(
+            AppError::TooLargeForQr {
+                size: 3000,
+                max: 2953,
+            },
+            "TOO_LARGE_FOR_QR",
+            "The value is 3000 bytes; a QR code holds at most 2953",
+            json!({ "size": 3000, "max": 2953 }),
+        );
+        snapshot(
+            AppError::SshAgentUnavailable("SSH_AUTH_SOCK isn't set".to_string()),
+            "SSH_AGENT_UNAVAILABLE",
+            "No ssh-agent is running: SSH_AUTH_SOCK isn't set",
+            Value::Null,
+        );
+    }
+
+    #[test]
+    fn share_errors() {
+        snapshot(
+            AppError::WrongRecipient {
+                recipient_fingerprint: "ab:cd".to_string(),
+            },
+            "WRONG_RECIPIENT",
+            "This file was encrypted for another key (ab:cd), not this vault's",
+            json!({ "recipient_fingerprint": "ab:cd" }),
+        );
+        snapshot(
+            AppError::CorruptShare("bad tag".to_string()),
+            "CORRUPT_SHARE",
+            "The shared secret file is damaged: bad tag",
+            Value::Null,
+        );
+        snapshot(
+            AppError::WrongPassphrase,
+            "WRONG_PASSPHRASE",
+            "The passphrase is wrong",
+            Value::Null,
+        );
+        snapshot(
+            AppError::ShareExpired {
+                expired_at: "2026-01-01T00:00:00Z".to_string(),
+            },
+            "SHARE_EXPIRED",
+            "This share bundle expired at 2026-01-01T00:00:00Z",
+            json!({ "expired_at": "2026-01-01T00:00:00Z" }),
+        );
+    }
+
+    #[test]
+    fn context_prefixes_only_free_text() {
+        assert_eq!(
+            AppError::Cli("exit 1".to_string())
+                .context("list")
+                .to_string(),
+            "list: exit 1"
+        );
+        assert_eq!(AppError::AuthDenied.context("list").code(), "AUTH_DENIED");
+    }
+}
@@ -0,0 +1,370 @@
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::{cli, names, settings};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use crypto_box::aead::OsRng;
+use crypto_box::PublicKey;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::api::http::{Body, Client, ClientBuilder, HttpRequestBuilder};
+use tauri::{command, AppHandle, State, Window};
+use zeroize::Zeroizing;
+
+const API: &str = "https://api.github.com";
+const API_VERSION: &str = "2022-11-28";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubMapping {
+    pub secret_name: String,
+    /// Name of the Actions secret in the repository.
+    pub gh_secret_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitHubPush {
+    pub secret_name: String,
+    pub gh_secret_name: String,
+    pub ok: bool,
+    pub error: Option<AppError>,
+}
+
+/// The key GitHub wants Actions secrets sealed to, and its id for the upload.
+#[derive(Debug, Deserialize)]
+struct RepoPublicKey {
+    key_id: String,
+    key: String,
+}
+
+/// `owner/name`, in the characters GitHub allows for either part.
+fn check_repo(repo: &str) -> Result<(), AppError> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    };
+    match repo.split_once('/') {
+        Some((owner, name)) if valid_part(owner) && valid_part(name) => Ok(()),
+        _ => Err(AppError::InvalidInput(format!(
+            "'{}' isn't a repository; use owner/name",
+            repo
+        ))),
+    }
+}
+
+/// GitHub's rules for secret names: letters, digits and `_`, not starting with a
+/// digit or `GITHUB_`.
+fn check_gh_secret_name(name: &str) -> Result<(), AppError> {
+    let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && !name.to_ascii_uppercase().starts_with("GITHUB_");
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "'{}' can't be a GitHub Actions secret name",
+            name
+        )))
+    }
+}
+
+/// Encrypts `value` the way GitHub expects Actions secrets: a libsodium sealed
+/// box to the repository's key, base64-encoded.
+pub fn seal_for_github(public_key: &str, value: &[u8]) -> Result<String, AppError> {
+    let key = BASE64
+        .decode(public_key.trim())
+        .ok()
+        .and_then(|bytes| PublicKey::from_slice(&bytes).ok())
+        .ok_or_else(|| AppError::GitHubApi {
+            status: 200,
+            message: "The repository's public key isn't a Curve25519 key".to_string(),
+        })?;
+    key.seal(&mut OsRng, value)
+        .map(|sealed| BASE64.encode(sealed))
+        .map_err(|_| AppError::Cli("Failed to encrypt the value".to_string()))
+}
+
+/// Unix time from the rate-limit headers: `x-ratelimit-reset` for the primary
+/// limit, `retry-after` (seconds from now) for secondary ones.
+fn rate_limit_reset(headers: &tauri::http::header::HeaderMap) -> Option<u64> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    header("x-ratelimit-reset").or_else(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        header("retry-after").map(|seconds| now + seconds)
+    })
+}
+
+/// Turns an unsuccessful response into the matching typed error. GitHub reports
+/// rate limits as 403 or 429, so the headers and message decide between those.
+fn api_error(
+    status: u16,
+    headers: &tauri::http::header::HeaderMap,
+    body: &[u8],
+    what: &str,
+) -> AppError {
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| body.get("message")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+    let exhausted = headers
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0");
+    let rate_limited = status == 429
+        || (status == 403 && (exhausted || message.to_lowercase().contains("rate limit")));
+    match status {
+        _ if rate_limited => AppError::GitHubRateLimited {
+            reset_at: rate_limit_reset(headers),
+        },
+        401 => AppError::GitHubForbidden(format!("GitHub rejected the token: {}", message)),
+        403 => AppError::GitHubForbidden(format!(
+            "The token can't {}; it needs the Secrets write permission: {}",
+            what, message
+        )),
+        404 => AppError::GitHubNotFound(format!(
+            "Couldn't {}: the repository doesn't exist or the token can't see it",
+            what
+        )),
+        status => AppError::GitHubApi { status, message },
+    }
+}
+
+/// Sends one API request and returns the body of a successful response.
+async fn send(
+    client: &Client,
+    token: &str,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+    what: &str,
+) -> Result<Vec<u8>, AppError> {
+    let failed = |e: tauri::api::Error| AppError::Cli(format!("Failed to {}: {}", what, e));
+    let mut request = HttpRequestBuilder::new(method, format!("{}{}", API, path))
+        .and_then(|request| request.header("Accept", "application/vnd.github+json"))
+        .and_then(|request| request.header("X-GitHub-Api-Version", API_VERSION))
+        .and_then(|request| request.header("User-Agent", "VibeSafe"))
+        .and_then(|request| request.header("Authorization", format!("Bearer {}", token)))
+        .map_err(failed)?
+        .timeout(REQUEST_TIMEOUT);
+    if let Some(body) = body {
+        request = request.body(Body::Json(body));
+    }
+    let response = client.send(request).await.map_err(failed)?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await.map_err(failed)?.data;
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(api_error(status.as_u16(), &headers, &body, what))
+    }
+}
+
+async fn push_one(
+    app: &AppHandle,
+    client: &Client,
+    token: &str,
+    repo: &str,
+    key: &RepoPublicKey,
+    mapping: &GitHubMapping,
+) -> Result<(), AppError> {
+    let sealed = {
+        let value = cli::get_secret(&mapping.secret_name).await?;
+        seal_for_github(&key.key, &value)?
+    };
+    send(
+        client,
+        token,
+        "PUT",
+        &format!("/repos/{}/actions/secrets/{}", repo, mapping.gh_secret_name),
+        Some(json!({ "encrypted_value": sealed, "key_id": key.key_id })),
+        &format!("update {}", mapping.gh_secret_name),
+    )
+    .await?;
+    audit::record(
+        app,
+        &mapping.secret_name,
+        AccessAction::Read,
+        Some(&format!("Pushed to GitHub Actions: {}", repo)),
+    )
+}
+
+/// Sets Actions secrets in `repo` (`owner/name`) from stored secrets, one per
+/// mapping. Authenticates with the token stored under the `github_token_secret`
+/// setting, which needs write access to the repository's secrets. Values are
+/// sealed to the repository's public key before they leave the app. Needs a
+/// fresh confirmation; each mapping reports its own result, and the access log
+/// gets every name that was pushed.
+#[command]
+pub async fn push_to_github_actions(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    repo: String,
+    mappings: Vec<GitHubMapping>,
+) -> Result<Vec<GitHubPush>, AppError> {
+    check_repo(&repo)?;
+    if mappings.is_empty() {
+        return Err(AppError::InvalidInput("No secrets selected".to_string()));
+    }
+    for mapping in &mappings {
+        names::check_secret_name(&mapping.secret_name)?;
+        check_gh_secret_name(&mapping.gh_secret_name)?;
+    }
+    let token_secret = settings::load_settings(&app)?.github_token_secret;
+    let has_token = crate::cached_secrets(&app, false)
+        .await?
+        .iter()
+        .any(|secret| secret.name == token_secret);
+    if !has_token {
+        return Err(AppError::InvalidInput(format!(
+            "Store a GitHub token as {} first, or pick another secret in settings",
+            token_secret
+        )));
+    }
+    let reason = format!(
+        "Push {} secret(s) to GitHub Actions in {}",
+        mappings.len(),
+        repo
+    );
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let token = cli::get_secret(&token_secret).await?;
+    let token = Zeroizing::new(
+        String::from_utf8(token.to_vec())
+            .map_err(|_| AppError::InvalidInput(format!("{} isn't a token", token_secret)))?,
+    );
+    audit::record(
+        &app,
+        &token_secret,
+        AccessAction::Read,
+        Some("GitHub Actions push"),
+    )?;
+
+    let client = ClientBuilder::new()
+        .max_redirections(0)
+        .build()
+        .map_err(|e| AppError::Cli(format!("Failed to start the HTTP client: {}", e)))?;
+    let key = send(
+        &client,
+        &token,
+        "GET",
+        &format!("/repos/{}/actions/secrets/public-key", repo),
+        None,
+        &format!("read the secrets key of {}", repo),
+    )
+    .await?;
+    let key: RepoPublicKey = serde_json::from_slice(&key).map_err(|e| AppError::GitHubApi {
+        status: 200,
+        message: format!("Unexpected public key response: {}", e),
+    })?;
+
+    let mut results = Vec::with_capacity(mappings.len());
+    for mapping in mappings {
+        let outcome = push_one(&app, &client, &token, &repo, &key, &mapping).await;
+        results.push(GitHubPush {
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+            secret_name: mapping.secret_name,
+            gh_secret_name: mapping.gh_secret_name,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_box::SecretKey;
+    use tauri::http::header::{HeaderMap, HeaderValue};
+
+    fn repo_key() -> SecretKey {
+        SecretKey::from([7u8; 32])
+    }
+
+    #[test]
+    fn sealed_values_open_with_the_repository_key() {
+        let key = repo_key();
+        let public_key = BASE64.encode(key.public_key().as_bytes());
+        let value = b"sk-live-\x00\xff";
+
+        let sealed = BASE64
+            .decode(seal_for_github(&format!(" {}\n", public_key), value).unwrap())
+            .unwrap();
+        // An ephemeral public key and a MAC precede the ciphertext.
+        assert_eq!(sealed.len(), 32 + 16 + value.len());
+        assert_eq!(key.unseal(&sealed).unwrap(), value);
+        assert!(SecretKey::from([8u8; 32]).unseal(&sealed).is_err());
+
+        let again = BASE64
+            .decode(seal_for_github(&public_key, value).unwrap())
+            .unwrap();
+        assert_ne!(again, sealed);
+    }
+
+    #[test]
+    fn malformed_public_keys_are_rejected() {
+        for public_key in ["", "not base64!", &BASE64.encode([1u8; 31])] {
+            assert!(matches!(
+                seal_for_github(public_key, b"v"),
+                Err(AppError::GitHubApi { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn repository_and_secret_names_are_checked() {
+        assert!(check_repo("octo-org/my.repo_1").is_ok());
+        for repo in ["octo", "octo/", "/repo", "octo/re po", "a/b/c"] {
+            assert!(check_repo(repo).is_err(), "{}", repo);
+        }
+        assert!(check_gh_secret_name("API_KEY_2").is_ok());
+        for name in ["", "2KEY", "github_token", "API-KEY"] {
+            assert!(check_gh_secret_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rate_limits_are_told_apart_from_permission_errors() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            api_error(
+                403,
+                &headers,
+                br#"{"message":"Resource not accessible"}"#,
+                "x"
+            ),
+            AppError::GitHubForbidden(_)
+        ));
+        assert!(matches!(
+            api_error(
+                403,
+                &headers,
+                br#"{"message":"API rate limit exceeded"}"#,
+                "x"
+            ),
+            AppError::GitHubRateLimited { reset_at: None }
+        ));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1700000000"));
+        assert!(matches!(
+            api_error(403, &headers, b"", "x"),
+            AppError::GitHubRateLimited {
+                reset_at: Some(1700000000)
+            }
+        ));
+        assert!(matches!(
+            api_error(404, &HeaderMap::new(), b"", "x"),
+            AppError::GitHubNotFound(_)
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::error::AppError;
+use crate::{cli, paths, settings};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::api::notification::Notification;
+use tauri::{command, AppHandle};
+use tokio::io::AsyncWriteExt;
+
+/// In the app config directory, next to settings.json.
+const HOOKS_FILE: &str = "hooks.json";
+/// In the app data directory. Hook output is sanitized before it's appended.
+const HOOKS_LOG: &str = "hooks.log";
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_TIMEOUT_SECS: u64 = 300;
+/// Output beyond this many bytes per stream isn't logged.
+const MAX_LOGGED_OUTPUT: usize = 64 * 1024;
+/// The only variables a hook inherits; everything else (tokens, VIBESAFE_*) is
+/// dropped.
+const KEPT_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "TMPDIR",
+    "SystemRoot",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookEvent {
+    #[serde(rename = "secret_added")]
+    Added,
+    /// Reserved: nothing changes a value in place yet, so nothing emits it.
+    #[serde(rename = "secret_updated")]
+    Updated,
+    #[serde(rename = "secret_deleted")]
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub id: String,
+    pub events: Vec<HookEvent>,
+    /// Secret names the hook runs for; `*` and `?` are wildcards. All by default.
+    #[serde(default = "any_name")]
+    pub pattern: String,
+    /// Absolute path of the program to run.
+    pub command: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn any_name() -> String {
+    "*".to_string()
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HooksFile {
+    #[serde(default)]
+    hooks: Vec<Hook>,
+}
+
+impl HookEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::Added => "secret_added",
+            HookEvent::Updated => "secret_updated",
+            HookEvent::Deleted => "secret_deleted",
+        }
+    }
+}
+
+impl Hook {
+    fn matches(&self, event: HookEvent, name: &str) -> bool {
+        if !self.events.contains(&event) {
+            return false;
+        }
+        let pattern = regex::escape(&self.pattern)
+            .replace("\\*", ".*")
+            .replace("\\?", ".");
+        Regex::new(&format!("^{}$", pattern)).is_ok_and(|pattern| pattern.is_match(name))
+    }
+}
+
+/// What a hook gets as JSON on stdin. Never the value.
+#[derive(Debug, Serialize)]
+struct HookPayload<'a> {
+    hook: &'a str,
+    event: HookEvent,
+    name: &'a str,
+    /// The command that caused the event, e.g. "add", "delete", "rename".
+    operation: &'a str,
+    /// Unix seconds.
+    timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HookRun {
+    pub id: String,
+    /// `None` when the hook timed out, was killed, or couldn't start.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u128,
+    /// Sanitized output, as written to the hooks log.
+    pub output: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl HookRun {
+    fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+fn hooks_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(paths::app_config_dir(app)?.join(HOOKS_FILE))
+}
+
+/// The configured hooks, checked: unique ids, absolute commands, a sane timeout.
+/// No file means no hooks.
+fn read_hooks(path: &Path) -> Result<Vec<Hook>, AppError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(AppError::Cli(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let hooks = serde_json::from_str::<HooksFile>(&contents)
+        .map_err(|e| AppError::InvalidInput(format!("{} is invalid: {}", path.display(), e)))?
+        .hooks;
+    for (i, hook) in hooks.iter().enumerate() {
+        let problem = if hook.id.is_empty() {
+            Some("has no id")
+        } else if hooks[..i].iter().any(|other| other.id == hook.id) {
+            Some("has a duplicate id")
+        } else if !hook.command.is_absolute() {
+            Some("needs an absolute command path")
+        } else if hook.timeout_secs == 0 || hook.timeout_secs > MAX_TIMEOUT_SECS {
+            Some("has a timeout outside 1-300 seconds")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            return Err(AppError::InvalidInput(format!(
+                "Hook {} in {} {}",
+                i + 1,
+                path.display(),
+                problem
+            )));
+        }
+    }
+    Ok(hooks)
+}
+
+/// Hooks are off until the user turns on the `hooks_enabled` setting, since
+/// they run arbitrary local programs.
+fn enabled(app: &AppHandle) -> bool {
+    settings::load_settings(app).is_ok_and(|settings| settings.hooks_enabled)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn sanitized_lines(output: &[u8]) -> Vec<String> {
+    let output = &output[..output.len().min(MAX_LOGGED_OUTPUT)];
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(cli::sanitize_line)
+        .collect()
+}
+
+async fn execute(hook: &Hook, payload: &HookPayload<'_>) -> HookRun {
+    let started = Instant::now();
+    let mut run = HookRun {
+        id: hook.id.clone(),
+        exit_code: None,
+        timed_out: false,
+        duration_ms: 0,
+        output: Vec::new(),
+        error: None,
+    };
+    let mut command = tokio::process::Command::new(&hook.command);
+    command
+        .args(&hook.args)
+        .env_clear()
+        .envs(
+            KEPT_ENV
+                .iter()
+                .filter_map(|key| Some((key, std::env::var_os(key)?))),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            run.error = Some(format!("Failed to start {}: {}", hook.command.display(), e));
+            return run;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let payload = serde_json::to_vec(payload).unwrap_or_default();
+        // A hook that ignores stdin may exit before reading it.
+        let _ = stdin.write_all(&payload).await;
+    }
+
+    let timeout = Duration::from_secs(hook.timeout_secs);
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => {
+            run.exit_code = output.status.code();
+            run.output = sanitized_lines(&output.stdout);
+            run.output.extend(sanitized_lines(&output.stderr));
+            if !output.status.success() {
+                run.error = Some(match output.status.code() {
+                    Some(code) => format!("Exited with code {}", code),
+                    None => "Was killed by a signal".to_string(),
+                });
+            }
+        }
+        Ok(Err(e)) => run.error = Some(format!("Failed to wait for the hook: {}", e)),
+        // Dropping the future drops the child, which kills it.
+        Err(_) => {
+            run.timed_out = true;
+            run.error = Some(format!("Timed out after {}s", hook.timeout_secs));
+        }
+    }
+    run.duration_ms = started.elapsed().as_millis();
+    run
+}
+
+fn append_log(app: &AppHandle, payload: &HookPayload<'_>, run: &HookRun) {
+    let path = match paths::app_data_dir(app) {
+        Ok(dir) if fs::create_dir_all(&dir).is_ok() => dir.join(HOOKS_LOG),
+        _ => return,
+    };
+    let mut entry = format!(
+        "[{}] {} {} {}: {}\n",
+        payload.timestamp,
+        run.id,
+        payload.event.as_str(),
+        payload.name,
+        run.error.as_deref().unwrap_or("ok")
+    );
+    for line in &run.output {
+        entry.push_str(&format!("    {}\n", line));
+    }
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    if let Ok(mut file) = options.open(&path) {
+        let _ = file.write_all(entry.as_bytes());
+    }
+}
+
+fn notify_failure(app: &AppHandle, run: &HookRun) {
+    let _ = Notification::new(&app.config().tauri.bundle.identifier)
+        .title("VibeSafe hook failed")
+        .body(format!(
+            "{}: {}",
+            run.id,
+            run.error.as_deref().unwrap_or("failed")
+        ))
+        .show();
+}
+
+async fn run_hook(app: &AppHandle, hook: &Hook, payload: &HookPayload<'_>) -> HookRun {
+    let run = execute(hook, payload).await;
+    append_log(app, payload, &run);
+    if !run.succeeded() {
+        notify_failure(app, &run);
+    }
+    run
+}
+
+/// Runs the hooks configured for `event` on `name` in the background, if hooks
+/// are enabled. Called after the change succeeded; a hook can't undo or delay it.
+pub fn fire(app: &AppHandle, event: HookEvent, name: &str, operation: &'static str) {
+    if !enabled(app) {
+        return;
+    }
+    let app = app.clone();
+    let name = name.to_string();
+    tauri::async_runtime::spawn(async move {
+        let hooks = match hooks_path(&app).and_then(|path| read_hooks(&path)) {
+            Ok(hooks) => hooks,
+            Err(error) => {
+                let _ = Notification::new(&app.config().tauri.bundle.identifier)
+                    .title("VibeSafe hooks not run")
+                    .body(error.to_string())
+                    .show();
+                return;
+            }
+        };
+        for hook in hooks.iter().filter(|hook| hook.matches(event, &name)) {
+            let payload = HookPayload {
+                hook: &hook.id,
+                event,
+                name: &name,
+                operation,
+                timestamp: now(),
+            };
+            run_hook(&app, hook, &payload).await;
+        }
+    });
+}
+
+#[derive(Debug, Serialize)]
+pub struct HookList {
+    /// The `hooks_enabled` setting; when off, none of the hooks run.
+    pub enabled: bool,
+    pub config_path: PathBuf,
+    pub hooks: Vec<Hook>,
+}
+
+/// The hooks in hooks.json, and whether hooks are enabled at all.
+#[command]
+pub fn list_hooks(app: AppHandle) -> Result<HookList, AppError> {
+    let path = hooks_path(&app)?;
+    Ok(HookList {
+        enabled: enabled(&app),
+        hooks: read_hooks(&path)?,
+        config_path: path,
+    })
+}
+
+/// Runs hook `id` once with a made-up `TEST_SECRET` event of its first type, the
+/// same way a real event would (scrubbed environment, timeout, log, failure
+/// notification). Fails while hooks are disabled.
+#[command]
+pub async fn test_hook(app: AppHandle, id: String) -> Result<HookRun, AppError> {
+    if !enabled(&app) {
+        return Err(AppError::InvalidInput(
+            "Hooks are disabled. Turn on hooks_enabled in settings first.".to_string(),
+        ));
+    }
+    let hooks = read_hooks(&hooks_path(&app)?)?;
+    let hook = hooks
+        .iter()
+        .find(|hook| hook.id == id)
+        .ok_or_else(|| AppError::InvalidInput(format!("No hook with id '{}'", id)))?;
+    let payload = HookPayload {
+        hook: &hook.id,
+        event: hook.events.first().copied().unwrap_or(HookEvent::Added),
+        name: "TEST_SECRET",
+        operation: "test",
+        timestamp: now(),
+    };
+    Ok(run_hook(&app, hook, &payload).await)
+}
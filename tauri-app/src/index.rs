@@ -0,0 +1,362 @@
+use crate::cli;
+use crate::error::AppError;
+use crate::metadata;
+use crate::SecretInfo;
+use serde::de::{DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserializer, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufReader, Read};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use tauri::{command, AppHandle, Manager, State};
+use tokio::sync::watch;
+
+/// Entries `list_secrets_page` returns without waiting for the rest of the list.
+const FIRST_PAGE: usize = 200;
+/// A progress event goes out every this many entries after the first page.
+const PROGRESS_EVERY: usize = 500;
+const LIST_PROGRESS_EVENT: &str = "vibesafe://list-progress";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListProgress {
+    pub loaded: usize,
+    pub complete: bool,
+    pub error: Option<AppError>,
+}
+
+/// Secrets loaded so far by the incremental list load. Searchable while the load
+/// is still running.
+pub struct SecretIndex {
+    entries: Mutex<Vec<SecretInfo>>,
+    progress: watch::Sender<ListProgress>,
+    loading: AtomicBool,
+}
+
+impl Default for SecretIndex {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+            progress: watch::channel(ListProgress::default()).0,
+            loading: AtomicBool::new(false),
+        }
+    }
+}
+
+impl SecretIndex {
+    fn entries(&self) -> std::sync::MutexGuard<'_, Vec<SecretInfo>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn publish(&self, app: &AppHandle, progress: ListProgress) {
+        self.progress.send_replace(progress.clone());
+        let _ = app.emit_all(LIST_PROGRESS_EVENT, progress);
+    }
+
+    fn first_page(&self) -> Vec<SecretInfo> {
+        self.entries().iter().take(FIRST_PAGE).cloned().collect()
+    }
+
+    /// Loaded secrets whose name contains `query`, ignoring case, limited to
+    /// `members` when given.
+    fn search(&self, query: &str, members: Option<&HashSet<String>>) -> Vec<SecretInfo> {
+        let query = query.to_lowercase();
+        self.entries()
+            .iter()
+            .filter(|secret| secret.name.to_lowercase().contains(&query))
+            .filter(|secret| members.map_or(true, |members| members.contains(&secret.name)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Calls `on_element` for each element of a JSON array as it is read, so only one
+/// element is held in memory at a time.
+struct EachElement<T, F> {
+    on_element: F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T: DeserializeOwned, F: FnMut(T)> DeserializeSeed<'de> for EachElement<T, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: DeserializeOwned, F: FnMut(T)> Visitor<'de> for EachElement<T, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a list of secrets")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(element) = seq.next_element()? {
+            (self.on_element)(element);
+        }
+        Ok(())
+    }
+}
+
+/// Reads `vibesafe list --json` output from `stdout`, handing each secret to
+/// `on_secret` as soon as it is parsed. Returns how many there were.
+fn read_list(
+    stdout: &mut dyn Read,
+    mut on_secret: impl FnMut(SecretInfo),
+) -> Result<usize, AppError> {
+    let unreadable = |e: serde_json::Error| {
+        AppError::UnparseableCliOutput(format!(
+            "Couldn't read the list output of VibeSafe CLI: {}",
+            e
+        ))
+    };
+    let mut loaded = 0;
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(stdout));
+    let each = EachElement {
+        on_element: |secret: SecretInfo| {
+            loaded += 1;
+            on_secret(secret);
+        },
+        marker: PhantomData,
+    };
+    each.deserialize(&mut deserializer).map_err(unreadable)?;
+    deserializer.end().map_err(unreadable)?;
+    Ok(loaded)
+}
+
+/// Reloads the index from `vibesafe list --json` on a background thread, unless a
+/// load is already running.
+fn start_load(app: &AppHandle) {
+    let index = app.state::<SecretIndex>();
+    if index.loading.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    index.entries().clear();
+    index.progress.send_replace(ListProgress::default());
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<SecretIndex>();
+        let result = cli::run_vibesafe_streaming(&["list", "--json"], |stdout| {
+            read_list(stdout, |secret| {
+                let loaded = {
+                    let mut entries = index.entries();
+                    entries.push(secret);
+                    entries.len()
+                };
+                if loaded == FIRST_PAGE || loaded % PROGRESS_EVERY == 0 {
+                    index.publish(
+                        &app,
+                        ListProgress {
+                            loaded,
+                            complete: false,
+                            error: None,
+                        },
+                    );
+                }
+            })
+        });
+
+        let progress = match result {
+            Ok(loaded) => ListProgress {
+                loaded,
+                complete: true,
+                error: None,
+            },
+            Err(e) => ListProgress {
+                loaded: index.entries().len(),
+                complete: true,
+                error: Some(e),
+            },
+        };
+        index.loading.store(false, Ordering::SeqCst);
+        index.publish(&app, progress);
+    });
+}
+
+/// Loads the whole list into the index and returns how many secrets it holds.
+pub async fn prime(app: &AppHandle) -> Result<usize, AppError> {
+    let mut progress = app.state::<SecretIndex>().progress.subscribe();
+    start_load(app);
+
+    let progress = progress
+        .wait_for(|progress| progress.complete)
+        .await
+        .map_err(|e| AppError::Cli(format!("List loading stopped: {}", e)))?
+        .clone();
+    match progress.error {
+        Some(error) => Err(error),
+        None => Ok(progress.loaded),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FirstPage {
+    secrets: Vec<SecretInfo>,
+    index_complete: bool,
+}
+
+/// Starts loading the full list and returns as soon as the first page is in. The
+/// rest is reported through `vibesafe://list-progress` events and is searchable
+/// with `search_secrets` as it arrives.
+#[command]
+pub async fn list_secrets_page(
+    app: AppHandle,
+    index: State<'_, SecretIndex>,
+) -> Result<FirstPage, AppError> {
+    let mut progress = index.progress.subscribe();
+    start_load(&app);
+
+    let progress = progress
+        .wait_for(|progress| progress.complete || progress.loaded >= FIRST_PAGE)
+        .await
+        .map_err(|e| AppError::Cli(format!("List loading stopped: {}", e)))?
+        .clone();
+    if let Some(error) = progress.error {
+        return Err(error);
+    }
+
+    Ok(FirstPage {
+        secrets: index.first_page(),
+        index_complete: progress.complete,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    matches: Vec<SecretInfo>,
+    /// False while the list is still loading, so more matches may follow.
+    index_complete: bool,
+}
+
+/// Case-insensitive name search over whatever `list_secrets_page` has loaded so far.
+/// With `project`, only that project's secrets match (e.g. the one `detect_project`
+/// found for the current directory).
+#[command]
+pub fn search_secrets(
+    app: AppHandle,
+    index: State<'_, SecretIndex>,
+    query: String,
+    project: Option<String>,
+) -> Result<SearchResults, AppError> {
+    let members: Option<HashSet<String>> = match project {
+        Some(project) => {
+            let project = metadata::load(&app)?
+                .projects
+                .remove(&project)
+                .ok_or_else(|| AppError::InvalidInput(format!("No project named '{}'", project)))?;
+            Some(
+                project
+                    .secrets
+                    .into_iter()
+                    .map(|entry| entry.secret)
+                    .collect(),
+            )
+        }
+        None => None,
+    };
+
+    let index_complete = {
+        let progress = index.progress.borrow();
+        progress.complete && progress.error.is_none()
+    };
+    Ok(SearchResults {
+        matches: index.search(&query, members.as_ref()),
+        index_complete,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn list_json(count: usize) -> String {
+        let entries: Vec<String> = (0..count)
+            .map(|i| format!(r#"{{"name": "SECRET_{}", "created_at": null}}"#, i))
+            .collect();
+        format!("[{}]", entries.join(","))
+    }
+
+    fn index_of(count: usize) -> SecretIndex {
+        let index = SecretIndex::default();
+        read_list(&mut list_json(count).as_bytes(), |secret| {
+            index.entries().push(secret)
+        })
+        .unwrap();
+        index
+    }
+
+    /// Counts the secrets parsed before the reader was asked for this part.
+    struct Tail {
+        seen: Rc<Cell<usize>>,
+        parsed_before: Rc<Cell<Option<usize>>>,
+        bytes: std::io::Cursor<Vec<u8>>,
+    }
+
+    impl Read for Tail {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.parsed_before.get().is_none() {
+                self.parsed_before.set(Some(self.seen.get()));
+            }
+            self.bytes.read(buf)
+        }
+    }
+
+    #[test]
+    fn secrets_arrive_before_the_list_ends() {
+        let json = list_json(3 * FIRST_PAGE);
+        let (head, tail) = json.split_at(json.len() / 2);
+        let seen = Rc::new(Cell::new(0));
+        let parsed_before = Rc::new(Cell::new(None));
+        let mut stdout = head.as_bytes().chain(Tail {
+            seen: seen.clone(),
+            parsed_before: parsed_before.clone(),
+            bytes: std::io::Cursor::new(tail.as_bytes().to_vec()),
+        });
+        let loaded = read_list(&mut stdout, |_| seen.set(seen.get() + 1)).unwrap();
+        assert_eq!(loaded, 3 * FIRST_PAGE);
+        assert!(parsed_before.get().unwrap() >= FIRST_PAGE);
+    }
+
+    #[test]
+    fn unreadable_lists_are_reported() {
+        for output in ["", "{}", r#"[{"name": 1}]"#, "[] trailing"] {
+            assert!(
+                matches!(
+                    read_list(&mut output.as_bytes(), |_| {}),
+                    Err(AppError::UnparseableCliOutput(_))
+                ),
+                "{}",
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn the_first_page_is_capped() {
+        assert_eq!(index_of(FIRST_PAGE + 50).first_page().len(), FIRST_PAGE);
+        assert_eq!(index_of(3).first_page().len(), 3);
+    }
+
+    #[test]
+    fn search_ignores_case_and_respects_projects() {
+        let index = index_of(30);
+        let names = |secrets: Vec<SecretInfo>| -> Vec<String> {
+            secrets.into_iter().map(|secret| secret.name).collect()
+        };
+        assert_eq!(
+            names(index.search("secret_2", None)),
+            (std::iter::once(2).chain(20..30))
+                .map(|i| format!("SECRET_{}", i))
+                .collect::<Vec<_>>()
+        );
+        let members = HashSet::from(["SECRET_21".to_string(), "SECRET_5".to_string()]);
+        assert_eq!(names(index.search("2", Some(&members))), ["SECRET_21"]);
+        assert!(index.search("missing", None).is_empty());
+    }
+}
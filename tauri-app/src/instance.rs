@@ -0,0 +1,344 @@
+use crate::askpass::{self, AskpassReply};
+use crate::atomic::atomic_write_json;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State};
+use zeroize::Zeroizing;
+
+/// Emitted in the running instance with a `SecondLaunch` when another copy starts.
+const SECOND_LAUNCH_EVENT: &str = "vibesafe://second-instance";
+/// In the app data directory. Held with an exclusive lock while the app runs.
+const LOCK_FILE: &str = "instance.lock";
+/// Next to the lock: where the running instance listens for later launches.
+const ENDPOINT_FILE: &str = "instance.json";
+/// A handoff is a line of JSON; anything bigger is not from us.
+const MAX_HANDOFF: u64 = 64 * 1024;
+/// How long the askpass helper waits for the user to confirm in the app.
+const ASKPASS_TIMEOUT: Duration = Duration::from_secs(120);
+/// How long a second launch keeps looking for the endpoint, which the first
+/// instance writes just after it takes the lock.
+const HANDOFF_ATTEMPTS: u32 = 20;
+const HANDOFF_RETRY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    /// Proves a connection comes from someone who can read the app data directory.
+    token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondLaunch {
+    /// Its command-line arguments, without the program path.
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+}
+
+/// What a connection asks of the running instance.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Request {
+    /// Another launch, which exits once this is sent.
+    Launch(SecondLaunch),
+    /// The SSH askpass helper wants the stored passphrase for a key. Answered on
+    /// the same connection with an `AskpassReply` line.
+    Askpass { key_path: String },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Handoff {
+    token: String,
+    #[serde(flatten)]
+    request: Request,
+}
+
+/// This process's claim to being the only instance. Dropping it releases the lock.
+pub struct Instance {
+    lock: Option<File>,
+    listener: Option<TcpListener>,
+    token: String,
+    second_launches: AtomicU32,
+}
+
+impl Instance {
+    /// No enforcement, when the lock can't be set up at all.
+    fn unenforced() -> Self {
+        Instance {
+            lock: None,
+            listener: None,
+            token: String::new(),
+            second_launches: AtomicU32::new(0),
+        }
+    }
+}
+
+fn open_lock(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+fn read_endpoint(dir: &Path) -> Option<Endpoint> {
+    let contents = fs::read(dir.join(ENDPOINT_FILE)).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// Connects to the running instance and sends `request` as one line.
+fn send(endpoint: Endpoint, request: Request) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, endpoint.port))?;
+    let handoff = Handoff {
+        token: endpoint.token,
+        request,
+    };
+    let mut line = serde_json::to_vec(&handoff)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    Ok(stream)
+}
+
+/// Hands this launch to the running instance. Best effort: it may be starting up
+/// or shutting down.
+fn hand_off(dir: &Path, args: &[String]) -> bool {
+    let launch = SecondLaunch {
+        args: args.iter().skip(1).cloned().collect(),
+        cwd: std::env::current_dir().ok(),
+    };
+    for _ in 0..HANDOFF_ATTEMPTS {
+        if let Some(endpoint) = read_endpoint(dir) {
+            if send(endpoint, Request::Launch(launch.clone())).is_ok() {
+                return true;
+            }
+        }
+        std::thread::sleep(HANDOFF_RETRY);
+    }
+    false
+}
+
+/// Asks the running instance for the stored passphrase of `key_path`; it
+/// confirms with the user first. `None` when no instance answers.
+pub fn askpass(dir: &Path, key_path: &str) -> Option<AskpassReply> {
+    let request = Request::Askpass {
+        key_path: key_path.to_string(),
+    };
+    let stream = send(read_endpoint(dir)?, request).ok()?;
+    stream.set_read_timeout(Some(ASKPASS_TIMEOUT)).ok()?;
+    let mut line = Zeroizing::new(String::new());
+    BufReader::new(stream.take(MAX_HANDOFF))
+        .read_line(&mut line)
+        .ok()?;
+    serde_json::from_str(&line).ok()
+}
+
+/// Makes this process the only running instance, using an exclusive lock on a
+/// file in the app data directory. If another instance holds it, this launch's
+/// arguments are passed to it (it comes to the front) and the process exits.
+/// Without a usable data directory the app runs unenforced.
+pub fn acquire(data_dir: Option<PathBuf>, args: &[String]) -> Instance {
+    let dir = match data_dir {
+        Some(dir) if fs::create_dir_all(&dir).is_ok() => dir,
+        _ => return Instance::unenforced(),
+    };
+    match claim(&dir, args) {
+        Ok(instance) => instance,
+        // A running instance that doesn't take the handoff is reported in the
+        // exit status.
+        Err(handed_off) => std::process::exit(if handed_off { 0 } else { 1 }),
+    }
+}
+
+/// Takes the lock in `dir` and starts listening, or hands `args` to the
+/// instance holding it and returns whether that worked.
+fn claim(dir: &Path, args: &[String]) -> Result<Instance, bool> {
+    let lock = match open_lock(&dir.join(LOCK_FILE)) {
+        Ok(lock) => lock,
+        Err(_) => return Ok(Instance::unenforced()),
+    };
+    if lock.try_lock_exclusive().is_err() {
+        return Err(hand_off(dir, args));
+    }
+
+    let mut instance = Instance::unenforced();
+    let token = uuid::Uuid::new_v4().to_string();
+    if let Ok(listener) = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        let endpoint = listener.local_addr().map(|addr| Endpoint {
+            port: addr.port(),
+            token: token.clone(),
+        });
+        if let Ok(endpoint) = endpoint {
+            if atomic_write_json(&dir.join(ENDPOINT_FILE), &endpoint).is_ok() {
+                instance.listener = Some(listener);
+                instance.token = token;
+            }
+        }
+    }
+    instance.lock = Some(lock);
+    Ok(instance)
+}
+
+fn read_request(stream: &TcpStream, token: &str) -> Option<Request> {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_HANDOFF))
+        .read_line(&mut line)
+        .ok()?;
+    let handoff: Handoff = serde_json::from_str(&line).ok()?;
+    (handoff.token == token).then_some(handoff.request)
+}
+
+/// Confirms with the user and writes the reply, on its own thread so launches
+/// aren't held up while the prompt is open.
+fn answer_askpass(app: &AppHandle, mut stream: TcpStream, key_path: String) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let reply = tauri::async_runtime::block_on(askpass::answer(&app, &key_path));
+        if let Ok(line) = serde_json::to_string(&reply) {
+            let mut line = Zeroizing::new(line);
+            line.push('\n');
+            let _ = stream.write_all(line.as_bytes());
+        }
+    });
+}
+
+/// Brings the window to the front and emits "vibesafe://second-instance" each
+/// time another copy is launched, so the UI can say the app is already running.
+/// Also answers the SSH askpass helper.
+pub fn listen(app: &AppHandle) {
+    let instance = app.state::<Instance>();
+    let listener = match instance.listener.as_ref().map(TcpListener::try_clone) {
+        Some(Ok(listener)) => listener,
+        _ => return,
+    };
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let instance = app.state::<Instance>();
+            match read_request(&stream, &instance.token) {
+                Some(Request::Launch(launch)) => {
+                    instance.second_launches.fetch_add(1, Ordering::Relaxed);
+                    crate::show_main_window(&app);
+                    let _ = app.emit_all(SECOND_LAUNCH_EVENT, &launch);
+                }
+                Some(Request::Askpass { key_path }) => answer_askpass(&app, stream, key_path),
+                None => {}
+            }
+        }
+    });
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceStatus {
+    /// This process holds the single-instance lock. False only when the app data
+    /// directory is unusable, in which case nothing stops a second copy.
+    pub enforced: bool,
+    pub pid: u32,
+    /// Launches handed to this instance since it started.
+    pub second_launches: u32,
+}
+
+#[command]
+pub fn get_instance_status(instance: State<'_, Instance>) -> InstanceStatus {
+    InstanceStatus {
+        enforced: instance.lock.is_some(),
+        pid: std::process::id(),
+        second_launches: instance.second_launches.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn a_second_launch_is_handed_to_the_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = claim(dir.path(), &args(&["vibesafe"])).unwrap();
+        assert!(first.lock.is_some());
+
+        let handed_off = claim(dir.path(), &args(&["vibesafe", "--hidden"])).err();
+        assert_eq!(handed_off, Some(true));
+        let (stream, _) = first.listener.as_ref().unwrap().accept().unwrap();
+        match read_request(&stream, &first.token) {
+            Some(Request::Launch(launch)) => assert_eq!(launch.args, ["--hidden"]),
+            other => panic!("expected a launch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn handoffs_with_the_wrong_token_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = claim(dir.path(), &args(&["vibesafe"])).unwrap();
+        let endpoint = Endpoint {
+            port: first
+                .listener
+                .as_ref()
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port(),
+            token: "guessed".to_string(),
+        };
+        send(
+            endpoint,
+            Request::Launch(SecondLaunch {
+                args: Vec::new(),
+                cwd: None,
+            }),
+        )
+        .unwrap();
+        let (stream, _) = first.listener.as_ref().unwrap().accept().unwrap();
+        assert!(read_request(&stream, &first.token).is_none());
+    }
+
+    #[test]
+    fn a_stale_lock_file_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        // Left behind by an instance that crashed: files present, lock not held.
+        fs::write(dir.path().join(LOCK_FILE), "").unwrap();
+        atomic_write_json(
+            &dir.path().join(ENDPOINT_FILE),
+            &Endpoint {
+                port: 9,
+                token: "old".to_string(),
+            },
+        )
+        .unwrap();
+
+        let instance = claim(dir.path(), &args(&["vibesafe"])).unwrap();
+        assert!(instance.lock.is_some());
+        let endpoint = read_endpoint(dir.path()).unwrap();
+        assert_eq!(endpoint.token, instance.token);
+        assert_eq!(
+            endpoint.port,
+            instance
+                .listener
+                .as_ref()
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port()
+        );
+    }
+
+    #[test]
+    fn an_unresponsive_instance_fails_the_handoff() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = claim(dir.path(), &args(&["vibesafe"])).unwrap();
+        drop(first.listener);
+        assert_eq!(claim(dir.path(), &args(&["vibesafe"])).err(), Some(false));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::cache::StoreCache;
+use crate::error::AppError;
+use crate::hooks::{self, HookEvent};
+use crate::state::{self, VaultState};
+use crate::{cli, names};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::{ExitStatus, Output};
+use tauri::{command, AppHandle, Manager};
+use zeroize::Zeroizing;
+
+/// Name used when neither the service nor the account has a usable character.
+const FALLBACK_NAME: &str = "KEYCHAIN_ITEM";
+/// Leaves room for a collision suffix under the CLI's 100-character limit.
+const MAX_BASE_LENGTH: usize = 90;
+
+/// A generic password item, identified the way `security find-generic-password`
+/// looks it up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeychainItemRef {
+    pub service: String,
+    pub account: String,
+    /// The keychain file holding the item; `None` searches the default list.
+    pub keychain: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeychainCandidate {
+    #[serde(flatten)]
+    pub item: KeychainItemRef,
+    /// What `import_from_keychain` would call it, given the current store.
+    pub suggested_name: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeychainImportStatus {
+    Imported,
+    /// The user denied access in the Keychain prompt.
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeychainImportResult {
+    #[serde(flatten)]
+    pub item: KeychainItemRef,
+    /// The secret it was stored as, when it was imported.
+    pub name: Option<String>,
+    pub status: KeychainImportStatus,
+    pub detail: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+async fn security(args: &[&str]) -> Result<Output, AppError> {
+    tokio::process::Command::new("/usr/bin/security")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::Cli(format!("Failed to run security: {}", e)))
+}
+
+#[cfg(not(target_os = "macos"))]
+async fn security(_args: &[&str]) -> Result<Output, AppError> {
+    Err(AppError::InvalidInput(
+        "Keychain import is only available on macOS".to_string(),
+    ))
+}
+
+/// An attribute value from `security dump-keychain`: `"text"`, `<NULL>`, or hex
+/// followed by the text in quotes when the value isn't plain ASCII.
+fn attribute_value(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if let Some(hex) = raw.strip_prefix("0x") {
+        let hex = hex.split_whitespace().next()?;
+        let bytes: Option<Vec<u8>> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect();
+        return bytes.map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+    }
+    raw.strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .map(str::to_string)
+}
+
+/// Generic password items (class `genp`) in `security dump-keychain` output.
+/// Without `-d` the dump holds attributes only, so no value is read and nothing
+/// prompts. Items without a service are left out; they can't be looked up.
+pub fn parse_dump(dump: &str) -> Vec<KeychainItemRef> {
+    let mut items = Vec::new();
+    let mut keychain = None;
+    let mut current: Option<(Option<String>, Option<String>)> = None;
+    let mut finish = |current: &mut Option<(Option<String>, Option<String>)>,
+                      keychain: &Option<String>| {
+        if let Some((Some(service), account)) = current.take() {
+            items.push(KeychainItemRef {
+                service,
+                account: account.unwrap_or_default(),
+                keychain: keychain.clone(),
+            });
+        }
+    };
+    for line in dump.lines() {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("keychain: ") {
+            finish(&mut current, &keychain);
+            keychain = attribute_value(path);
+        } else if let Some(class) = line.strip_prefix("class: ") {
+            finish(&mut current, &keychain);
+            if class.trim() == "\"genp\"" {
+                current = Some((None, None));
+            }
+        } else if let Some((service, account)) = current.as_mut() {
+            if let Some(value) = line.strip_prefix("\"svce\"<blob>=") {
+                *service = attribute_value(value);
+            } else if let Some(value) = line.strip_prefix("\"acct\"<blob>=") {
+                *account = attribute_value(value);
+            }
+        }
+    }
+    finish(&mut current, &keychain);
+    items
+}
+
+/// A secret name for `service` and `account`: both upper-cased and joined by
+/// `_`, with anything the CLI doesn't allow (and runs of it) turned into one `_`.
+/// A name already in `taken`, or a reserved one, gets `_2`, `_3`, … appended.
+/// The result is added to `taken`.
+pub fn derive_name(service: &str, account: &str, taken: &mut HashSet<String>) -> String {
+    let mut base = String::new();
+    for c in format!("{}_{}", service, account).chars() {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            base.push(c.to_ascii_uppercase());
+        } else if !base.ends_with('_') {
+            base.push('_');
+        }
+    }
+    let mut base: String = base
+        .trim_matches(['_', '-'])
+        .chars()
+        .take(MAX_BASE_LENGTH)
+        .collect();
+    if base.is_empty() {
+        base = FALLBACK_NAME.to_string();
+    }
+
+    let mut name = base.clone();
+    let mut n = 1;
+    while taken.contains(&name) || names::reserved_reason(&name).is_some() {
+        n += 1;
+        name = format!("{}_{}", base, n);
+    }
+    taken.insert(name.clone());
+    name
+}
+
+async fn existing_names(app: &AppHandle) -> Result<HashSet<String>, AppError> {
+    Ok(crate::cached_secrets(app, true)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect())
+}
+
+/// Generic password items in the user's keychains, optionally only those whose
+/// service contains `service_filter` (case-insensitive). Names and services only:
+/// no value is read, so the Keychain doesn't prompt. macOS only.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn list_keychain_candidates(
+    app: AppHandle,
+    service_filter: Option<String>,
+) -> Result<Vec<KeychainCandidate>, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let output = security(&["dump-keychain"]).await?;
+    if !output.status.success() {
+        return Err(AppError::Cli(format!(
+            "security dump-keychain failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let filter = service_filter
+        .map(|filter| filter.trim().to_lowercase())
+        .filter(|filter| !filter.is_empty());
+
+    let mut taken = existing_names(&app).await?;
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    for item in parse_dump(&String::from_utf8_lossy(&output.stdout)) {
+        let wanted = filter
+            .as_ref()
+            .map_or(true, |filter| item.service.to_lowercase().contains(filter));
+        if wanted && seen.insert(item.clone()) {
+            candidates.push(KeychainCandidate {
+                suggested_name: derive_name(&item.service, &item.account, &mut taken),
+                item,
+            });
+        }
+    }
+    Ok(candidates)
+}
+
+/// `security` reports a denied or dismissed prompt this way.
+fn access_denied(status: ExitStatus, stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    status.code() == Some(128)
+        || [
+            "canceled",
+            "cancelled",
+            "denied",
+            "not allowed",
+            "passphrase you entered",
+        ]
+        .iter()
+        .any(|reason| stderr.contains(reason))
+}
+
+async fn import_one(item: &KeychainItemRef, name: &str) -> Result<KeychainImportStatus, AppError> {
+    let mut args = vec![
+        "find-generic-password",
+        "-s",
+        &item.service,
+        "-a",
+        &item.account,
+        "-w",
+    ];
+    if let Some(keychain) = &item.keychain {
+        args.push(keychain);
+    }
+    // The system shows its own prompt for items the app hasn't been allowed to read.
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = security(&args).await?;
+    let stdout = Zeroizing::new(stdout);
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        if access_denied(status, &stderr) {
+            return Ok(KeychainImportStatus::Skipped);
+        }
+        return Err(AppError::Cli(format!(
+            "security couldn't read the item: {}",
+            stderr.trim()
+        )));
+    }
+
+    let value = stdout.strip_suffix(b"\n").unwrap_or(&stdout);
+    if value.contains(&b'\n') {
+        return Err(AppError::InvalidInput(
+            "Multi-line values can't be passed to the CLI".to_string(),
+        ));
+    }
+    let mut input = Zeroizing::new(Vec::with_capacity(value.len() + 1));
+    input.extend_from_slice(value);
+    input.push(b'\n');
+    cli::add_with_stdin(name, &input).await?;
+    Ok(KeychainImportStatus::Imported)
+}
+
+/// Copies each item's password into a new secret named after its service and
+/// account (see `list_keychain_candidates`), never overwriting an existing one.
+/// Reading a value brings up the Keychain's own prompt; items the user denies are
+/// reported as skipped. Nothing is changed in the Keychain. macOS only.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn import_from_keychain(
+    app: AppHandle,
+    items: Vec<KeychainItemRef>,
+) -> Result<Vec<KeychainImportResult>, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    if items.is_empty() {
+        return Err(AppError::InvalidInput("No items selected".to_string()));
+    }
+    let mut taken = existing_names(&app).await?;
+
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let name = derive_name(&item.service, &item.account, &mut taken);
+        let result = match import_one(&item, &name).await {
+            Ok(status) => KeychainImportResult {
+                name: matches!(status, KeychainImportStatus::Imported).then(|| name),
+                status,
+                detail: None,
+                item,
+            },
+            Err(error) => KeychainImportResult {
+                name: None,
+                status: KeychainImportStatus::Failed,
+                detail: Some(error.to_string()),
+                item,
+            },
+        };
+        results.push(result);
+    }
+    app.state::<StoreCache>().invalidate();
+    for name in results.iter().filter_map(|result| result.name.as_deref()) {
+        hooks::fire(&app, HookEvent::Added, name, "import_from_keychain");
+    }
+    Ok(results)
+}
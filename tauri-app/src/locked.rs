@@ -0,0 +1,195 @@
+use serde::Serialize;
+use tauri::command;
+use zeroize::{Zeroize, Zeroizing};
+
+/// A decrypted value kept in memory the OS is asked not to swap to disk (mlock,
+/// VirtualLock), and wiped before it is unlocked and freed.
+pub struct LockedBuffer {
+    bytes: Zeroizing<Vec<u8>>,
+    guard: Option<region::LockGuard>,
+}
+
+impl LockedBuffer {
+    /// Takes over `bytes` and tries to lock their pages into RAM. Failing to lock
+    /// (a low RLIMIT_MEMLOCK, a sandbox) isn't an error; `is_locked` reports it.
+    pub fn new(bytes: Zeroizing<Vec<u8>>) -> Self {
+        let guard = if bytes.is_empty() {
+            None
+        } else {
+            region::lock(bytes.as_ptr(), bytes.len()).ok()
+        };
+        LockedBuffer { bytes, guard }
+    }
+
+    /// Holds the bytes without trying to lock them.
+    pub fn unlocked(bytes: Zeroizing<Vec<u8>>) -> Self {
+        LockedBuffer { bytes, guard: None }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.guard.is_some()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Wipes while the pages are still locked, then unlocks them.
+    fn wipe(&mut self) {
+        self.bytes.zeroize();
+        self.guard.take();
+    }
+}
+
+impl Drop for LockedBuffer {
+    fn drop(&mut self) {
+        // The Vec frees last.
+        self.wipe();
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemoryProtection {
+    /// What the OS calls locking: "mlock" or "VirtualLock".
+    pub mechanism: &'static str,
+    /// A page could be locked and unlocked just now, so `get_secret` values will
+    /// be kept out of swap.
+    pub can_lock: bool,
+    /// Bytes this process may lock in total (RLIMIT_MEMLOCK's soft limit, or the
+    /// minimum working set on Windows). `None` when unlimited or unknown.
+    pub budget_bytes: Option<u64>,
+    pub unlimited: bool,
+    pub page_size: usize,
+}
+
+/// Locks one page of a throwaway buffer, the same way `LockedBuffer` would.
+fn probe_lock() -> bool {
+    let page = Zeroizing::new(vec![0u8; region::page::size()]);
+    region::lock(page.as_ptr(), page.len()).is_ok()
+}
+
+#[cfg(unix)]
+fn lock_budget() -> (Option<u64>, bool) {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes the struct it's given.
+    if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } != 0 {
+        return (None, false);
+    }
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        (None, true)
+    } else {
+        // rlim_t is u64 on most targets but not all.
+        #[allow(clippy::unnecessary_cast)]
+        (Some(limit.rlim_cur as u64), false)
+    }
+}
+
+#[cfg(windows)]
+fn lock_budget() -> (Option<u64>, bool) {
+    use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessWorkingSetSize};
+
+    let (mut minimum, mut maximum) = (0usize, 0usize);
+    // SAFETY: the pseudo-handle needs no closing, and the call only writes the two sizes.
+    let ok = unsafe { GetProcessWorkingSetSize(GetCurrentProcess(), &mut minimum, &mut maximum) };
+    if ok.as_bool() {
+        (Some(minimum as u64), false)
+    } else {
+        (None, false)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock_budget() -> (Option<u64>, bool) {
+    (None, false)
+}
+
+/// Whether decrypted values can be kept out of swap here, and how much memory
+/// the process may lock, so the UI can warn where they can't. Reads limits and
+/// locks one empty page; changes nothing.
+#[command]
+pub fn check_memory_protection() -> MemoryProtection {
+    let (budget_bytes, unlimited) = lock_budget();
+    MemoryProtection {
+        mechanism: if cfg!(windows) {
+            "VirtualLock"
+        } else {
+            "mlock"
+        },
+        can_lock: probe_lock(),
+        budget_bytes,
+        unlimited,
+        page_size: region::page::size(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, PoisonError};
+
+    /// Held by tests that lock memory, since one of them lowers the limit.
+    static LIMIT: Mutex<()> = Mutex::new(());
+
+    fn secret() -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(b"sk-live-0123456789".to_vec())
+    }
+
+    #[test]
+    fn buffers_lock_where_the_os_allows() {
+        let _limit = LIMIT.lock().unwrap_or_else(PoisonError::into_inner);
+        let buffer = LockedBuffer::new(secret());
+        assert_eq!(buffer.as_bytes(), b"sk-live-0123456789");
+        assert_eq!(buffer.is_locked(), probe_lock());
+
+        assert!(!LockedBuffer::new(Zeroizing::new(Vec::new())).is_locked());
+        let unlocked = LockedBuffer::unlocked(secret());
+        assert!(!unlocked.is_locked());
+        assert_eq!(unlocked.as_bytes(), b"sk-live-0123456789");
+    }
+
+    #[test]
+    fn wiping_zeroes_the_bytes_before_unlocking() {
+        let _limit = LIMIT.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut buffer = LockedBuffer::new(secret());
+        let (ptr, len) = (buffer.bytes.as_ptr(), buffer.bytes.len());
+        buffer.wipe();
+        assert!(buffer.as_bytes().is_empty());
+        assert!(!buffer.is_locked());
+        // SAFETY: the Vec still owns its allocation (only its length was reset),
+        // and every byte of it was initialized.
+        let wiped = unsafe { std::slice::from_raw_parts(ptr, len) };
+        assert!(wiped.iter().all(|&byte| byte == 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn an_exhausted_budget_falls_back_to_unlocked() {
+        let _limit = LIMIT.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit and setrlimit only read and write the struct given.
+        unsafe {
+            assert_eq!(libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit), 0);
+            let exhausted = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: limit.rlim_max,
+            };
+            assert_eq!(libc::setrlimit(libc::RLIMIT_MEMLOCK, &exhausted), 0);
+        }
+        let protection = check_memory_protection();
+        let buffer = LockedBuffer::new(secret());
+        // SAFETY: as above; the soft limit goes back to what it was.
+        unsafe { libc::setrlimit(libc::RLIMIT_MEMLOCK, &limit) };
+
+        assert_eq!(protection.budget_bytes, Some(0));
+        assert!(!protection.unlimited);
+        assert_eq!(buffer.as_bytes(), b"sk-live-0123456789");
+        // CAP_IPC_LOCK (root) ignores the limit.
+        assert_eq!(buffer.is_locked(), protection.can_lock);
+    }
+}
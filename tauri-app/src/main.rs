@@ -3,173 +3,758 @@
     windows_subsystem = "windows"
 )]
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Ordering;
 use tauri::{
-    command, generate_context, generate_handler, Builder, CustomMenuItem, Manager, SystemTray,
-    SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem, Window, WindowBuilder, WindowUrl,
+    command, generate_context, generate_handler, AppHandle, Builder, CustomMenuItem, Manager,
+    RunEvent, State, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
+    SystemTraySubmenu, Window,
 };
-use tauri::api::shell::Command as ShellCommand;
+use zeroize::Zeroizing;
 
-#[derive(Debug, Serialize, Deserialize)]
+mod api_server;
+mod askpass;
+mod atomic;
+mod audit;
+mod auth;
+mod breaches;
+mod bundle;
+mod cache;
+mod claude;
+mod cli;
+mod cli_config;
+mod clipboard;
+mod cloud_sync;
+mod docker;
+mod elevation;
+mod env_import;
+mod error;
+mod github;
+mod health;
+mod hooks;
+mod hygiene;
+mod index;
+mod instance;
+mod keychain;
+mod locked;
+mod metadata;
+mod names;
+mod native_messaging;
+mod passkeys;
+mod paths;
+mod pin;
+mod power;
+mod projects;
+mod qr;
+mod readonly;
+mod rename;
+mod rotation;
+mod runner;
+mod screen;
+mod secret_urls;
+mod selftest;
+mod settings;
+mod share;
+mod shell_export;
+mod shred;
+mod snapshots;
+mod ssh_agent;
+mod state;
+mod stats;
+mod store_location;
+mod store_watch;
+mod templates;
+#[cfg(all(test, unix))]
+mod test_support;
+mod upgrade;
+mod warmup;
+
+use audit::AccessAction;
+use auth::SharedAuthProvider;
+use cache::StoreCache;
+use error::AppError;
+use hooks::HookEvent;
+use locked::LockedBuffer;
+use settings::AppSettings;
+use shred::TempFiles;
+use state::VaultState;
+
+/// Passed by the login autostart entry so the app comes up in the tray only.
+const HIDDEN_FLAG: &str = "--hidden";
+/// Label of the single window declared in tauri.conf.json.
+const MAIN_WINDOW: &str = "main";
+/// Tray status line for the passkey, retitled on every status fetch.
+const TRAY_PASSKEY_ITEM: &str = "status_passkey";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SecretInfo {
     name: String,
     created_at: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AuthProtection {
+    enabled: bool,
+    /// App-side confirmation backend, e.g. "windows-hello" or "polkit".
+    backend: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct VibeSafeStatus {
     initialized: bool,
     key_exists: bool,
     passkey_enabled: bool,
     secrets_count: u32,
     claude_integration: bool,
+    #[serde(default)]
+    auth_protection: AuthProtection,
+    /// Raw passkey fields from newer CLIs; the UI reads `passkey` instead.
+    #[serde(default, skip_serializing)]
+    passkey_type: Option<String>,
+    #[serde(default, skip_serializing)]
+    enrolled_at: Option<String>,
+    #[serde(default, skip_serializing)]
+    device_name: Option<String>,
+    /// Filled in by the app from the fields above.
+    #[serde(skip_deserializing)]
+    passkey: passkeys::PasskeyDetails,
+    /// Filled in by the app: whether the private key needs a passphrase, which
+    /// the CLI only asks for when no passkey is enabled.
+    #[serde(skip_deserializing)]
+    passphrase_protected: bool,
+    /// Filled in by the app; see `state::from_status`.
+    #[serde(skip_deserializing)]
+    state: VaultState,
+    /// Filled in by the app: the cloud sync client the store is in, if any.
+    #[serde(skip_deserializing)]
+    sync_provider: Option<cloud_sync::SyncProvider>,
+    /// Filled in by the app: what to do about the store being synced or not
+    /// downloaded.
+    #[serde(skip_deserializing)]
+    sync_warning: Option<String>,
 }
 
+/// Fetches status. When the list cache is cold too (e.g. the initial load) the list
+/// is fetched concurrently and cached, since the UI asks for it next.
+async fn fetch_status(
+    app: &AppHandle,
+    provider: &SharedAuthProvider,
+) -> Result<VibeSafeStatus, AppError> {
+    let cache = app.state::<StoreCache>();
+    let stdout = if cache.secrets.is_cold() {
+        let generation = cache.secrets.generation();
+        let (status, list) = cli::status_and_list().await?;
+        cache
+            .secrets
+            .store(generation, cli::parse_json(&list, "list").await?);
+        status
+    } else {
+        cli::run_vibesafe_shared(&cli::action_args("status", &[])?).await?
+    };
+    let mut status: VibeSafeStatus = cli::parse_status(&stdout).await?;
+    status.auth_protection = AuthProtection {
+        enabled: status.passkey_enabled,
+        backend: provider.backend().to_string(),
+    };
+    status.passkey = passkeys::PasskeyDetails::from_status(
+        status.passkey_enabled,
+        status.passkey_type.as_deref(),
+        status.enrolled_at.as_deref(),
+        status.device_name.as_deref(),
+    );
+    status.passphrase_protected = !status.passkey_enabled && cli_config::key_encrypted()?;
+    let sync = cloud_sync::status();
+    status.sync_provider = sync.provider;
+    status.sync_warning = sync.warning;
+    let _ = app
+        .tray_handle()
+        .get_item(TRAY_PASSKEY_ITEM)
+        .set_title(status.passkey.summary());
+    if status.initialized {
+        // The store may have been created with the CLI after launch.
+        store_watch::start(app);
+    }
+    Ok(status)
+}
+
+async fn cached_status(app: &AppHandle, force_refresh: bool) -> Result<VibeSafeStatus, AppError> {
+    let provider = app.state::<SharedAuthProvider>().inner().clone();
+    let handle = app.clone();
+    let mut status = cache::cached(
+        app,
+        |cache| &cache.status,
+        force_refresh,
+        move || {
+            let app = handle.clone();
+            let provider = provider.clone();
+            async move { fetch_status(&app, &provider).await }
+        },
+    )
+    .await?;
+    // Decided on every read, since locking doesn't refetch.
+    status.state = state::of(&status, state::is_unlocked());
+    Ok(status)
+}
+
+/// Served from the cache while it is fresh; `force_refresh` always runs the CLI.
 #[command]
-async fn vibesafe_status() -> Result<VibeSafeStatus, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["status", "--json"])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe status: {}", e))?;
+async fn vibesafe_status(
+    app: AppHandle,
+    force_refresh: Option<bool>,
+) -> Result<VibeSafeStatus, AppError> {
+    cached_status(&app, force_refresh.unwrap_or(false)).await
+}
 
-    if output.status.success() {
-        let status_str = String::from_utf8_lossy(&output.stdout);
-        serde_json::from_str(&status_str)
-            .map_err(|e| format!("Failed to parse status JSON: {}", e))
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+async fn list_secrets() -> Result<Vec<SecretInfo>, AppError> {
+    let stdout = cli::run_vibesafe_shared(&cli::action_args("list", &[])?).await?;
+    cli::parse_json(&stdout, "list").await
+}
+
+async fn cached_secrets(app: &AppHandle, force_refresh: bool) -> Result<Vec<SecretInfo>, AppError> {
+    cache::cached(app, |cache| &cache.secrets, force_refresh, list_secrets).await
+}
+
+#[command]
+async fn vibesafe_list(
+    app: AppHandle,
+    force_refresh: Option<bool>,
+) -> Result<Vec<SecretInfo>, AppError> {
+    cached_secrets(&app, force_refresh.unwrap_or(false)).await
+}
+
+/// One part of `hydrate`'s result. Each part fails on its own.
+#[derive(Debug, Serialize)]
+struct Section<T> {
+    data: Option<T>,
+    error: Option<AppError>,
+}
+
+impl<T> From<Result<T, AppError>> for Section<T> {
+    fn from(result: Result<T, AppError>) -> Self {
+        match result {
+            Ok(data) => Self {
+                data: Some(data),
+                error: None,
+            },
+            Err(error) => Self {
+                data: None,
+                error: Some(error),
+            },
+        }
     }
 }
 
+#[derive(Debug, Serialize)]
+struct Hydration {
+    status: Section<VibeSafeStatus>,
+    secrets: Section<Vec<SecretInfo>>,
+    settings: Section<AppSettings>,
+}
+
+/// Everything the UI needs at startup in one call; later changes arrive as events.
+/// On a cold cache status and list are fetched together.
 #[command]
-async fn vibesafe_list() -> Result<Vec<SecretInfo>, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["list", "--json"])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe list: {}", e))?;
+async fn hydrate(app: AppHandle) -> Hydration {
+    let status = cached_status(&app, false).await.into();
+    let secrets = cached_secrets(&app, false).await.into();
 
-    if output.status.success() {
-        let list_str = String::from_utf8_lossy(&output.stdout);
-        serde_json::from_str(&list_str)
-            .map_err(|e| format!("Failed to parse list JSON: {}", e))
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+    Hydration {
+        status,
+        secrets,
+        settings: settings::load_settings(&app).into(),
+    }
+}
+
+/// Largest page `list_secrets_paged` returns, whatever the caller asks for.
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Serialize)]
+struct SecretPage {
+    secrets: Vec<SecretInfo>,
+    total: usize,
+}
+
+/// Orders by creation time, keeping secrets without one at the end.
+fn cmp_created(a: &SecretInfo, b: &SecretInfo, newest_first: bool) -> Ordering {
+    match (&a.created_at, &b.created_at) {
+        (Some(x), Some(y)) if newest_first => y.cmp(x),
+        (Some(x), Some(y)) => x.cmp(y),
+        _ => a.created_at.is_none().cmp(&b.created_at.is_none()),
     }
 }
 
+/// Returns one page of secrets. `sort` is "name", "created" (oldest first) or
+/// "recent" (newest first); secrets without a creation time sort last.
 #[command]
-async fn vibesafe_add(name: String, value: String) -> Result<String, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["add", &name, &value])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe add: {}", e))?;
+async fn list_secrets_paged(
+    app: AppHandle,
+    offset: usize,
+    limit: usize,
+    sort: String,
+) -> Result<SecretPage, AppError> {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let mut secrets = cached_secrets(&app, false).await?;
+
+    match sort.as_str() {
+        "name" => secrets.sort_by(|a, b| a.name.cmp(&b.name)),
+        "created" => secrets.sort_by(|a, b| cmp_created(a, b, false)),
+        "recent" => secrets.sort_by(|a, b| cmp_created(a, b, true)),
+        other => {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown sort '{}'. Use name, created or recent.",
+                other
+            )))
+        }
+    }
+
+    let total = secrets.len();
+    let secrets = secrets.into_iter().skip(offset).take(limit).collect();
+    Ok(SecretPage { secrets, total })
+}
+
+#[derive(Debug, Serialize)]
+struct SecretValue {
+    /// The value as text, or base64 when `is_binary` is set.
+    value: String,
+    is_binary: bool,
+    /// The app's copy of the value was locked into RAM while it was held. False
+    /// means it could have been swapped to disk.
+    memory_locked: bool,
+}
 
-    if output.status.success() {
-        Ok("Secret added successfully".to_string())
+impl SecretValue {
+    /// Values that aren't valid UTF-8 are base64-encoded rather than lossily
+    /// converted, so binary secrets come back byte for byte.
+    fn from_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self {
+                value: text.to_string(),
+                is_binary: false,
+                memory_locked: false,
+            },
+            Err(_) => Self {
+                value: BASE64.encode(bytes),
+                is_binary: true,
+                memory_locked: false,
+            },
+        }
+    }
+}
+
+/// The decrypted value is held in a buffer locked into RAM (unless `lock_memory`
+/// is false) and wiped once the response is built; `memory_locked` says whether
+/// locking worked. The copy sent to the UI is outside the app's control.
+#[command]
+async fn get_secret(
+    app: AppHandle,
+    name: String,
+    lock_memory: Option<bool>,
+) -> Result<SecretValue, AppError> {
+    let stdout = cli::get_secret(&name).await?;
+    let stdout = if lock_memory.unwrap_or(true) {
+        LockedBuffer::new(stdout)
     } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+        LockedBuffer::unlocked(stdout)
+    };
+    audit::record(&app, &name, AccessAction::Read, None)?;
+    let mut value = SecretValue::from_bytes(stdout.as_bytes());
+    value.memory_locked = stdout.is_locked();
+    Ok(value)
+}
+
+/// Returns a secret's raw bytes base64-encoded, whether or not they are valid UTF-8.
+#[command]
+async fn get_binary_secret(app: AppHandle, name: String) -> Result<String, AppError> {
+    let stdout = cli::get_secret(&name).await?;
+    audit::record(&app, &name, AccessAction::Read, None)?;
+    Ok(BASE64.encode(&stdout[..]))
+}
+
+/// Decodes a base64 value into the line the CLI reads on stdin.
+fn binary_input(base64_value: &str) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    // Sized up front (with room for the newline) so the buffer never reallocates
+    // and leaves an unwiped copy of the value behind.
+    let value = base64_value.trim();
+    let mut input = Zeroizing::new(Vec::with_capacity(
+        base64::decoded_len_estimate(value.len()) + 1,
+    ));
+    BASE64
+        .decode_vec(value, &mut input)
+        .map_err(|e| AppError::InvalidInput(format!("Value is not valid base64: {}", e)))?;
+    if input.is_empty() {
+        return Err(AppError::InvalidInput("Value is empty".to_string()));
     }
+    input.push(b'\n');
+    Ok(input)
 }
 
+/// Stores a binary value (certificate, keyfile, ...) sent as base64. The decoded
+/// bytes go to the CLI on stdin; see `cli::add_with_stdin` for values with line
+/// breaks.
+///
+/// Valid in: Ready.
 #[command]
-async fn vibesafe_delete(name: String) -> Result<String, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["delete", &name, "--yes"])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe delete: {}", e))?;
+async fn add_binary_secret(
+    app: AppHandle,
+    cache: State<'_, StoreCache>,
+    name: String,
+    base64_value: String,
+) -> Result<String, AppError> {
+    names::check_secret_name(&name)?;
+    state::require(&app, &[VaultState::Ready]).await?;
+    let input = binary_input(&Zeroizing::new(base64_value))?;
+    cli::add_with_stdin(&name, &input).await?;
+    cache.invalidate();
+    hooks::fire(&app, HookEvent::Added, &name, "add");
+    Ok("Secret added successfully".to_string())
+}
 
-    if output.status.success() {
-        Ok("Secret deleted successfully".to_string())
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+/// The value goes to the CLI on stdin, never in its arguments; a CLI that can't
+/// take it that way fails with `UNSUPPORTED_STDIN`, and one that only reads a line
+/// fails multi-line values with `MULTILINE_VALUE`.
+///
+/// Valid in: Ready.
+#[command]
+async fn vibesafe_add(
+    app: AppHandle,
+    cache: State<'_, StoreCache>,
+    name: String,
+    value: String,
+) -> Result<String, AppError> {
+    let value = Zeroizing::new(value);
+    names::check_secret_name(&name)?;
+    state::require(&app, &[VaultState::Ready]).await?;
+    let mut input = Zeroizing::new(Vec::with_capacity(value.len() + 1));
+    input.extend_from_slice(value.as_bytes());
+    input.push(b'\n');
+    cli::add_with_stdin(&name, &input).await?;
+    cache.invalidate();
+    hooks::fire(&app, HookEvent::Added, &name, "add");
+    Ok("Secret added successfully".to_string())
+}
+
+/// A read-only secret fails with `READ_ONLY_SECRET` unless `override_readonly`
+/// is set and the user confirms; deleting it clears the flag.
+///
+/// Valid in: Ready.
+#[command]
+async fn vibesafe_delete(
+    app: AppHandle,
+    cache: State<'_, StoreCache>,
+    window: Window,
+    provider: State<'_, auth::SharedAuthProvider>,
+    name: String,
+    override_readonly: Option<bool>,
+) -> Result<String, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    readonly::check_writable(
+        &app,
+        provider.inner().clone(),
+        window,
+        &name,
+        override_readonly.unwrap_or(false),
+    )
+    .await?;
+    snapshots::take(&app, &format!("delete {}", name))?;
+    cli::run_vibesafe(&cli::action_args("delete", &[&name])?).await?;
+    cache.invalidate();
+    hooks::fire(&app, HookEvent::Deleted, &name, "delete");
+    // Best effort: the delete itself succeeded.
+    let _ = metadata::cleanup_orphans(&app).await;
+    Ok("Secret deleted successfully".to_string())
+}
+
+/// Creates the store, at `store_path` when given (an absolute directory, empty
+/// or holding a store, outside cloud-synced folders unless `accept_sync_warning`
+/// is set) and at the default location otherwise. The choice is remembered and
+/// passed to every later CLI run as `VIBESAFE_HOME`; an existing store isn't
+/// moved by initializing again.
+///
+/// Valid in: Uninitialized.
+#[command]
+async fn vibesafe_init(
+    app: AppHandle,
+    cache: State<'_, StoreCache>,
+    store_path: Option<String>,
+    accept_sync_warning: Option<bool>,
+) -> Result<String, AppError> {
+    state::require(&app, &[VaultState::Uninitialized]).await?;
+    let location = match store_path {
+        Some(path) => Some(store_location::validate(
+            &path,
+            accept_sync_warning.unwrap_or(false),
+        )?),
+        None => None,
+    };
+    let saved = store_location::saved(&app)?;
+    if let (Some(location), Some(saved)) = (&location, &saved) {
+        if location != saved {
+            return Err(AppError::InvalidInput(format!(
+                "This app already uses the store at {}; initializing again doesn't move it",
+                saved.display()
+            )));
+        }
+    }
+
+    let previous = paths::store_override();
+    if let Some(location) = &location {
+        paths::set_store_override(Some(location.clone()));
     }
+    if let Err(e) = cli::run_vibesafe(&cli::action_args("init", &[])?).await {
+        paths::set_store_override(previous);
+        return Err(e);
+    }
+    if let Some(location) = &location {
+        store_location::save(&app, location)?;
+    }
+    cache.invalidate();
+    store_watch::start(&app);
+    Ok("VibeSafe initialized successfully".to_string())
 }
 
+/// Generates a new key pair and re-encrypts every secret with it. Progress lines
+/// arrive as "cli:output" events while it runs, and each stage as
+/// "vibesafe://rotation-progress". The rotation is journaled so one cut short by
+/// a quit can be finished with `resume_key_rotation`. Needs a token from
+/// `require_elevation` for `rotate_keypair`.
+///
+/// Valid in: Ready.
 #[command]
-async fn vibesafe_init() -> Result<String, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["init"])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe init: {}", e))?;
+async fn rotate_keypair(
+    app: AppHandle,
+    elevations: State<'_, elevation::Elevations>,
+    elevation_token: String,
+) -> Result<String, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    elevations.redeem(&elevation_token, elevation::Operation::RotateKeypair)?;
+    let snapshot = snapshots::take(&app, "rotate_keypair")?;
+    rotation::run(&app, snapshot.id).await?;
+    Ok("Keys rotated successfully".to_string())
+}
+
+#[derive(Debug, Serialize)]
+struct PasskeyEnabled {
+    message: String,
+    /// The factor the CLI registered, when its output identifies one.
+    factor: Option<String>,
+}
 
-    if output.status.success() {
-        Ok("VibeSafe initialized successfully".to_string())
+/// Maps the CLI's confirmation line to the factor it registered.
+fn registered_factor(output: &str) -> Option<String> {
+    if output.contains("Keychain passkey protection enabled") {
+        Some("Touch ID / Face ID (macOS Keychain)".to_string())
+    } else if output.contains("FIDO2 passkey protection enabled") {
+        Some("FIDO2 security key".to_string())
     } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+        None
     }
 }
 
+/// Valid in: Ready.
 #[command]
-async fn vibesafe_enable_passkey(passkey_type: String) -> Result<String, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["passkey", "enable", "--type", &passkey_type])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe passkey enable: {}", e))?;
+async fn vibesafe_enable_passkey(
+    app: AppHandle,
+    cache: State<'_, StoreCache>,
+    passkey_type: String,
+) -> Result<PasskeyEnabled, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let stdout = cli::run_vibesafe(&cli::action_args("enable_passkey", &[&passkey_type])?).await?;
+    cache.invalidate();
 
-    if output.status.success() {
-        Ok("Passkey enabled successfully".to_string())
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+    Ok(PasskeyEnabled {
+        message: "Passkey enabled successfully".to_string(),
+        factor: registered_factor(&String::from_utf8_lossy(&stdout)),
+    })
+}
+
+/// Longest justification `copy_secret_to_clipboard_with_reason` accepts, in characters.
+const MAX_REASON_LEN: usize = 500;
+
+/// The value exactly as stored, whitespace included. The clipboard takes text,
+/// so binary values are refused rather than mangled.
+fn copy_text(value: &[u8]) -> Result<Zeroizing<String>, AppError> {
+    std::str::from_utf8(value)
+        .map(|text| Zeroizing::new(text.to_string()))
+        .map_err(|_| {
+            AppError::InvalidInput(
+                "Binary values can't be copied as text. Use get_binary_secret instead.".to_string(),
+            )
+        })
+}
+
+/// Fetches a value for the clipboard, logging the copy first.
+async fn read_for_copy(
+    app: &AppHandle,
+    name: &str,
+    reason: Option<&str>,
+) -> Result<Zeroizing<String>, AppError> {
+    let stdout = cli::get_secret(name).await?;
+    let value = copy_text(&stdout)?;
+    audit::record(app, name, AccessAction::Copy, reason)?;
+    Ok(value)
+}
+
+async fn copy_secret(
+    app: &AppHandle,
+    name: &str,
+    reason: Option<&str>,
+) -> Result<String, AppError> {
+    let secret_value = read_for_copy(app, name, reason).await?;
+    clipboard::copy_with_auto_clear(app, &secret_value)?;
+    Ok(format!(
+        "Secret copied to clipboard (auto-clear in {}s)",
+        clipboard::CLEAR_AFTER.as_secs()
+    ))
+}
+
+#[command]
+async fn copy_secret_to_clipboard(app: AppHandle, name: String) -> Result<String, AppError> {
+    copy_secret(&app, &name, None).await
+}
+
+/// Like `copy_secret_to_clipboard`, recording `reason` with the access log entry.
+/// The reason is stored as plain text, so it must not contain the secret.
+#[command]
+async fn copy_secret_to_clipboard_with_reason(
+    app: AppHandle,
+    name: String,
+    reason: String,
+) -> Result<String, AppError> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::InvalidInput("A reason is required".to_string()));
+    }
+    if reason.chars().count() > MAX_REASON_LEN {
+        return Err(AppError::InvalidInput(format!(
+            "Reason is too long (max {} characters)",
+            MAX_REASON_LEN
+        )));
+    }
+    if reason.chars().any(char::is_control) {
+        return Err(AppError::InvalidInput(
+            "Reason can't contain control characters".to_string(),
+        ));
     }
+
+    copy_secret(&app, &name, Some(reason)).await
 }
 
+/// Copies with auto-clear off, for pastes that take longer than the timeout.
+/// Returns the token for `unpin_clipboard`; "clipboard:pin-changed" tells the UI
+/// that the value will stay on the clipboard.
 #[command]
-async fn copy_secret_to_clipboard(name: String) -> Result<String, String> {
-    let output = ShellCommand::new_sidecar("vibesafe")
-        .map_err(|e| format!("Failed to create vibesafe command: {}", e))?
-        .args(["get", &name])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute vibesafe get: {}", e))?;
+async fn copy_secret_pinned(app: AppHandle, name: String) -> Result<String, AppError> {
+    let secret_value = read_for_copy(&app, &name, None).await?;
+    clipboard::copy_pinned(&app, &secret_value)
+}
 
-    if output.status.success() {
-        let secret_value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+#[command]
+async fn get_cli_version() -> Result<String, AppError> {
+    Ok(cli::cli_version().await?.to_string())
+}
 
-        // Copy to clipboard using Tauri's clipboard API
-        use tauri::api::clipboard::write_text;
-        write_text(&secret_value)
-            .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+/// Bundled and PATH CLI versions, flagging a mismatch so the UI can warn that
+/// behavior depends on which one is used.
+#[command]
+async fn compare_cli_versions() -> cli::CliVersions {
+    cli::compare_versions().await
+}
 
-        // Auto-clear clipboard after 30 seconds for security
-        tokio::spawn(async move {
-            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
-            // Clear clipboard by overwriting with empty string
-            let _ = write_text("");
-        });
+/// Which CLI binary commands run, and whether it is the bundled one or from PATH.
+#[command]
+fn get_cli_path() -> Result<cli::CliLocation, AppError> {
+    cli::locate_cli()
+}
 
-        Ok("Secret copied to clipboard (auto-clear in 30s)".to_string())
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("VibeSafe error: {}", error))
+/// The command line `action` would run with `args`, from the same binary lookup
+/// and argument building the real commands use. Names and values are redacted,
+/// so it can be pasted into a bug report.
+#[command]
+fn preview_command(action: String, args: Vec<String>) -> Result<cli::CommandPreview, AppError> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    cli::preview(&action, &args)
+}
+
+/// Whether the CLI can take an added value on stdin, and how. `unsupported` means
+/// adding from the app fails with `UNSUPPORTED_STDIN` until the CLI is updated.
+#[command]
+async fn check_stdin_add_support() -> Result<cli::StdinAdd, AppError> {
+    cli::stdin_add_support().await
+}
+
+/// Exit code, stderr, binary and redacted arguments of the last failed CLI run.
+#[command]
+fn get_last_cli_failure() -> Option<cli::CliFailure> {
+    cli::last_failure()
+}
+
+/// Refuses value search unless it's enabled in settings.
+fn check_value_search(enabled: bool) -> Result<(), AppError> {
+    if !enabled {
+        return Err(AppError::InvalidInput(
+            "Value search is disabled. It decrypts every secret, so enable it in settings first."
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Names of the `secrets` whose value contains `query`, reading each once.
+async fn names_containing(secrets: Vec<SecretInfo>, query: &[u8]) -> Result<Vec<String>, AppError> {
+    let mut matches = Vec::new();
+    for secret in secrets {
+        let value = cli::get_secret(&secret.name).await?;
+        if value.windows(query.len()).any(|window| window == query) {
+            matches.push(secret.name);
+        }
+    }
+    Ok(matches)
+}
+
+/// Returns the names of secrets whose value contains `query`. Every secret is
+/// decrypted to check it, so this is off unless `value_search_enabled` is set,
+/// and with a passkey the user confirms once for the whole search, warned that
+/// it decrypts everything. Values are never returned.
+#[command]
+async fn search_secret_values(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, auth::SharedAuthProvider>,
+    query: String,
+) -> Result<Vec<String>, AppError> {
+    let query = Zeroizing::new(query);
+    if query.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Search query is required".to_string(),
+        ));
+    }
+    check_value_search(settings::load_settings(&app)?.value_search_enabled)?;
+    let secrets = list_secrets().await?;
+    if cached_status(&app, false).await?.passkey_enabled {
+        let reason = format!(
+            "Search values: this decrypts all {} secret(s)",
+            secrets.len()
+        );
+        auth::confirm_presence(provider.inner().clone(), window, reason).await?;
     }
+    names_containing(secrets, query.as_bytes()).await
 }
 
 fn create_tray() -> SystemTray {
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
     let show = CustomMenuItem::new("show".to_string(), "Show Window");
     let add_secret = CustomMenuItem::new("add_secret".to_string(), "Add Secret");
+    let passkey =
+        CustomMenuItem::new(TRAY_PASSKEY_ITEM.to_string(), "Passkey: checking…").disabled();
 
     let tray_menu = SystemTrayMenu::new()
         .add_item(show)
+        .add_submenu(SystemTraySubmenu::new(
+            "Status",
+            SystemTrayMenu::new().add_item(passkey),
+        ))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(add_secret)
         .add_native_item(SystemTrayMenuItem::Separator)
@@ -178,50 +763,393 @@ fn create_tray() -> SystemTray {
     SystemTray::new().with_menu(tray_menu)
 }
 
+/// Decides whether the main window is shown at launch. `--hidden` always wins,
+/// then `start_minimized`, then `show_window_on_launch`.
+fn should_show_window(hidden_flag: bool, settings: &AppSettings) -> bool {
+    !hidden_flag && !settings.start_minimized && settings.show_window_on_launch
+}
+
+/// Brings the main window to the front. Tray handlers can't report errors, so a
+/// missing window or a failed show is ignored rather than panicking.
+fn show_main_window(app: &AppHandle) -> Option<Window> {
+    let window = app.get_window(MAIN_WINDOW)?;
+    window.show().ok()?;
+    let _ = window.set_focus();
+    Some(window)
+}
+
+/// Stops launched programs and removes everything the session left on disk. Runs
+/// before any exit.
+fn shutdown(app: &AppHandle) {
+    api_server::stop(app);
+    app.state::<runner::ManagedProcesses>().kill_all();
+    app.state::<TempFiles>().shred_all();
+}
+
+/// Applies the saved `prefer_bundled_cli` setting and store location for the
+/// helper modes, which run the CLI before (or without) the app's setup.
+fn apply_saved_config(config_dir: Option<std::path::PathBuf>) {
+    if let Some(dir) = config_dir {
+        let settings = settings::read_settings_file(&settings::settings_path_in(&dir));
+        cli::apply_settings(&settings.unwrap_or_default());
+        store_location::load_from(&dir);
+    }
+}
+
 fn main() {
+    let context = generate_context!();
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(position) = args
+        .iter()
+        .position(|arg| arg == native_messaging::HOST_FLAG)
+    {
+        let config = context.config();
+        apply_saved_config(tauri::api::path::app_config_dir(config));
+        let data_dir = tauri::api::path::app_data_dir(config);
+        std::process::exit(native_messaging::run_host(&args[position + 1..], data_dir));
+    }
+    let started_as_helper = args.first().is_some_and(|arg0| {
+        std::path::Path::new(arg0)
+            .file_stem()
+            .is_some_and(|stem| stem == askpass::HELPER_NAME)
+    });
+    let askpass_args = match args.iter().position(|arg| arg == askpass::ASKPASS_FLAG) {
+        Some(position) => Some(&args[position + 1..]),
+        None if started_as_helper => Some(&args[1..]),
+        None => None,
+    };
+    if let Some(askpass_args) = askpass_args {
+        let data_dir = tauri::api::path::app_data_dir(context.config());
+        std::process::exit(askpass::run(askpass_args, data_dir));
+    }
+
+    let instance = instance::acquire(tauri::api::path::app_data_dir(context.config()), &args);
     let tray = create_tray();
 
     Builder::default()
         .system_tray(tray)
+        .manage(instance)
+        .manage(auth::platform_provider())
+        .manage(auth::AuthAttempts::default())
+        .manage(pin::PinPrompts::default())
+        .manage(TempFiles::default())
+        .manage(clipboard::ClipboardState::default())
+        .manage(health::LastHealth::default())
+        .manage(runner::ManagedProcesses::default())
+        .manage(store_watch::StoreWatcher::default())
+        .manage(StoreCache::default())
+        .manage(index::SecretIndex::default())
+        .manage(settings::ResetToken::default())
+        .manage(settings::SettingsHistory::default())
+        .manage(elevation::Elevations::default())
+        .manage(api_server::ApiServer::default())
+        .manage(hygiene::VaultAudit::default())
+        .manage(stats::StatsCache::default())
+        .setup(|app| {
+            // The window is created hidden (see tauri.conf.json) and only shown here,
+            // so an autostarted launch never flashes it on screen.
+            let settings = settings::load_settings(&app.handle()).unwrap_or_default();
+            cli::apply_settings(&settings);
+            store_location::load(&app.handle());
+            let hidden_flag = std::env::args().any(|arg| arg == HIDDEN_FLAG);
+
+            tauri::async_runtime::spawn(warmup::initialize(app.handle()));
+            tauri::async_runtime::spawn(passkeys::watch_passkey_state(app.handle()));
+            store_watch::start(&app.handle());
+            tauri::async_runtime::spawn(api_server::start_if_enabled(app.handle()));
+            instance::listen(&app.handle());
+            power::watch(&app.handle());
+            rotation::check_incomplete(&app.handle());
+
+            if should_show_window(hidden_flag, &settings) {
+                let window = app
+                    .get_window(MAIN_WINDOW)
+                    .ok_or("The main window is missing from tauri.conf.json")?;
+                window.show()?;
+                window.set_focus()?;
+            }
+            Ok(())
+        })
         .on_system_tray_event(|app, event| match event {
             SystemTrayEvent::LeftClick {
                 position: _,
                 size: _,
                 ..
             } => {
-                let window = app.get_window("main").unwrap();
-                window.show().unwrap();
-                window.set_focus().unwrap();
+                show_main_window(app);
             }
             SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
                 "quit" => {
+                    shutdown(app);
                     std::process::exit(0);
                 }
                 "show" => {
-                    let window = app.get_window("main").unwrap();
-                    window.show().unwrap();
-                    window.set_focus().unwrap();
+                    show_main_window(app);
                 }
                 "add_secret" => {
-                    let window = app.get_window("main").unwrap();
-                    window.show().unwrap();
-                    window.set_focus().unwrap();
-                    // Emit event to frontend to show add secret dialog
-                    window.emit("show_add_secret", {}).unwrap();
+                    if let Some(window) = show_main_window(app) {
+                        // Emit event to frontend to show add secret dialog
+                        let _ = window.emit("show_add_secret", ());
+                    }
                 }
                 _ => {}
-            }
+            },
             _ => {}
         })
         .invoke_handler(generate_handler![
+            hydrate,
+            instance::get_instance_status,
             vibesafe_status,
             vibesafe_list,
+            list_secrets_paged,
+            index::list_secrets_page,
+            index::search_secrets,
+            get_secret,
+            locked::check_memory_protection,
+            get_binary_secret,
             vibesafe_add,
+            names::validate_secret_name,
+            names::is_reserved_name,
+            add_binary_secret,
             vibesafe_delete,
+            metadata::cleanup_orphaned_metadata,
+            metadata::get_secret_metadata,
+            snapshots::snapshot_store,
+            snapshots::list_snapshots,
+            snapshots::restore_snapshot,
+            rename::rename_secret,
+            rename::rename_by_pattern,
+            readonly::set_secret_readonly,
+            readonly::list_readonly_secrets,
+            secret_urls::set_secret_url,
+            secret_urls::open_secret_url,
+            share::get_share_public_key,
+            share::share_secret_encrypted,
+            share::import_shared_secret,
+            bundle::create_share_bundle,
+            bundle::open_share_bundle,
+            bundle::import_share_bundle,
+            env_import::scan_project_for_env_files,
+            env_import::migrate_env_file,
+            keychain::list_keychain_candidates,
+            keychain::import_from_keychain,
+            hooks::list_hooks,
+            hooks::test_hook,
+            api_server::start_api_server,
+            api_server::stop_api_server,
+            api_server::get_api_server_status,
+            api_server::create_api_client,
+            api_server::list_api_clients,
+            api_server::revoke_api_client,
+            docker::export_docker_env,
+            docker::generate_compose_override,
+            qr::get_secret_qr,
+            templates::render_template,
+            github::push_to_github_actions,
+            claude::get_claude_integration_status,
+            claude::enable_claude_integration,
+            claude::disable_claude_integration,
             vibesafe_init,
             vibesafe_enable_passkey,
-            copy_secret_to_clipboard
+            rotate_keypair,
+            rotation::resume_key_rotation,
+            rotation::get_key_rotation_status,
+            elevation::require_elevation,
+            copy_secret_to_clipboard,
+            copy_secret_to_clipboard_with_reason,
+            copy_secret_pinned,
+            clipboard::unpin_clipboard,
+            clipboard::copy_secret_name,
+            power::wipe_caches,
+            audit::get_secret_last_access,
+            audit::find_unused_secrets,
+            audit::export_audit_report,
+            state::get_vault_state,
+            state::unlock_vault,
+            state::unlock_with_passphrase,
+            health::health_check,
+            runner::run_with_secrets,
+            runner::kill_managed_process,
+            shell_export::generate_shell_export,
+            projects::create_project,
+            projects::list_projects,
+            projects::delete_project,
+            projects::detect_project,
+            projects::get_project_env,
+            screen::is_screen_being_recorded,
+            search_secret_values,
+            get_cli_version,
+            get_cli_path,
+            compare_cli_versions,
+            selftest::selftest_parsing,
+            upgrade::post_upgrade_check,
+            native_messaging::install_native_messaging_manifest,
+            askpass::install_ssh_askpass,
+            ssh_agent::load_into_ssh_agent,
+            cli_config::get_cli_config,
+            cli_config::set_cli_config,
+            preview_command,
+            check_stdin_add_support,
+            get_last_cli_failure,
+            hygiene::find_placeholder_secrets,
+            hygiene::store_health_score,
+            stats::get_vault_stats,
+            store_location::get_store_location,
+            hygiene::run_vault_audit,
+            hygiene::cancel_vault_audit,
+            hygiene::get_vault_audit_report,
+            breaches::check_breaches,
+            passkeys::list_passkeys,
+            passkeys::remove_passkey,
+            passkeys::test_touchid,
+            auth::list_supported_passkey_types,
+            auth::confirm_user_presence,
+            pin::get_pin_prompt,
+            pin::submit_confirmation_pin,
+            pin::cancel_confirmation_pin,
+            pin::has_confirmation_pin,
+            pin::set_confirmation_pin,
+            settings::get_app_settings,
+            settings::update_app_settings,
+            settings::list_themes,
+            settings::get_theme,
+            settings::set_theme,
+            settings::list_clipboard_clear_strategies,
+            settings::set_clipboard_clear_strategy,
+            settings::request_reset_token,
+            settings::reset_settings,
+            settings::export_settings,
+            settings::import_settings
         ])
-        .run(generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                shutdown(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn values_keep_their_trailing_newlines() {
+        let fake = test_support::fake_cli(r#"[ "$1" = get ] && cat "store/$2""#);
+        std::fs::create_dir(fake.path("store")).unwrap();
+        let values: &[(&str, &[u8])] = &[
+            (
+                "PEM",
+                b"-----BEGIN KEY-----\nAAAA\n-----END KEY-----\n".as_slice(),
+            ),
+            ("CRLF", b"value\r\n"),
+            ("BLANK_LINES", b"value\n\n"),
+        ];
+        for (name, stored) in values {
+            std::fs::write(fake.path("store").join(name), stored).unwrap();
+            let bytes = tauri::async_runtime::block_on(cli::get_secret(name)).unwrap();
+            assert_eq!(&bytes[..], *stored, "{}", name);
+            let value = SecretValue::from_bytes(&bytes);
+            assert!(!value.is_binary);
+            assert_eq!(value.value.as_bytes(), *stored, "{}", name);
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn binary_values_round_trip() {
+        // Stores stdin without the newline the real CLI strips.
+        let fake = test_support::fake_cli(
+            r#"case "$1" in
+  add) if [ "$2" = --help ]; then echo "Usage: vibesafe add [OPTIONS] NAME --stdin"
+       else cat > in && head -c $(($(wc -c < in) - 1)) in > "store/$2"; fi ;;
+  get) cat "store/$2" ;;
+esac"#,
+        );
+        std::fs::create_dir(fake.path("store")).unwrap();
+        let values: &[&[u8]] = &[b"\xff\xfe\x00\x01", b"\x00", b"caf\xe9\r", b" padded \t"];
+        for (i, stored) in values.iter().enumerate() {
+            let name = format!("KEY_{}", i);
+            let input = binary_input(&BASE64.encode(stored)).unwrap();
+            cli::add_with_stdin(&name, &input).await.unwrap();
+            let bytes = cli::get_secret(&name).await.unwrap();
+            assert_eq!(&bytes[..], *stored);
+            let value = SecretValue::from_bytes(&bytes);
+            assert_eq!(value.is_binary, std::str::from_utf8(stored).is_err());
+        }
+    }
+
+    #[test]
+    fn binary_input_is_the_decoded_value_and_a_newline() {
+        assert_eq!(&binary_input(" AAE= ").unwrap()[..], b"\x00\x01\n");
+        assert!(binary_input("").is_err());
+        assert!(binary_input("not base64!").is_err());
+    }
+
+    #[test]
+    fn copies_keep_the_value_as_stored() {
+        assert_eq!(&*copy_text(b"  token\n").unwrap(), "  token\n");
+        assert!(matches!(
+            copy_text(b"\xff\x00"),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn the_window_is_shown_only_when_nothing_hides_it() {
+        let cases = [
+            // (--hidden, start_minimized, show_window_on_launch, shown)
+            (false, false, true, true),
+            (false, false, false, false),
+            (false, true, true, false),
+            (false, true, false, false),
+            (true, false, true, false),
+            (true, false, false, false),
+            (true, true, true, false),
+            (true, true, false, false),
+        ];
+        for (hidden, start_minimized, show_window_on_launch, shown) in cases {
+            let settings = AppSettings {
+                start_minimized,
+                show_window_on_launch,
+                ..AppSettings::default()
+            };
+            assert_eq!(
+                should_show_window(hidden, &settings),
+                shown,
+                "--hidden={} start_minimized={} show_window_on_launch={}",
+                hidden,
+                start_minimized,
+                show_window_on_launch
+            );
+        }
+        assert!(should_show_window(false, &AppSettings::default()));
+    }
+
+    #[test]
+    fn value_search_needs_the_setting() {
+        assert!(check_value_search(false).is_err());
+        assert!(check_value_search(true).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn value_search_reads_each_secret_once() {
+        let fake = test_support::fake_cli(
+            r#"[ "$1" = get ] && echo "$2" >> gets && printf 'key-%s' "$2""#,
+        );
+        let secrets = serde_json::from_str::<Vec<SecretInfo>>(
+            r#"[{"name": "ALPHA"}, {"name": "BETA"}, {"name": "ALPHABET"}]"#,
+        )
+        .unwrap();
+        let matches = names_containing(secrets, b"ALPHA").await.unwrap();
+        assert_eq!(matches, ["ALPHA", "ALPHABET"]);
+        let gets = std::fs::read_to_string(fake.path("gets")).unwrap();
+        assert_eq!(
+            gets.lines().collect::<Vec<_>>(),
+            ["ALPHA", "BETA", "ALPHABET"]
+        );
+    }
+}
@@ -1,10 +1,11 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import PinPrompt from "./PinPrompt";
 import "./globals.css";
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {window.location.hash === "#pin" ? <PinPrompt /> : <App />}
   </React.StrictMode>,
 );
\ No newline at end of file
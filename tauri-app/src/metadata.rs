@@ -0,0 +1,305 @@
+use crate::error::AppError;
+use crate::projects::Project;
+use crate::state::{self, VaultState};
+use crate::{atomic, paths};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tauri::{command, AppHandle};
+
+/// App-side data about secrets that the CLI doesn't store. Never holds values.
+const METADATA_FILE: &str = "metadata.json";
+
+/// Serializes read-modify-write cycles within the app.
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    /// Bumped on every save.
+    pub revision: u64,
+    pub projects: BTreeMap<String, Project>,
+    /// Secrets that can't be deleted or renamed without an override.
+    pub readonly: BTreeSet<String>,
+    /// A link per secret, such as the provider's dashboard.
+    pub urls: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SecretMetadata {
+    pub name: String,
+    pub readonly: bool,
+    pub url: Option<String>,
+    /// The projects that use the secret, sorted.
+    pub projects: Vec<String>,
+}
+
+pub fn metadata_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(paths::app_data_dir(app)?.join(METADATA_FILE))
+}
+
+/// Empty metadata when the file doesn't exist yet.
+pub fn load(app: &AppHandle) -> Result<Metadata, AppError> {
+    load_from(&metadata_path(app)?)
+}
+
+fn load_from(path: &Path) -> Result<Metadata, AppError> {
+    if !path.exists() {
+        return Ok(Metadata::default());
+    }
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| AppError::Cli(format!("Failed to read metadata: {}", e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| AppError::Cli(format!("Failed to parse metadata: {}", e)))
+}
+
+/// Loads the metadata, applies `edit` and saves the result atomically. Nothing is
+/// written when `edit` fails.
+pub fn update<T>(
+    app: &AppHandle,
+    edit: impl FnOnce(&mut Metadata) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    update_at(&metadata_path(app)?, edit)
+}
+
+fn update_at<T>(
+    path: &Path,
+    edit: impl FnOnce(&mut Metadata) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut metadata = load_from(path)?;
+    let result = edit(&mut metadata)?;
+    metadata.revision += 1;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create data directory: {}", e)))?;
+    }
+    atomic::atomic_write_json(path, &metadata)
+        .map_err(|e| AppError::Cli(format!("Failed to write metadata: {}", e)))?;
+    Ok(result)
+}
+
+impl Metadata {
+    /// Drops entries for secrets not in `live`, returning how many went from each
+    /// store. Stores keyed by secret name belong here as they are added.
+    fn remove_orphans(&mut self, live: &HashSet<String>) -> BTreeMap<&'static str, usize> {
+        let mut project_entries = 0;
+        for project in self.projects.values_mut() {
+            let before = project.secrets.len();
+            project.secrets.retain(|entry| live.contains(&entry.secret));
+            project_entries += before - project.secrets.len();
+        }
+
+        let before = self.readonly.len();
+        self.readonly.retain(|name| live.contains(name));
+        let readonly = before - self.readonly.len();
+
+        let before = self.urls.len();
+        self.urls.retain(|name, _| live.contains(name));
+
+        let mut removed = BTreeMap::new();
+        removed.insert("projects", project_entries);
+        removed.insert("readonly", readonly);
+        removed.insert("urls", before - self.urls.len());
+        removed
+    }
+
+    /// Moves everything kept under `from` to `to`: project entries, the
+    /// read-only flag and the URL. Returns whether anything moved.
+    fn rename_secret(&mut self, from: &str, to: &str) -> bool {
+        let mut moved = false;
+        for project in self.projects.values_mut() {
+            moved |= project.rename_secret(from, to);
+        }
+        if self.readonly.remove(from) {
+            self.readonly.insert(to.to_string());
+            moved = true;
+        }
+        if let Some(url) = self.urls.remove(from) {
+            self.urls.insert(to.to_string(), url);
+            moved = true;
+        }
+        moved
+    }
+}
+
+/// Makes the metadata follow a rename made through the app. The file is only
+/// rewritten when the secret had any.
+pub fn rename_secret(app: &AppHandle, from: &str, to: &str) -> Result<(), AppError> {
+    if !load(app)?.rename_secret(from, to) {
+        return Ok(());
+    }
+    update(app, |metadata| {
+        metadata.rename_secret(from, to);
+        Ok(())
+    })
+}
+
+/// Removes metadata for secrets that no longer exist, against a fresh list. The
+/// file is only rewritten when something was removed.
+pub async fn cleanup_orphans(app: &AppHandle) -> Result<BTreeMap<&'static str, usize>, AppError> {
+    let live: HashSet<String> = crate::cached_secrets(app, true)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    let removed = load(app)?.remove_orphans(&live);
+    if removed.values().all(|&count| count == 0) {
+        return Ok(removed);
+    }
+    update(app, |metadata| Ok(metadata.remove_orphans(&live)))
+}
+
+/// Also runs after each delete. Returns the number of entries removed per store.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn cleanup_orphaned_metadata(
+    app: AppHandle,
+) -> Result<BTreeMap<&'static str, usize>, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    cleanup_orphans(&app).await
+}
+
+/// What the app stores about one secret, for a details view. Names only; the
+/// secret need not exist.
+#[command]
+pub fn get_secret_metadata(app: AppHandle, name: String) -> Result<SecretMetadata, AppError> {
+    let mut metadata = load(&app)?;
+    Ok(SecretMetadata {
+        readonly: metadata.readonly.contains(&name),
+        url: metadata.urls.remove(&name),
+        projects: metadata
+            .projects
+            .iter()
+            .filter(|(_, project)| project.secrets.iter().any(|entry| entry.secret == name))
+            .map(|(project, _)| project.clone())
+            .collect(),
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::ProjectSecret;
+
+    fn sample() -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.projects.insert(
+            "app".to_string(),
+            Project {
+                path: PathBuf::from("/work/app"),
+                secrets: vec![
+                    ProjectSecret {
+                        secret: "OLD_KEY".to_string(),
+                        env_name: "API_KEY".to_string(),
+                    },
+                    ProjectSecret {
+                        secret: "DB_URL".to_string(),
+                        env_name: "DATABASE_URL".to_string(),
+                    },
+                ],
+            },
+        );
+        metadata.readonly.insert("OLD_KEY".to_string());
+        metadata.urls.insert(
+            "OLD_KEY".to_string(),
+            "https://example.com/keys".to_string(),
+        );
+        metadata
+    }
+
+    #[test]
+    fn a_missing_file_is_empty_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = load_from(&dir.path().join(METADATA_FILE)).unwrap();
+        assert_eq!(metadata.revision, 0);
+        assert!(metadata.projects.is_empty() && metadata.readonly.is_empty());
+    }
+
+    #[test]
+    fn updates_persist_and_bump_the_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join(METADATA_FILE);
+        update_at(&path, |metadata| {
+            *metadata = sample();
+            Ok(())
+        })
+        .unwrap();
+        update_at(&path, |metadata| {
+            metadata.readonly.insert("DB_URL".to_string());
+            Ok(())
+        })
+        .unwrap();
+
+        let metadata = load_from(&path).unwrap();
+        assert_eq!(metadata.revision, 2);
+        assert_eq!(metadata.projects["app"].secrets.len(), 2);
+        assert_eq!(
+            metadata.readonly.iter().collect::<Vec<_>>(),
+            ["DB_URL", "OLD_KEY"]
+        );
+        assert_eq!(metadata.urls["OLD_KEY"], "https://example.com/keys");
+    }
+
+    #[test]
+    fn failed_edits_write_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(METADATA_FILE);
+        let result: Result<(), AppError> = update_at(&path, |metadata| {
+            metadata.readonly.insert("A".to_string());
+            Err(AppError::InvalidInput("no".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn files_from_older_versions_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(METADATA_FILE);
+        fs::write(&path, r#"{"readonly": ["A"], "added_later": 1}"#).unwrap();
+        let metadata = load_from(&path).unwrap();
+        assert_eq!(metadata.readonly.len(), 1);
+        assert!(metadata.urls.is_empty());
+
+        fs::write(&path, "{").unwrap();
+        assert!(load_from(&path).is_err());
+    }
+
+    #[test]
+    fn renames_move_every_store() {
+        let mut metadata = sample();
+        assert!(metadata.rename_secret("OLD_KEY", "NEW_KEY"));
+        let entries = &metadata.projects["app"].secrets;
+        assert_eq!(entries[0].secret, "NEW_KEY");
+        assert_eq!(entries[0].env_name, "API_KEY");
+        assert_eq!(entries[1].secret, "DB_URL");
+        assert!(metadata.readonly.contains("NEW_KEY") && !metadata.readonly.contains("OLD_KEY"));
+        assert_eq!(metadata.urls["NEW_KEY"], "https://example.com/keys");
+        assert!(!metadata.urls.contains_key("OLD_KEY"));
+
+        assert!(!metadata.rename_secret("UNKNOWN", "OTHER"));
+    }
+
+    #[test]
+    fn orphans_are_counted_per_store() {
+        let mut metadata = sample();
+        let live: HashSet<String> = ["DB_URL".to_string()].into();
+        let removed = metadata.remove_orphans(&live);
+        assert_eq!(removed["projects"], 1);
+        assert_eq!(removed["readonly"], 1);
+        assert_eq!(removed["urls"], 1);
+        assert_eq!(metadata.projects["app"].secrets.len(), 1);
+        assert!(metadata
+            .remove_orphans(&live)
+            .values()
+            .all(|&count| count == 0));
+    }
+}
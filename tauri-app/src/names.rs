@@ -0,0 +1,87 @@
+use crate::error::AppError;
+use serde::Serialize;
+use tauri::command;
+
+/// Same limit as the CLI's `_validate_secret_name`.
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Integrations (the Claude helper, `export NAME=$(vibesafe get NAME)`) turn secret
+/// names into environment variables; these would clobber the shell or the CLI.
+const RESERVED_ENV_NAMES: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "SHELL",
+    "PWD",
+    "LANG",
+    "LC_ALL",
+    "TMPDIR",
+    "VIBESAFE_HOME",
+    "PYTHONPATH",
+    "PYTHONHOME",
+    "PYTHONIOENCODING",
+    "LD_PRELOAD",
+    "LD_LIBRARY_PATH",
+    "DYLD_INSERT_LIBRARIES",
+    "DYLD_LIBRARY_PATH",
+];
+
+/// Why `name` collides with something the CLI or an integration uses, if it does.
+pub fn reserved_reason(name: &str) -> Option<String> {
+    if name.starts_with('-') {
+        return Some("Names starting with '-' are read as options by the CLI".to_string());
+    }
+    // Environment names are case-insensitive on Windows.
+    RESERVED_ENV_NAMES
+        .iter()
+        .find(|reserved| reserved.eq_ignore_ascii_case(name))
+        .map(|reserved| {
+            format!(
+                "{} is an environment variable integrations would overwrite",
+                reserved
+            )
+        })
+}
+
+#[derive(Debug, Serialize)]
+pub struct NameCheck {
+    safe: bool,
+    reason: Option<String>,
+}
+
+#[command]
+pub fn is_reserved_name(name: String) -> NameCheck {
+    let reason = reserved_reason(&name);
+    NameCheck {
+        safe: reason.is_none(),
+        reason,
+    }
+}
+
+/// Applies the CLI's naming rules plus the reserved-name check, so a bad name
+/// fails here with a clear message instead of inside the CLI.
+pub fn check_secret_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty() {
+        return Err(AppError::EmptyName);
+    }
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(AppError::NameTooLong {
+            max: MAX_NAME_LENGTH,
+        });
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(AppError::InvalidNameCharacters);
+    }
+    match reserved_reason(name) {
+        Some(reason) => Err(AppError::ReservedName(reason)),
+        None => Ok(()),
+    }
+}
+
+#[command]
+pub fn validate_secret_name(name: String) -> Result<(), AppError> {
+    check_secret_name(&name)
+}
@@ -0,0 +1,457 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::error::AppError;
+use crate::{cli, paths, shell_export};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::api::dialog::blocking::ask;
+use tauri::{command, AppHandle, Window};
+use zeroize::Zeroizing;
+
+/// Starts the process as a native messaging host instead of the app. The launcher
+/// written by `install_native_messaging_manifest` passes it ahead of the browser's
+/// own arguments.
+pub const HOST_FLAG: &str = "--native-messaging-host";
+/// Name the browser extension connects to; also the manifest's file name.
+const HOST_NAME: &str = "com.vibesafe.app";
+/// Requests are a few bytes of JSON; anything bigger is not from our extension.
+const MAX_REQUEST: u32 = 64 * 1024;
+/// Chrome drops host messages over 1 MiB.
+const MAX_RESPONSE: usize = 1024 * 1024;
+const BROWSERS: &[&str] = &["chrome", "chromium", "edge", "brave", "firefox"];
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Request {
+    Ping,
+    List,
+    Get { name: String },
+}
+
+/// Reads one length-prefixed message. `None` means the browser closed the pipe.
+fn read_message(input: &mut impl Read) -> io::Result<Option<Zeroizing<Vec<u8>>>> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    // The protocol uses native byte order, which is little-endian everywhere we ship.
+    let length = u32::from_le_bytes(length);
+    if length > MAX_REQUEST {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Message of {} bytes is too large", length),
+        ));
+    }
+    let mut message = Zeroizing::new(vec![0u8; length as usize]);
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let mut bytes = Zeroizing::new(serde_json::to_vec(message)?);
+    if bytes.len() > MAX_RESPONSE {
+        *bytes = serde_json::to_vec(&failure(AppError::InvalidInput(
+            "The response is larger than the browser accepts".to_string(),
+        )))?;
+    }
+    output.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    output.write_all(&bytes)?;
+    output.flush()
+}
+
+fn failure(error: AppError) -> Value {
+    json!({ "ok": false, "error": error })
+}
+
+/// The extension that started us. Chrome passes its origin first; Firefox passes
+/// the manifest path and then the add-on id.
+fn caller(args: &[String]) -> String {
+    args.iter()
+        .find(|arg| arg.starts_with("chrome-extension://"))
+        .or_else(|| args.get(1))
+        .cloned()
+        .unwrap_or_else(|| "An unknown extension".to_string())
+}
+
+/// Asks the user before a value leaves for the browser. There is no app window in
+/// host mode, so the dialog has no parent.
+fn consent(origin: &str, name: &str) -> bool {
+    ask(
+        None::<&Window>,
+        "VibeSafe",
+        format!(
+            "{} is asking for the secret \"{}\".\n\nAllow it to read this value?",
+            origin, name
+        ),
+    )
+}
+
+/// Answers one request. `consent` is asked before a value is read.
+fn handle(
+    request: Request,
+    origin: &str,
+    log: &Path,
+    consent: impl Fn(&str, &str) -> bool,
+) -> Value {
+    let result = match request {
+        Request::Ping => Ok(json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") })),
+        Request::List => tauri::async_runtime::block_on(crate::list_secrets()).map(|secrets| {
+            let names: Vec<String> = secrets.into_iter().map(|secret| secret.name).collect();
+            json!({ "ok": true, "secrets": names })
+        }),
+        Request::Get { name } => {
+            if !consent(origin, &name) {
+                Err(AppError::AuthCancelled)
+            } else {
+                audit::record_to(
+                    log,
+                    &name,
+                    AccessAction::Read,
+                    Some(&format!("Native messaging: {}", origin)),
+                )
+                .and_then(|_| tauri::async_runtime::block_on(cli::get_secret(&name)))
+                .map(|stdout| {
+                    let value = crate::SecretValue::from_bytes(&stdout[..]);
+                    json!({ "ok": true, "value": value.value, "is_binary": value.is_binary })
+                })
+            }
+        }
+    };
+    result.unwrap_or_else(failure)
+}
+
+/// Serves requests on stdin/stdout until the browser disconnects. `args` are the
+/// arguments after `HOST_FLAG`; `data_dir` holds the access log every `get` is
+/// recorded in. `main` applies the saved CLI preference and store location
+/// first. Returns the process exit code.
+pub fn run_host(args: &[String], data_dir: Option<PathBuf>) -> i32 {
+    let origin = caller(args);
+    let log = match data_dir {
+        Some(dir) => audit::log_path_in(&dir),
+        None => return 1,
+    };
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut input = stdin.lock();
+    let mut output = stdout.lock();
+
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return 0,
+            Err(_) => return 1,
+        };
+        let response = match serde_json::from_slice::<Request>(&message) {
+            Ok(request) => handle(request, &origin, &log, consent),
+            Err(e) => failure(AppError::InvalidInput(format!("Bad request: {}", e))),
+        };
+        if write_message(&mut output, &response).is_err() {
+            return 1;
+        }
+    }
+}
+
+/// Where `browser` looks for host manifests for the current user. On Windows the
+/// location is a registry key rather than a folder.
+#[cfg(not(windows))]
+fn manifest_dir(browser: &str) -> Result<PathBuf, AppError> {
+    let (linux, macos) = match browser {
+        "chrome" => ("google-chrome", "Google/Chrome"),
+        "chromium" => ("chromium", "Chromium"),
+        "edge" => ("microsoft-edge", "Microsoft Edge"),
+        "brave" => ("BraveSoftware/Brave-Browser", "BraveSoftware/Brave-Browser"),
+        "firefox" => {
+            let home = dirs::home_dir().ok_or(AppError::AppDirUnavailable("home"))?;
+            return Ok(if cfg!(target_os = "macos") {
+                home.join("Library/Application Support/Mozilla/NativeMessagingHosts")
+            } else {
+                home.join(".mozilla/native-messaging-hosts")
+            });
+        }
+        other => return Err(unknown_browser(other)),
+    };
+    let config = dirs::config_dir().ok_or(AppError::AppDirUnavailable("config"))?;
+    let browser_dir = if cfg!(target_os = "macos") {
+        macos
+    } else {
+        linux
+    };
+    Ok(config.join(browser_dir).join("NativeMessagingHosts"))
+}
+
+#[cfg(windows)]
+fn registry_key(browser: &str) -> Result<String, AppError> {
+    let vendor = match browser {
+        "chrome" => "Google\\Chrome",
+        "chromium" => "Chromium",
+        "edge" => "Microsoft\\Edge",
+        "brave" => "BraveSoftware\\Brave-Browser",
+        "firefox" => "Mozilla",
+        other => return Err(unknown_browser(other)),
+    };
+    Ok(format!(
+        "HKCU\\Software\\{}\\NativeMessagingHosts\\{}",
+        vendor, HOST_NAME
+    ))
+}
+
+fn unknown_browser(browser: &str) -> AppError {
+    AppError::InvalidInput(format!(
+        "Unknown browser '{}'. Use chrome, chromium, edge, brave or firefox.",
+        browser
+    ))
+}
+
+/// Chrome ids are 32 letters a–p; Firefox ids are an email-like string or a
+/// braced UUID. Either way they end up in JSON and a launcher, so only plain
+/// characters are allowed.
+fn check_extension_id(browser: &str, id: &str) -> Result<(), AppError> {
+    let valid = if browser == "firefox" {
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "@.-_{}".contains(c))
+    } else {
+        id.len() == 32 && id.chars().all(|c| ('a'..='p').contains(&c))
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "'{}' isn't a {} extension id",
+            id, browser
+        )))
+    }
+}
+
+/// `cmd` launcher for `exe`. `%` is doubled so a batch file doesn't expand it;
+/// a `"` can't be escaped inside quotes, and Windows paths can't contain one.
+fn batch_launcher(exe: &Path) -> Result<String, AppError> {
+    let exe = exe.to_string_lossy();
+    if exe.contains('"') {
+        return Err(AppError::InvalidInput(format!(
+            "Can't launch the app from {}, which contains a double quote",
+            exe
+        )));
+    }
+    Ok(format!(
+        "@echo off\r\n\"{}\" {} %*\r\n",
+        exe.replace('%', "%%"),
+        HOST_FLAG
+    ))
+}
+
+fn shell_launcher(exe: &Path) -> String {
+    format!(
+        "#!/bin/sh\nexec {} {} \"$@\"\n",
+        shell_export::posix_quote(&exe.to_string_lossy()),
+        HOST_FLAG
+    )
+}
+
+/// Writes the script browsers launch: it runs this executable in host mode. The
+/// manifest can't carry arguments, hence the indirection.
+fn write_launcher(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let exe = std::env::current_exe()
+        .map_err(|e| AppError::Cli(format!("Can't find the app executable: {}", e)))?;
+    let dir = paths::app_data_dir(app)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Cli(format!("Failed to create data directory: {}", e)))?;
+
+    let (file, script) = if cfg!(windows) {
+        ("native-messaging-host.bat", batch_launcher(&exe)?)
+    } else {
+        ("native-messaging-host.sh", shell_launcher(&exe))
+    };
+    let path = dir.join(file);
+    atomic_write(&path, script.as_bytes())
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o700))
+            .map_err(|e| AppError::Cli(format!("Failed to make the launcher executable: {}", e)))?;
+    }
+    Ok(path)
+}
+
+/// Registers the app as a native messaging host for one extension in `browser`
+/// (chrome, chromium, edge, brave or firefox), for the current user only. Returns
+/// the manifest path. Every `get` the extension sends still asks the user first.
+#[command]
+pub fn install_native_messaging_manifest(
+    app: AppHandle,
+    browser: String,
+    extension_id: String,
+) -> Result<PathBuf, AppError> {
+    if !BROWSERS.contains(&browser.as_str()) {
+        return Err(unknown_browser(&browser));
+    }
+    check_extension_id(&browser, &extension_id)?;
+    let launcher = write_launcher(&app)?;
+
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "VibeSafe secrets",
+        "path": launcher,
+        "type": "stdio",
+    });
+    if browser == "firefox" {
+        manifest["allowed_extensions"] = json!([extension_id]);
+    } else {
+        manifest["allowed_origins"] = json!([format!("chrome-extension://{}/", extension_id)]);
+    }
+
+    #[cfg(not(windows))]
+    let dir = manifest_dir(&browser)?;
+    #[cfg(windows)]
+    let dir = paths::app_data_dir(&app)?
+        .join("native-messaging")
+        .join(&browser);
+
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Cli(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    let contents = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| AppError::Cli(format!("Failed to serialize the manifest: {}", e)))?;
+    atomic_write(&path, &contents)
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))?;
+
+    #[cfg(windows)]
+    {
+        let key = registry_key(&browser)?;
+        let status = std::process::Command::new("reg")
+            .args(["add", &key, "/ve", "/t", "REG_SZ", "/d"])
+            .arg(&path)
+            .arg("/f")
+            .status()
+            .map_err(|e| AppError::Cli(format!("Failed to run reg: {}", e)))?;
+        if !status.success() {
+            return Err(AppError::Cli(format!("Failed to register {}", key)));
+        }
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn framed(body: &[u8]) -> Vec<u8> {
+        let mut message = (body.len() as u32).to_le_bytes().to_vec();
+        message.extend_from_slice(body);
+        message
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let mut output = Vec::new();
+        write_message(&mut output, &json!({ "cmd": "ping" })).unwrap();
+        let mut input = Cursor::new(output);
+        let message = read_message(&mut input).unwrap().unwrap();
+        assert!(matches!(
+            serde_json::from_slice(&message).unwrap(),
+            Request::Ping
+        ));
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn oversized_requests_are_rejected_before_reading() {
+        let mut input = Cursor::new((MAX_REQUEST + 1).to_le_bytes().to_vec());
+        let error = read_message(&mut input).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_requests_are_errors() {
+        let mut message = framed(br#"{"cmd":"list"}"#);
+        message.truncate(message.len() - 3);
+        let error = read_message(&mut Cursor::new(message)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_responses_become_errors() {
+        let mut output = Vec::new();
+        let value = "x".repeat(MAX_RESPONSE);
+        write_message(&mut output, &json!({ "ok": true, "value": value })).unwrap();
+        let message = read_message(&mut Cursor::new(output)).unwrap().unwrap();
+        let response: Value = serde_json::from_slice(&message).unwrap();
+        assert_eq!(response["ok"], false);
+        assert_eq!(response["error"]["code"], "INVALID_INPUT");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn values_are_read_only_after_consent() {
+        let fake =
+            crate::test_support::fake_cli("[ \"$1\" = get ] && touch read && printf 'sk-123'");
+        let log = fake.path("access.log");
+        let get = || Request::Get {
+            name: "OPENAI_KEY".to_string(),
+        };
+
+        let refused = handle(get(), "chrome-extension://abc/", &log, |_, _| false);
+        assert_eq!(refused["ok"], false);
+        assert_eq!(refused["error"]["code"], "AUTH_CANCELLED");
+        assert!(!fake.path("read").exists());
+        assert!(!log.exists());
+
+        let allowed = handle(get(), "chrome-extension://abc/", &log, |origin, name| {
+            origin == "chrome-extension://abc/" && name == "OPENAI_KEY"
+        });
+        assert_eq!(allowed["ok"], true);
+        assert_eq!(allowed["value"], "sk-123");
+        assert!(fs::read_to_string(&log).unwrap().contains("OPENAI_KEY"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_shell_launcher_quotes_the_executable_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("it's \"$(touch pwned)\" `id`");
+        fs::create_dir(&app_dir).unwrap();
+        let exe = app_dir.join("vibesafe");
+        fs::write(
+            &exe,
+            "#!/bin/sh\nfor arg in \"$@\"; do echo \"$arg\"; done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o700)).unwrap();
+        let launcher = dir.path().join("native-messaging-host.sh");
+        fs::write(&launcher, shell_launcher(&exe)).unwrap();
+
+        let output = std::process::Command::new("/bin/sh")
+            .arg(&launcher)
+            .arg("chrome-extension://abc/")
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{}\nchrome-extension://abc/\n", HOST_FLAG)
+        );
+        assert!(!dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn the_batch_launcher_escapes_percent_signs() {
+        assert_eq!(
+            batch_launcher(Path::new(r"C:\100% Apps\VibeSafe.exe")).unwrap(),
+            format!(
+                "@echo off\r\n\"C:\\100%% Apps\\VibeSafe.exe\" {} %*\r\n",
+                HOST_FLAG
+            )
+        );
+        assert!(batch_launcher(Path::new(r#"C:\a"b\VibeSafe.exe"#)).is_err());
+    }
+}
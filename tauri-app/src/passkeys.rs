@@ -0,0 +1,195 @@
+use crate::cache::StoreCache;
+use crate::cli::{self, CliVersion};
+use crate::cli_config;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State};
+
+/// First CLI release with `passkey list`, `passkey remove` and `passkey test`.
+const PASSKEY_MANAGEMENT: CliVersion = CliVersion::new(1, 1, 0);
+const PASSKEY_STATE_CHANGED_EVENT: &str = "passkey:state-changed";
+/// How often `watch_passkey_state` rereads the CLI config.
+const PASSKEY_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyInfo {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub name: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// The kind of factor protecting the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasskeyType {
+    /// Touch ID / Face ID through the macOS Keychain.
+    TouchId,
+    /// A FIDO2 hardware security key.
+    SecurityKey,
+    Pin,
+}
+
+impl PasskeyType {
+    /// Reads the CLI's `passkey_type`. The CLI says "keychain" and "fido2"; the
+    /// other spellings are accepted for forward compatibility.
+    fn from_cli(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "keychain" | "touchid" | "touch_id" => Some(PasskeyType::TouchId),
+            "fido2" | "security_key" | "webauthn" => Some(PasskeyType::SecurityKey),
+            "pin" => Some(PasskeyType::Pin),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PasskeyType::TouchId => "Touch ID",
+            PasskeyType::SecurityKey => "Hardware security key",
+            PasskeyType::Pin => "PIN",
+        }
+    }
+}
+
+/// What the status knows about the passkey.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PasskeyDetails {
+    #[default]
+    Disabled,
+    /// Enabled, but the CLI predates `passkey_type` or reports a type the app
+    /// doesn't know. Nothing is guessed.
+    #[serde(rename = "passkey_details_unavailable")]
+    Unavailable { reported_type: Option<String> },
+    Known {
+        passkey_type: PasskeyType,
+        enrolled_at: Option<String>,
+        device_name: Option<String>,
+    },
+}
+
+impl PasskeyDetails {
+    /// From the passkey fields of `vibesafe status --json`.
+    pub fn from_status(
+        enabled: bool,
+        passkey_type: Option<&str>,
+        enrolled_at: Option<&str>,
+        device_name: Option<&str>,
+    ) -> Self {
+        if !enabled {
+            return PasskeyDetails::Disabled;
+        }
+        match passkey_type.and_then(PasskeyType::from_cli) {
+            Some(passkey_type) => PasskeyDetails::Known {
+                passkey_type,
+                enrolled_at: enrolled_at.map(str::to_string),
+                device_name: device_name.map(str::to_string),
+            },
+            None => PasskeyDetails::Unavailable {
+                reported_type: passkey_type.map(str::to_string),
+            },
+        }
+    }
+
+    /// One line for the tray's status submenu.
+    pub fn summary(&self) -> String {
+        match self {
+            PasskeyDetails::Disabled => "Passkey: off".to_string(),
+            PasskeyDetails::Unavailable { .. } => "Passkey: on (type unknown)".to_string(),
+            PasskeyDetails::Known {
+                passkey_type,
+                enrolled_at,
+                device_name,
+            } => {
+                let mut summary = format!("Passkey: {}", passkey_type.label());
+                if let Some(device_name) = device_name {
+                    summary.push_str(&format!(" ({})", device_name));
+                }
+                if let Some(enrolled_at) = enrolled_at {
+                    summary.push_str(&format!(", enrolled {}", enrolled_at));
+                }
+                summary
+            }
+        }
+    }
+}
+
+#[command]
+pub async fn list_passkeys() -> Result<Vec<PasskeyInfo>, AppError> {
+    cli::require_version(PASSKEY_MANAGEMENT, "Passkey management").await?;
+
+    let stdout = cli::run_vibesafe(&cli::action_args("list_passkeys", &[])?).await?;
+    cli::parse_json(&stdout, "passkey list").await
+}
+
+/// Revokes one enrolled factor. Removing the last one is refused unless
+/// `confirm_last` is set, since that turns passkey protection off.
+#[command]
+pub async fn remove_passkey(
+    cache: State<'_, StoreCache>,
+    id: String,
+    confirm_last: bool,
+) -> Result<(), AppError> {
+    let passkeys = list_passkeys().await?;
+    if !passkeys.iter().any(|passkey| passkey.id == id) {
+        return Err(AppError::PasskeyNotFound(id));
+    }
+    if passkeys.len() == 1 && !confirm_last {
+        return Err(AppError::LastPasskey);
+    }
+
+    cli::run_vibesafe(&cli::action_args("remove_passkey", &[&id])?).await?;
+    cache.invalidate();
+    Ok(())
+}
+
+/// Runs a no-op authentication through the CLI so the user can check their factor
+/// works. No secret is read; a dismissed prompt comes back as `AUTH_CANCELLED`.
+#[command]
+pub async fn test_touchid() -> Result<String, AppError> {
+    cli::require_version(PASSKEY_MANAGEMENT, "Testing passkey authentication").await?;
+
+    cli::run_vibesafe(&cli::action_args("test_passkey", &[])?).await?;
+    Ok("Authentication succeeded".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PasskeyStateChanged {
+    enabled: bool,
+}
+
+/// Whether the CLI config has passkey protection on, `None` if it can't be read.
+/// Only reads the file.
+fn passkey_enabled() -> Option<bool> {
+    let config = cli_config::read_config().ok()?;
+    Some(
+        config
+            .get("passkey_enabled")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false),
+    )
+}
+
+/// Runs for the life of the app, rereading the CLI config so a passkey enabled or
+/// disabled from the CLI is noticed. On a change the status cache is dropped and
+/// "passkey:state-changed" tells the UI to refresh instead of showing stale
+/// protection.
+pub async fn watch_passkey_state(app: AppHandle) {
+    // An unreadable config (e.g. mid-write by the CLI) is skipped, not a change.
+    let mut last = passkey_enabled();
+    loop {
+        tokio::time::sleep(PASSKEY_POLL_INTERVAL).await;
+        let enabled = match passkey_enabled() {
+            Some(enabled) => enabled,
+            None => continue,
+        };
+        let changed = last.is_some_and(|last| last != enabled);
+        last = Some(enabled);
+        if changed {
+            app.state::<StoreCache>().invalidate();
+            let _ = app.emit_all(PASSKEY_STATE_CHANGED_EVENT, PasskeyStateChanged { enabled });
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::AppError;
+use once_cell::sync::Lazy;
+use std::env;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use tauri::AppHandle;
+
+/// A store location chosen at init, passed to the CLI as `VIBESAFE_HOME`.
+static STORE_OVERRIDE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// The per-user config directory (settings.json). Unavailable when the platform
+/// reports no home directory, e.g. some portable launches.
+pub fn app_config_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path_resolver()
+        .app_config_dir()
+        .ok_or(AppError::AppDirUnavailable("config"))
+}
+
+/// The per-user data directory (the access log).
+pub fn app_data_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path_resolver()
+        .app_data_dir()
+        .ok_or(AppError::AppDirUnavailable("data"))
+}
+
+pub fn set_store_override(dir: Option<PathBuf>) {
+    *STORE_OVERRIDE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = dir;
+}
+
+pub fn store_override() -> Option<PathBuf> {
+    STORE_OVERRIDE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// The CLI's store: key pair, secrets.json and config.json. The location chosen
+/// at init if there is one, else `$VIBESAFE_HOME`, else `~/.vibesafe`.
+pub fn store_dir() -> Result<PathBuf, AppError> {
+    if let Some(dir) = store_override() {
+        return Ok(dir);
+    }
+    if let Some(dir) = env::var_os("VIBESAFE_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".vibesafe"))
+        .ok_or(AppError::AppDirUnavailable("home"))
+}
@@ -0,0 +1,289 @@
+use crate::atomic::atomic_write_json;
+use crate::error::AppError;
+use crate::paths;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State, WindowBuilder, WindowEvent, WindowUrl};
+use zeroize::Zeroizing;
+
+/// In the app data directory. Holds an argon2id hash of the PIN, never the PIN.
+const PIN_FILE: &str = "confirmation_pin.json";
+const PIN_WINDOW: &str = "pin";
+/// The frontend renders the PIN prompt instead of the main view for this hash.
+const PIN_WINDOW_URL: &str = "index.html#pin";
+const MIN_PIN_DIGITS: usize = 4;
+const MAX_PIN_DIGITS: usize = 12;
+/// An unanswered prompt counts as cancelled after this long.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Serialize, Deserialize)]
+struct StoredPin {
+    /// PHC string: algorithm, parameters, salt and hash.
+    hash: String,
+}
+
+fn pin_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(paths::app_data_dir(app)?.join(PIN_FILE))
+}
+
+fn read_hash_from(path: &Path) -> Result<Option<String>, AppError> {
+    match fs::read(path) {
+        Ok(contents) => serde_json::from_slice::<StoredPin>(&contents)
+            .map(|stored| Some(stored.hash))
+            .map_err(|e| AppError::Cli(format!("{} is invalid: {}", path.display(), e))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::Cli(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+fn check_format(pin: &str) -> Result<(), AppError> {
+    let digits = pin.chars().count();
+    if !pin.chars().all(|c| c.is_ascii_digit())
+        || !(MIN_PIN_DIGITS..=MAX_PIN_DIGITS).contains(&digits)
+    {
+        return Err(AppError::InvalidInput(format!(
+            "The PIN must be {} to {} digits",
+            MIN_PIN_DIGITS, MAX_PIN_DIGITS
+        )));
+    }
+    Ok(())
+}
+
+fn hash_pin(pin: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Cli(format!("Failed to hash the PIN: {}", e)))
+}
+
+fn pin_matches(hash: &str, pin: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(pin.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Replaces the PIN at `path` with `pin`. When one is set, `current` must match it.
+fn set_pin_at(path: &Path, pin: &str, current: Option<&str>) -> Result<(), AppError> {
+    check_format(pin)?;
+    if let Some(hash) = read_hash_from(path)? {
+        if !current.is_some_and(|current| pin_matches(&hash, current)) {
+            return Err(AppError::AuthDenied);
+        }
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let stored = StoredPin {
+        hash: hash_pin(pin)?,
+    };
+    atomic_write_json(path, &stored)
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+type Answer = Option<Zeroizing<String>>;
+
+/// The PIN prompt that's open, if any. Only one is shown at a time.
+#[derive(Default)]
+pub struct PinPrompts {
+    pending: Mutex<Option<(String, Sender<Answer>)>>,
+}
+
+impl PinPrompts {
+    /// Registers a prompt for `reason`; its answer arrives on the receiver.
+    fn begin(&self, reason: &str) -> Result<Receiver<Answer>, AppError> {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        if pending.is_some() {
+            return Err(AppError::AuthUnavailable(
+                "Another PIN prompt is already open".to_string(),
+            ));
+        }
+        let (sender, receiver) = mpsc::channel();
+        *pending = Some((reason.to_string(), sender));
+        Ok(receiver)
+    }
+
+    /// Hands `answer` to the waiting prompt; `None` cancels it. Nothing happens
+    /// when no prompt is open.
+    fn answer(&self, answer: Answer) {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some((_, sender)) = pending {
+            let _ = sender.send(answer);
+        }
+    }
+
+    fn reason(&self) -> Option<String> {
+        self.pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(|(reason, _)| reason.clone())
+    }
+}
+
+/// Blocking: shows the PIN window for `reason` and checks what the user
+/// enters. Closing the window or leaving it for two minutes cancels; a wrong PIN
+/// is a refusal, which `auth::confirm_presence` counts towards the lockout.
+/// Fails with `AuthUnavailable` when no PIN has been set.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn prompt(app: &AppHandle, reason: &str) -> Result<(), AppError> {
+    let hash = read_hash_from(&pin_path(app)?)?.ok_or_else(|| {
+        AppError::AuthUnavailable(
+            "No confirmation PIN is set. Set one with set_confirmation_pin.".to_string(),
+        )
+    })?;
+    let prompts = app.state::<PinPrompts>();
+    let receiver = prompts.begin(reason)?;
+    let window = WindowBuilder::new(app, PIN_WINDOW, WindowUrl::App(PIN_WINDOW_URL.into()))
+        .title("VibeSafe")
+        .inner_size(360.0, 220.0)
+        .resizable(false)
+        .always_on_top(true)
+        .center()
+        .focused(true)
+        .build();
+    let window = match window {
+        Ok(window) => window,
+        Err(e) => {
+            prompts.answer(None);
+            return Err(AppError::AuthUnavailable(format!(
+                "Couldn't open the PIN prompt: {}",
+                e
+            )));
+        }
+    };
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if matches!(event, WindowEvent::CloseRequested { .. }) {
+            handle.state::<PinPrompts>().answer(None);
+        }
+    });
+
+    let answer = receiver.recv_timeout(PROMPT_TIMEOUT).ok().flatten();
+    prompts.answer(None);
+    let _ = window.close();
+    match answer {
+        None => Err(AppError::AuthCancelled),
+        Some(pin) if pin_matches(&hash, &pin) => Ok(()),
+        Some(_) => Err(AppError::AuthDenied),
+    }
+}
+
+/// Why the open PIN prompt is asking; read by the PIN window.
+#[command]
+pub fn get_pin_prompt(prompts: State<'_, PinPrompts>) -> Option<String> {
+    prompts.reason()
+}
+
+#[command]
+pub fn submit_confirmation_pin(prompts: State<'_, PinPrompts>, pin: String) {
+    prompts.answer(Some(Zeroizing::new(pin)));
+}
+
+#[command]
+pub fn cancel_confirmation_pin(prompts: State<'_, PinPrompts>) {
+    prompts.answer(None);
+}
+
+/// Whether a confirmation PIN is set for the PIN prompt to check against.
+#[command]
+pub fn has_confirmation_pin(app: AppHandle) -> Result<bool, AppError> {
+    Ok(read_hash_from(&pin_path(&app)?)?.is_some())
+}
+
+/// Sets the PIN the confirmation prompt asks for where polkit isn't available:
+/// 4 to 12 digits. Changing it needs `current_pin`.
+#[command]
+pub fn set_confirmation_pin(
+    app: AppHandle,
+    pin: String,
+    current_pin: Option<String>,
+) -> Result<(), AppError> {
+    let pin = Zeroizing::new(pin);
+    let current_pin = current_pin.map(Zeroizing::new);
+    set_pin_at(
+        &pin_path(&app)?,
+        &pin,
+        current_pin.as_deref().map(String::as_str),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_are_digits_only() {
+        assert!(check_format("1234").is_ok());
+        assert!(check_format("123456789012").is_ok());
+        assert!(check_format("123").is_err());
+        assert!(check_format("1234567890123").is_err());
+        assert!(check_format("12a4").is_err());
+    }
+
+    #[test]
+    fn changing_the_pin_needs_the_current_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PIN_FILE);
+        assert_eq!(read_hash_from(&path).unwrap(), None);
+        set_pin_at(&path, "1234", None).unwrap();
+        let hash = read_hash_from(&path).unwrap().unwrap();
+        assert!(!hash.contains("1234"));
+        assert!(pin_matches(&hash, "1234"));
+        assert!(!pin_matches(&hash, "4321"));
+
+        assert!(matches!(
+            set_pin_at(&path, "5678", None),
+            Err(AppError::AuthDenied)
+        ));
+        assert!(matches!(
+            set_pin_at(&path, "5678", Some("0000")),
+            Err(AppError::AuthDenied)
+        ));
+        set_pin_at(&path, "5678", Some("1234")).unwrap();
+        assert!(pin_matches(
+            &read_hash_from(&path).unwrap().unwrap(),
+            "5678"
+        ));
+    }
+
+    #[test]
+    fn answers_reach_the_open_prompt_only() {
+        let prompts = PinPrompts::default();
+        // No prompt open: dropped.
+        prompts.answer(Some(Zeroizing::new("1234".to_string())));
+
+        let receiver = prompts.begin("Reveal API_KEY").unwrap();
+        assert_eq!(prompts.reason().as_deref(), Some("Reveal API_KEY"));
+        assert!(prompts.begin("Another").is_err());
+        std::thread::scope(|scope| {
+            scope.spawn(|| prompts.answer(Some(Zeroizing::new("1234".to_string()))));
+        });
+        let answer = receiver.recv().unwrap().unwrap();
+        assert_eq!(answer.as_str(), "1234");
+        assert_eq!(prompts.reason(), None);
+
+        let receiver = prompts.begin("Copy API_KEY").unwrap();
+        prompts.answer(None);
+        assert!(receiver.recv().unwrap().is_none());
+    }
+}
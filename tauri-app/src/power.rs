@@ -0,0 +1,228 @@
+use crate::api_server;
+use crate::cache::StoreCache;
+use crate::{clipboard, state};
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+/// Emitted with a `Locked` when the app drops what it held in memory because
+/// the machine is going to sleep (or `wipe_caches` was called).
+const LOCKED_EVENT: &str = "vibesafe:locked";
+
+#[derive(Debug, Clone, Serialize)]
+struct Locked {
+    /// Set when a copied value couldn't be cleared from the clipboard.
+    clipboard_error: Option<String>,
+}
+
+/// Locks a passkey-protected store again, drops the cached status and secret
+/// list, stops the local API server, and clears a copied value still on the
+/// clipboard (which also cancels its pending auto-clear and any pin). A
+/// clipboard holding something the app didn't put there is left alone.
+fn wipe(app: &AppHandle) {
+    state::lock();
+    app.state::<StoreCache>().invalidate();
+    api_server::stop(app);
+    let locked = Locked {
+        clipboard_error: clipboard::clear_secret(app).err().map(|e| e.to_string()),
+    };
+    let _ = app.emit_all(LOCKED_EVENT, locked);
+}
+
+/// After a wake, fetches the status again so the UI and tray don't show what
+/// was true before the machine slept.
+fn resumed(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = crate::cached_status(&app, true).await;
+    });
+}
+
+/// Locks the app now, the same way going to sleep does: caches and a copied
+/// value are cleared and the local API server is stopped.
+#[command]
+pub fn wipe_caches(app: AppHandle) {
+    wipe(&app);
+}
+
+/// Starts watching for the machine going to sleep and waking up. Called from
+/// setup, on the main thread.
+pub fn watch(app: &AppHandle) {
+    platform::watch(app);
+}
+
+/// logind announces sleep with `PrepareForSleep(true)` and wake with
+/// `PrepareForSleep(false)`. A delay inhibitor, taken at start and after every
+/// wake, holds the suspend back until the wipe is done.
+#[cfg(target_os = "linux")]
+mod platform {
+    use tauri::AppHandle;
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::OwnedFd;
+
+    fn inhibit(manager: &Proxy<'_>) -> Option<OwnedFd> {
+        manager
+            .call(
+                "Inhibit",
+                &(
+                    "sleep",
+                    "VibeSafe",
+                    "Clearing cached secrets before sleep",
+                    "delay",
+                ),
+            )
+            .ok()
+    }
+
+    fn listen(app: &AppHandle) -> zbus::Result<()> {
+        let connection = Connection::system()?;
+        let manager = Proxy::new(
+            &connection,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )?;
+        let mut inhibitor = inhibit(&manager);
+        for signal in manager.receive_signal("PrepareForSleep")? {
+            let going_to_sleep: bool = match signal.body().deserialize() {
+                Ok(going_to_sleep) => going_to_sleep,
+                Err(_) => continue,
+            };
+            if going_to_sleep {
+                super::wipe(app);
+                // Closing the descriptor lets the suspend go ahead.
+                inhibitor.take();
+            } else {
+                inhibitor = inhibit(&manager);
+                super::resumed(app);
+            }
+        }
+        Ok(())
+    }
+
+    /// Without logind (no systemd, a sandbox) sleep isn't noticed.
+    pub fn watch(app: &AppHandle) {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            let _ = listen(&app);
+        });
+    }
+}
+
+/// NSWorkspace posts `NSWorkspaceWillSleepNotification` before the machine
+/// sleeps and `NSWorkspaceDidWakeNotification` after, on the main thread. The
+/// observers stay registered for the life of the process.
+#[cfg(target_os = "macos")]
+mod platform {
+    use block::ConcreteBlock;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use tauri::AppHandle;
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {
+        static NSWorkspaceWillSleepNotification: *mut Object;
+        static NSWorkspaceDidWakeNotification: *mut Object;
+    }
+
+    unsafe fn observe(center: *mut Object, name: *mut Object, handler: impl Fn() + 'static) {
+        let block = ConcreteBlock::new(move |_notification: *mut Object| handler()).copy();
+        let nil = std::ptr::null_mut::<Object>();
+        let _: *mut Object = msg_send![center,
+            addObserverForName: name
+            object: nil
+            queue: nil
+            usingBlock: &*block];
+    }
+
+    pub fn watch(app: &AppHandle) {
+        let (sleeping, waking) = (app.clone(), app.clone());
+        unsafe {
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let center: *mut Object = msg_send![workspace, notificationCenter];
+            observe(center, NSWorkspaceWillSleepNotification, move || {
+                super::wipe(&sleeping)
+            });
+            observe(center, NSWorkspaceDidWakeNotification, move || {
+                super::resumed(&waking)
+            });
+        }
+    }
+}
+
+/// Windows calls back with `PBT_APMSUSPEND` before suspending and
+/// `PBT_APMRESUMEAUTOMATIC` after any wake. Suspend waits for the callback
+/// (up to two seconds), so the wipe finishes first. The registration is never
+/// removed, so its context lives for the life of the process.
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use tauri::AppHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Power::{
+        PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_CALLBACK,
+        DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+    };
+
+    const PBT_APMSUSPEND: u32 = 4;
+    const PBT_APMRESUMEAUTOMATIC: u32 = 18;
+
+    unsafe extern "system" fn changed(
+        context: *const c_void,
+        kind: u32,
+        _setting: *const c_void,
+    ) -> u32 {
+        let app = &*(context as *const AppHandle);
+        match kind {
+            PBT_APMSUSPEND => super::wipe(app),
+            PBT_APMRESUMEAUTOMATIC => super::resumed(app),
+            _ => {}
+        }
+        0
+    }
+
+    /// If registration fails, sleep isn't noticed.
+    pub fn watch(app: &AppHandle) {
+        let parameters = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(changed),
+            Context: Box::into_raw(Box::new(app.clone())) as *mut c_void,
+        }));
+        let mut registration = std::ptr::null_mut();
+        unsafe {
+            PowerRegisterSuspendResumeNotification(
+                DEVICE_NOTIFY_CALLBACK.0,
+                HANDLE(parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as isize),
+                &mut registration,
+            );
+        }
+    }
+}
+
+/// On other platforms there's no notification hooked, so a jump in wall-clock
+/// time between two ticks stands in for one: the process was suspended, and
+/// the wipe runs only as the machine wakes, after the fact.
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use std::time::{Duration, SystemTime};
+    use tauri::AppHandle;
+
+    const TICK: Duration = Duration::from_secs(5);
+    /// A gap this much longer than a tick means the machine slept.
+    const SLEEP_GAP: Duration = Duration::from_secs(30);
+
+    pub fn watch(app: &AppHandle) {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            let mut last = SystemTime::now();
+            loop {
+                std::thread::sleep(TICK);
+                let now = SystemTime::now();
+                let gap = now.duration_since(last).unwrap_or_default();
+                last = now;
+                if gap > TICK + SLEEP_GAP {
+                    super::wipe(&app);
+                    super::resumed(&app);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,276 @@
+use crate::error::AppError;
+use crate::{metadata, names, runner};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle};
+
+/// A directory and the secrets it needs, stored in the metadata file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub path: PathBuf,
+    pub secrets: Vec<ProjectSecret>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSecret {
+    pub secret: String,
+    /// Variable the secret is exposed as; the secret name unless overridden.
+    pub env_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectMatch {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectEnv {
+    /// Environment variable name to secret name, ready for `run_with_secrets`.
+    pub env: BTreeMap<String, String>,
+    /// Entries whose secret no longer exists (deleted or renamed outside the app).
+    /// They are kept so the project can be repaired instead of silently shrinking,
+    /// until `cleanup_orphaned_metadata` runs (it does after every in-app delete).
+    pub broken: Vec<ProjectSecret>,
+}
+
+fn canonical_dir(path: &str) -> Result<PathBuf, AppError> {
+    let path = Path::new(path)
+        .canonicalize()
+        .map_err(|e| AppError::InvalidInput(format!("Can't use {}: {}", path, e)))?;
+    if !path.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "{} isn't a directory",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+impl Project {
+    /// Checks the secret names and the variable each is exposed as.
+    fn new(
+        path: PathBuf,
+        secret_names: Vec<String>,
+        env_overrides: BTreeMap<String, String>,
+    ) -> Result<Self, AppError> {
+        let mut env_names = HashSet::new();
+        let mut secrets = Vec::with_capacity(secret_names.len());
+        for secret in secret_names {
+            names::check_secret_name(&secret)?;
+            let env_name = env_overrides
+                .get(&secret)
+                .cloned()
+                .unwrap_or_else(|| secret.clone());
+            if !runner::is_env_name(&env_name) {
+                return Err(AppError::InvalidInput(format!(
+                    "'{}' isn't a valid environment variable name; set an override for {}",
+                    env_name, secret
+                )));
+            }
+            if !env_names.insert(env_name.clone()) {
+                return Err(AppError::InvalidInput(format!(
+                    "Two secrets would both set {}",
+                    env_name
+                )));
+            }
+            secrets.push(ProjectSecret { secret, env_name });
+        }
+        Ok(Project { path, secrets })
+    }
+
+    /// Points entries for `from` at `to`. Returns whether there were any.
+    pub fn rename_secret(&mut self, from: &str, to: &str) -> bool {
+        let mut renamed = false;
+        for entry in &mut self.secrets {
+            if entry.secret == from {
+                entry.secret = to.to_string();
+                renamed = true;
+            }
+        }
+        renamed
+    }
+
+    /// Splits the entries into those whose secret is in `existing` and the rest.
+    fn env(self, existing: &HashSet<String>) -> ProjectEnv {
+        let mut env = BTreeMap::new();
+        let mut broken = Vec::new();
+        for entry in self.secrets {
+            if existing.contains(&entry.secret) {
+                env.insert(entry.env_name, entry.secret);
+            } else {
+                broken.push(entry);
+            }
+        }
+        ProjectEnv { env, broken }
+    }
+}
+
+/// Registers (or replaces) a project. `env_overrides` maps secret name to the
+/// variable name to use instead of the secret's own name.
+#[command]
+pub fn create_project(
+    app: AppHandle,
+    name: String,
+    path: String,
+    secret_names: Vec<String>,
+    env_overrides: Option<BTreeMap<String, String>>,
+) -> Result<Project, AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Project name can't be empty".to_string(),
+        ));
+    }
+    let project = Project::new(
+        canonical_dir(&path)?,
+        secret_names,
+        env_overrides.unwrap_or_default(),
+    )?;
+    metadata::update(&app, |metadata| {
+        metadata.projects.insert(name, project.clone());
+        Ok(())
+    })?;
+    Ok(project)
+}
+
+#[command]
+pub fn list_projects(app: AppHandle) -> Result<BTreeMap<String, Project>, AppError> {
+    Ok(metadata::load(&app)?.projects)
+}
+
+#[command]
+pub fn delete_project(app: AppHandle, name: String) -> Result<(), AppError> {
+    metadata::update(&app, |metadata| {
+        metadata
+            .projects
+            .remove(&name)
+            .map(|_| ())
+            .ok_or_else(|| AppError::InvalidInput(format!("No project named '{}'", name)))
+    })
+}
+
+/// The project whose directory most closely contains `cwd`, if any.
+fn closest(projects: BTreeMap<String, Project>, cwd: &Path) -> Option<ProjectMatch> {
+    projects
+        .into_iter()
+        .filter(|(_, project)| cwd.starts_with(&project.path))
+        .max_by_key(|(_, project)| project.path.components().count())
+        .map(|(name, project)| ProjectMatch {
+            name,
+            path: project.path,
+        })
+}
+
+pub fn project_for(app: &AppHandle, cwd: &str) -> Result<Option<ProjectMatch>, AppError> {
+    let cwd = canonical_dir(cwd)?;
+    Ok(closest(metadata::load(app)?.projects, &cwd))
+}
+
+#[command]
+pub fn detect_project(app: AppHandle, cwd: String) -> Result<Option<ProjectMatch>, AppError> {
+    project_for(&app, &cwd)
+}
+
+/// A project's variable mapping, checked against the current secret list.
+#[command]
+pub async fn get_project_env(app: AppHandle, name: String) -> Result<ProjectEnv, AppError> {
+    let project = metadata::load(&app)?
+        .projects
+        .remove(&name)
+        .ok_or_else(|| AppError::InvalidInput(format!("No project named '{}'", name)))?;
+    let existing: HashSet<String> = crate::cached_secrets(&app, false)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    Ok(project.env(&existing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(secrets: &[&str], overrides: &[(&str, &str)]) -> Result<Project, AppError> {
+        Project::new(
+            PathBuf::from("/work/app"),
+            secrets.iter().map(|name| name.to_string()).collect(),
+            overrides
+                .iter()
+                .map(|(secret, env)| (secret.to_string(), env.to_string()))
+                .collect(),
+        )
+    }
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn projects_round_trip_through_the_metadata_file() {
+        let mut metadata = metadata::Metadata::default();
+        metadata.projects.insert(
+            "app".to_string(),
+            project(&["STRIPE_KEY", "db-url"], &[("db-url", "DATABASE_URL")]).unwrap(),
+        );
+        let json = serde_json::to_string(&metadata).unwrap();
+        let project = serde_json::from_str::<metadata::Metadata>(&json)
+            .unwrap()
+            .projects
+            .remove("app")
+            .unwrap();
+        assert_eq!(project.path, Path::new("/work/app"));
+        let env = project.env(&names(&["STRIPE_KEY", "db-url"]));
+        assert_eq!(env.env["STRIPE_KEY"], "STRIPE_KEY");
+        assert_eq!(env.env["DATABASE_URL"], "db-url");
+        assert!(env.broken.is_empty());
+    }
+
+    #[test]
+    fn variable_names_are_checked() {
+        assert!(project(&["db-url"], &[]).is_err());
+        assert!(project(&["A", "B"], &[("B", "A")]).is_err());
+        assert!(project(&["A", "B"], &[("B", "C")]).is_ok());
+    }
+
+    #[test]
+    fn renamed_secrets_are_marked_broken_not_dropped() {
+        let mut project = project(&["OLD_KEY", "OTHER"], &[("OLD_KEY", "API_KEY")]).unwrap();
+
+        // Renamed outside the app: the entry stays, flagged.
+        let env = project.clone().env(&names(&["NEW_KEY", "OTHER"]));
+        assert_eq!(env.env.keys().collect::<Vec<_>>(), ["OTHER"]);
+        assert_eq!(env.broken.len(), 1);
+        assert_eq!(env.broken[0].secret, "OLD_KEY");
+        assert_eq!(env.broken[0].env_name, "API_KEY");
+        assert_eq!(project.secrets.len(), 2);
+
+        // Renamed through the app: the entry follows, keeping its variable.
+        project.rename_secret("OLD_KEY", "NEW_KEY");
+        let env = project.env(&names(&["NEW_KEY", "OTHER"]));
+        assert_eq!(env.env["API_KEY"], "NEW_KEY");
+        assert!(env.broken.is_empty());
+    }
+
+    #[test]
+    fn the_innermost_project_wins() {
+        let mut projects = BTreeMap::new();
+        for (name, path) in [
+            ("repo", "/work"),
+            ("app", "/work/app"),
+            ("other", "/elsewhere"),
+        ] {
+            projects.insert(
+                name.to_string(),
+                Project {
+                    path: PathBuf::from(path),
+                    secrets: Vec::new(),
+                },
+            );
+        }
+        let found = |cwd: &str| closest(projects.clone(), Path::new(cwd)).map(|found| found.name);
+        assert_eq!(found("/work/app/src").as_deref(), Some("app"));
+        assert_eq!(found("/work/lib").as_deref(), Some("repo"));
+        assert_eq!(found("/work-other"), None);
+    }
+}
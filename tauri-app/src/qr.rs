@@ -0,0 +1,191 @@
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::{cli, names, screen};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use qrcode::render::svg;
+use qrcode::types::QrError;
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State, Window};
+use zeroize::Zeroizing;
+
+/// Bytes a version 40 code holds in byte mode at the lowest error correction.
+const MAX_QR_BYTES: usize = 2953;
+/// Pixels per module in the PNG.
+const PNG_SCALE: usize = 8;
+/// Blank modules around the code; scanners need at least 4.
+const QUIET_ZONE: usize = 4;
+const SVG_SIZE: u32 = 320;
+const AUDIT_REASON: &str = "Shown as a QR code";
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QrImage {
+    /// `image/png` or `image/svg+xml`, for a data URL.
+    pub mime: &'static str,
+    /// The image, base64-encoded.
+    pub data: String,
+}
+
+/// The code as an 8-bit grayscale PNG, `PNG_SCALE` pixels per module.
+fn render_png(code: &QrCode) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    let width = code.width();
+    let size = (width + 2 * QUIET_ZONE) * PNG_SCALE;
+    let mut pixels = Zeroizing::new(vec![255u8; size * size]);
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != Color::Dark {
+            continue;
+        }
+        let (x, y) = (i % width + QUIET_ZONE, i / width + QUIET_ZONE);
+        for row in y * PNG_SCALE..(y + 1) * PNG_SCALE {
+            let start = row * size + x * PNG_SCALE;
+            pixels[start..start + PNG_SCALE].fill(0);
+        }
+    }
+
+    let failed = |e: png::EncodingError| AppError::Cli(format!("Failed to encode the PNG: {}", e));
+    let mut image = Zeroizing::new(Vec::new());
+    let mut encoder = png::Encoder::new(&mut *image, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(failed)?;
+    writer.write_image_data(&pixels).map_err(failed)?;
+    writer.finish().map_err(failed)?;
+    Ok(image)
+}
+
+/// Renders `value` as a QR code in `format`, base64-encoded.
+pub fn render(value: &[u8], format: QrFormat) -> Result<QrImage, AppError> {
+    let too_large = AppError::TooLargeForQr {
+        size: value.len(),
+        max: MAX_QR_BYTES,
+    };
+    if value.len() > MAX_QR_BYTES {
+        return Err(too_large);
+    }
+    let code = QrCode::with_error_correction_level(value, EcLevel::L).map_err(|e| match e {
+        QrError::DataTooLong => too_large,
+        e => AppError::Cli(format!("Failed to build the QR code: {}", e)),
+    })?;
+    Ok(match format {
+        QrFormat::Png => QrImage {
+            mime: "image/png",
+            data: BASE64.encode(&*render_png(&code)?),
+        },
+        QrFormat::Svg => {
+            let image = Zeroizing::new(
+                code.render::<svg::Color>()
+                    .min_dimensions(SVG_SIZE, SVG_SIZE)
+                    .quiet_zone(true)
+                    .build(),
+            );
+            QrImage {
+                mime: "image/svg+xml",
+                data: BASE64.encode(image.as_bytes()),
+            }
+        }
+    })
+}
+
+/// Shows `name` as a QR code for scanning with a phone. Refused while a screen
+/// recorder or sharing app is detected; otherwise needs a fresh confirmation,
+/// and the access log gets the name. The value and image buffers are zeroized
+/// once the response is built.
+#[command]
+pub async fn get_secret_qr(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    name: String,
+    format: QrFormat,
+) -> Result<QrImage, AppError> {
+    names::check_secret_name(&name)?;
+    if let Ok(recording) = screen::is_screen_being_recorded().await {
+        if recording.recording {
+            return Err(AppError::ScreenRecording { app: recording.app });
+        }
+    }
+    let reason = format!("Show {} as a QR code", name);
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let stdout = cli::get_secret(&name).await?;
+    let image = render(&stdout[..], format)?;
+    audit::record(&app, &name, AccessAction::Read, Some(AUDIT_REASON))?;
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_png(data: &str) -> (u32, Vec<u8>) {
+        let bytes = BASE64.decode(data).unwrap();
+        let mut reader = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+        assert_eq!(info.width, info.height);
+        pixels.truncate(info.buffer_size());
+        (info.width, pixels)
+    }
+
+    #[test]
+    fn png_modules_match_the_code() {
+        let value = b"otpauth://totp/VibeSafe?secret=JBSWY3DPEHPK3PXP";
+        let image = render(value, QrFormat::Png).unwrap();
+        assert_eq!(image.mime, "image/png");
+
+        let code = QrCode::with_error_correction_level(&value[..], EcLevel::L).unwrap();
+        let width = code.width();
+        let (size, pixels) = decode_png(&image.data);
+        assert_eq!(size as usize, (width + 2 * QUIET_ZONE) * PNG_SCALE);
+        let pixel = |x: usize, y: usize| pixels[y * size as usize + x];
+        // The quiet zone is blank, and each module's centre has its colour.
+        assert!((0..size as usize).all(|i| pixel(i, 0) == 255 && pixel(0, i) == 255));
+        for (i, color) in code.to_colors().into_iter().enumerate() {
+            let x = (i % width + QUIET_ZONE) * PNG_SCALE + PNG_SCALE / 2;
+            let y = (i / width + QUIET_ZONE) * PNG_SCALE + PNG_SCALE / 2;
+            assert_eq!(pixel(x, y), if color == Color::Dark { 0 } else { 255 });
+        }
+    }
+
+    #[test]
+    fn svgs_are_at_least_the_display_size() {
+        let image = render(b"value", QrFormat::Svg).unwrap();
+        assert_eq!(image.mime, "image/svg+xml");
+        let svg = String::from_utf8(BASE64.decode(&image.data).unwrap()).unwrap();
+        assert!(svg.contains("<svg"));
+        let width: u32 = svg
+            .split("width=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(width >= SVG_SIZE);
+    }
+
+    #[test]
+    fn values_over_the_capacity_are_refused() {
+        let (size, _) = decode_png(&render(&[0xff; MAX_QR_BYTES], QrFormat::Png).unwrap().data);
+        // Version 40 is 177 modules wide.
+        assert_eq!(size as usize, (177 + 2 * QUIET_ZONE) * PNG_SCALE);
+        for format in [QrFormat::Png, QrFormat::Svg] {
+            assert!(matches!(
+                render(&[0xff; MAX_QR_BYTES + 1], format),
+                Err(AppError::TooLargeForQr {
+                    size: 2954,
+                    max: MAX_QR_BYTES
+                })
+            ));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::metadata;
+use crate::state::{self, VaultState};
+use tauri::{command, AppHandle, Window};
+
+pub fn is_readonly(app: &AppHandle, name: &str) -> Result<bool, AppError> {
+    Ok(metadata::load(app)?.readonly.contains(name))
+}
+
+/// Whether a change to `name` has to be confirmed first; `READ_ONLY_SECRET`
+/// when it is read-only and there's no override.
+fn needs_confirmation(
+    readonly: bool,
+    override_readonly: bool,
+    name: &str,
+) -> Result<bool, AppError> {
+    match (readonly, override_readonly) {
+        (false, _) => Ok(false),
+        (true, false) => Err(AppError::ReadOnlySecret(name.to_string())),
+        (true, true) => Ok(true),
+    }
+}
+
+/// Lets a change to `name` through. A read-only secret fails with
+/// `READ_ONLY_SECRET` unless `override_readonly` is set, and then only after a
+/// fresh confirmation.
+pub async fn check_writable(
+    app: &AppHandle,
+    provider: SharedAuthProvider,
+    window: Window,
+    name: &str,
+    override_readonly: bool,
+) -> Result<(), AppError> {
+    if !needs_confirmation(is_readonly(app, name)?, override_readonly, name)? {
+        return Ok(());
+    }
+    auth::confirm_presence(
+        provider,
+        window,
+        format!("Change read-only secret {}", name),
+    )
+    .await
+}
+
+/// Marks a secret read-only on this machine, or clears the mark. Deleting or
+/// renaming a read-only secret then needs `override_readonly` and a
+/// confirmation, and batch renames skip it. The CLI knows nothing of the flag.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn set_secret_readonly(
+    app: AppHandle,
+    name: String,
+    readonly: bool,
+) -> Result<(), AppError> {
+    crate::names::check_secret_name(&name)?;
+    state::require(&app, &[VaultState::Ready]).await?;
+    if readonly
+        && !crate::cached_secrets(&app, false)
+            .await?
+            .iter()
+            .any(|secret| secret.name == name)
+    {
+        return Err(AppError::InvalidInput(format!("No secret named {}", name)));
+    }
+    metadata::update(&app, |metadata| {
+        if readonly {
+            metadata.readonly.insert(name);
+        } else {
+            metadata.readonly.remove(&name);
+        }
+        Ok(())
+    })
+}
+
+/// The names of the secrets marked read-only, sorted.
+#[command]
+pub fn list_readonly_secrets(app: AppHandle) -> Result<Vec<String>, AppError> {
+    Ok(metadata::load(&app)?.readonly.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_secrets_refuse_changes_without_an_override() {
+        assert!(matches!(
+            needs_confirmation(true, false, "PROD_KEY"),
+            Err(AppError::ReadOnlySecret(name)) if name == "PROD_KEY"
+        ));
+        assert!(needs_confirmation(true, true, "PROD_KEY").unwrap());
+    }
+
+    #[test]
+    fn other_secrets_change_freely() {
+        assert!(!needs_confirmation(false, false, "DEV_KEY").unwrap());
+        assert!(!needs_confirmation(false, true, "DEV_KEY").unwrap());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::auth::SharedAuthProvider;
+use crate::cache::StoreCache;
+use crate::error::AppError;
+use crate::hooks::{self, HookEvent};
+use crate::state::{self, VaultState};
+use crate::{cli, metadata, names, readonly, snapshots};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use tauri::{command, AppHandle, Manager, State, Window};
+use zeroize::Zeroizing;
+
+/// Renames one secret. The CLI has no rename, so the value is copied to `to` and
+/// `from` is deleted; if the delete fails the copy is removed again, leaving the
+/// store as it was. Project entries, the read-only flag and the URL follow the
+/// new name.
+async fn rename_one(app: &AppHandle, from: &str, to: &str) -> Result<(), AppError> {
+    let stdout = cli::get_secret(from).await?;
+    let value = &stdout[..];
+    if value.contains(&b'\n') {
+        return Err(AppError::InvalidInput(format!(
+            "{} contains a newline, which can't be passed back to the CLI",
+            from
+        )));
+    }
+    let mut input = Zeroizing::new(Vec::with_capacity(value.len() + 1));
+    input.extend_from_slice(value);
+    input.push(b'\n');
+
+    cli::add_with_stdin(to, &input).await?;
+    if let Err(error) = cli::run_vibesafe(&cli::action_args("delete", &[from])?).await {
+        let _ = cli::run_vibesafe(&cli::action_args("delete", &[to])?).await;
+        return Err(error);
+    }
+    app.state::<StoreCache>().invalidate();
+    hooks::fire(app, HookEvent::Deleted, from, "rename");
+    hooks::fire(app, HookEvent::Added, to, "rename");
+    metadata::rename_secret(app, from, to)
+}
+
+/// A read-only secret fails with `READ_ONLY_SECRET` unless `override_readonly`
+/// is set and the user confirms; it stays read-only under the new name.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn rename_secret(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    from: String,
+    to: String,
+    override_readonly: Option<bool>,
+) -> Result<(), AppError> {
+    names::check_secret_name(&to)?;
+    state::require(&app, &[VaultState::Ready]).await?;
+    readonly::check_writable(
+        &app,
+        provider.inner().clone(),
+        window,
+        &from,
+        override_readonly.unwrap_or(false),
+    )
+    .await?;
+    rename_one(&app, &from, &to).await
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenameItem {
+    pub from: String,
+    pub to: String,
+    /// Whether the rename was carried out; always false for a dry run.
+    pub applied: bool,
+    /// The secret is read-only and was left alone; rename it with
+    /// `rename_secret` and an override.
+    pub skipped_readonly: bool,
+    pub error: Option<String>,
+}
+
+/// The renames `rename` makes of `existing`, checked as `rename_by_pattern`
+/// describes. Read-only secrets are in the plan but skipped.
+fn plan_renames(
+    existing: &[String],
+    readonly: &BTreeSet<String>,
+    rename: impl Fn(&str) -> String,
+) -> Result<Vec<RenameItem>, AppError> {
+    let mut plan = Vec::new();
+    for name in existing {
+        let renamed = rename(name);
+        if renamed != *name {
+            plan.push(RenameItem {
+                from: name.clone(),
+                to: renamed,
+                applied: false,
+                skipped_readonly: readonly.contains(name),
+                error: None,
+            });
+        }
+    }
+
+    let existing: HashSet<&str> = existing.iter().map(String::as_str).collect();
+    let mut targets = HashSet::new();
+    for item in plan.iter().filter(|item| !item.skipped_readonly) {
+        names::check_secret_name(&item.to)
+            .map_err(|e| AppError::InvalidInput(format!("{} -> {}: {}", item.from, item.to, e)))?;
+        if existing.contains(item.to.as_str()) || !targets.insert(item.to.as_str()) {
+            return Err(AppError::InvalidInput(format!(
+                "{} -> {} collides with another secret",
+                item.from, item.to
+            )));
+        }
+    }
+    Ok(plan)
+}
+
+/// Renames every secret whose name matches `find`, replacing it with `replace`
+/// (a plain substring, or a regex with `$1`-style groups when `regex` is set).
+/// The whole plan is checked first: new names must be valid and must not collide
+/// with each other or with existing secrets, otherwise nothing is renamed.
+/// Read-only secrets are skipped and marked `skipped_readonly`. With `dry_run`
+/// the plan is returned without changes.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn rename_by_pattern(
+    app: AppHandle,
+    find: String,
+    replace: String,
+    regex: bool,
+    dry_run: bool,
+) -> Result<Vec<RenameItem>, AppError> {
+    if find.is_empty() {
+        return Err(AppError::InvalidInput(
+            "The search pattern is empty".to_string(),
+        ));
+    }
+    state::require(&app, &[VaultState::Ready]).await?;
+    let pattern = if regex {
+        Some(
+            Regex::new(&find)
+                .map_err(|e| AppError::InvalidInput(format!("Invalid pattern: {}", e)))?,
+        )
+    } else {
+        None
+    };
+
+    let existing: Vec<String> = crate::cached_secrets(&app, true)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    let readonly = metadata::load(&app)?.readonly;
+    let mut plan = plan_renames(&existing, &readonly, |name| match &pattern {
+        Some(pattern) => pattern.replace_all(name, replace.as_str()).into_owned(),
+        None => name.replace(&find, &replace),
+    })?;
+    if dry_run {
+        return Ok(plan);
+    }
+    if plan.iter().any(|item| !item.skipped_readonly) {
+        snapshots::take(&app, "rename_by_pattern")?;
+    }
+
+    for item in plan.iter_mut().filter(|item| !item.skipped_readonly) {
+        match rename_one(&app, &item.from, &item.to).await {
+            Ok(()) => item.applied = true,
+            Err(error) => item.error = Some(error.to_string()),
+        }
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn prod_to_live(name: &str) -> String {
+        name.replace("PROD_", "LIVE_")
+    }
+
+    #[test]
+    fn batch_renames_skip_read_only_secrets() {
+        let existing = strings(&["PROD_API", "PROD_DB", "DEV_API"]);
+        let readonly: BTreeSet<String> = ["PROD_DB".to_string()].into();
+        let plan = plan_renames(&existing, &readonly, prod_to_live).unwrap();
+
+        let summary: Vec<(&str, &str, bool)> = plan
+            .iter()
+            .map(|item| (item.from.as_str(), item.to.as_str(), item.skipped_readonly))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("PROD_API", "LIVE_API", false),
+                ("PROD_DB", "LIVE_DB", true)
+            ]
+        );
+        assert!(plan.iter().all(|item| !item.applied));
+    }
+
+    #[test]
+    fn a_skipped_secret_doesnt_block_the_plan() {
+        // LIVE_DB exists, but the only rename onto it is the read-only one.
+        let existing = strings(&["PROD_DB", "LIVE_DB"]);
+        let readonly: BTreeSet<String> = ["PROD_DB".to_string()].into();
+        assert!(plan_renames(&existing, &readonly, prod_to_live).is_ok());
+        assert!(plan_renames(&existing, &BTreeSet::new(), prod_to_live).is_err());
+    }
+
+    #[test]
+    fn collisions_and_invalid_names_fail_the_whole_plan() {
+        let existing = strings(&["PROD_A", "STAGING_A"]);
+        assert!(plan_renames(&existing, &BTreeSet::new(), |name| name
+            .replace("PROD_", "")
+            .replace("STAGING_", ""))
+        .is_err());
+        assert!(plan_renames(&existing, &BTreeSet::new(), |name| name.replace('_', " ")).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::atomic::atomic_write_json;
+use crate::cache::StoreCache;
+use crate::elevation::{Elevations, Operation};
+use crate::error::AppError;
+use crate::state::{self, VaultState};
+use crate::{cli, paths};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Manager, State};
+
+/// In the app data directory while a rotation runs, and after it until the next
+/// one, marked complete.
+const JOURNAL_FILE: &str = "rotation.json";
+/// Emitted with a `RotationProgress` as the CLI reaches each stage.
+const PROGRESS_EVENT: &str = "vibesafe://rotation-progress";
+/// Emitted at launch with the `RotationStatus` when a rotation didn't finish.
+const INCOMPLETE_EVENT: &str = "vibesafe://rotation-incomplete";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretState {
+    Pending,
+    Reencrypted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub name: String,
+    pub state: SecretState,
+}
+
+/// What a rotation was doing, by secret name. No values or keys: the store files
+/// are recorded only as SHA-256 digests, to tell afterwards whether the CLI got
+/// as far as writing them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Journal {
+    started_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    /// Taken just before the rotation; what to restore if it was cut off halfway.
+    snapshot_id: String,
+    public_key_digest: Option<String>,
+    secrets_digest: Option<String>,
+    secrets: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStage {
+    Starting,
+    Decrypting,
+    GeneratingKeys,
+    Reencrypting,
+    Saving,
+    Complete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationProgress {
+    pub stage: RotationStage,
+    /// Secrets re-encrypted and saved. The CLI writes the whole store at once,
+    /// so this goes from 0 to `total` at the end.
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationStatus {
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub snapshot_id: String,
+    pub done: usize,
+    pub total: usize,
+}
+
+fn journal_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(paths::app_data_dir(app)?.join(JOURNAL_FILE))
+}
+
+fn read_journal(app: &AppHandle) -> Result<Option<Journal>, AppError> {
+    let path = journal_path(app)?;
+    match fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents).map(Some).map_err(|e| {
+            AppError::UnparseableCliOutput(format!("{} is invalid: {}", path.display(), e))
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::Cli(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+fn write_journal(app: &AppHandle, journal: &Journal) -> Result<(), AppError> {
+    let path = journal_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    atomic_write_json(&path, journal)
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// SHA-256 of a store file, `None` if it doesn't exist.
+fn store_digest(file: &str) -> Result<Option<String>, AppError> {
+    let path = paths::store_dir()?.join(file);
+    match fs::read(&path) {
+        Ok(contents) => Ok(Some(
+            Sha256::digest(&contents)
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::Cli(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+impl Journal {
+    fn status(&self) -> RotationStatus {
+        RotationStatus {
+            started_at: self.started_at,
+            completed_at: self.completed_at,
+            snapshot_id: self.snapshot_id.clone(),
+            done: self
+                .secrets
+                .iter()
+                .filter(|entry| entry.state == SecretState::Reencrypted)
+                .count(),
+            total: self.secrets.len(),
+        }
+    }
+
+    /// Marks every secret re-encrypted and the rotation finished.
+    fn complete(&mut self) {
+        for entry in &mut self.secrets {
+            entry.state = SecretState::Reencrypted;
+        }
+        self.completed_at = Some(Utc::now());
+    }
+}
+
+/// The stage a line of `vibesafe rotate` output starts, if it starts one.
+fn stage_of(line: &str) -> Option<RotationStage> {
+    if line.contains("Decrypting secrets") {
+        Some(RotationStage::Decrypting)
+    } else if line.contains("Generating new key pair") {
+        Some(RotationStage::GeneratingKeys)
+    } else if line.contains("Re-encrypting secrets") {
+        Some(RotationStage::Reencrypting)
+    } else if line.contains("Backed up old") {
+        Some(RotationStage::Saving)
+    } else {
+        None
+    }
+}
+
+fn emit_progress(app: &AppHandle, stage: RotationStage, done: usize, total: usize) {
+    let _ = app.emit_all(PROGRESS_EVENT, RotationProgress { stage, done, total });
+}
+
+/// Runs `vibesafe rotate` under `journal`, which is written first and marked
+/// complete (every secret re-encrypted) once the CLI succeeds.
+async fn rotate(app: &AppHandle, mut journal: Journal) -> Result<(), AppError> {
+    write_journal(app, &journal)?;
+    let total = journal.secrets.len();
+    emit_progress(app, RotationStage::Starting, 0, total);
+
+    let mut last_stage = RotationStage::Starting;
+    let outcome = cli::run_vibesafe_with_progress(app, &["rotate", "--yes"], |line| {
+        if let Some(stage) = stage_of(line).filter(|stage| *stage != last_stage) {
+            last_stage = stage;
+            emit_progress(app, stage, 0, total);
+        }
+    })
+    .await;
+    app.state::<StoreCache>().invalidate();
+    if let Err(error) = outcome {
+        // A CLI that failed before writing anything leaves nothing to resume.
+        let untouched = store_digest("public.pem").ok() == Some(journal.public_key_digest.clone())
+            && store_digest("secrets.json").ok() == Some(journal.secrets_digest.clone());
+        if untouched {
+            let _ = journal_path(app).map(fs::remove_file);
+        }
+        return Err(error);
+    }
+
+    journal.complete();
+    write_journal(app, &journal)?;
+    emit_progress(app, RotationStage::Complete, total, total);
+    Ok(())
+}
+
+/// Starts a journaled rotation of every secret, after `snapshot_id` was taken.
+pub async fn run(app: &AppHandle, snapshot_id: String) -> Result<(), AppError> {
+    let secrets = crate::list_secrets()
+        .await?
+        .into_iter()
+        .map(|secret| JournalEntry {
+            name: secret.name,
+            state: SecretState::Pending,
+        })
+        .collect();
+    let journal = Journal {
+        started_at: Utc::now(),
+        completed_at: None,
+        snapshot_id,
+        public_key_digest: store_digest("public.pem")?,
+        secrets_digest: store_digest("secrets.json")?,
+        secrets,
+    };
+    rotate(app, journal).await
+}
+
+/// At launch: emits "vibesafe://rotation-incomplete" if the last rotation
+/// never finished, so the UI can offer `resume_key_rotation`.
+pub fn check_incomplete(app: &AppHandle) {
+    if let Ok(Some(journal)) = read_journal(app) {
+        if journal.completed_at.is_none() {
+            let _ = app.emit_all(INCOMPLETE_EVENT, journal.status());
+        }
+    }
+}
+
+/// The last rotation's journal, `None` if the app never rotated keys.
+#[command]
+pub fn get_key_rotation_status(app: AppHandle) -> Result<Option<RotationStatus>, AppError> {
+    Ok(read_journal(&app)?.map(|journal| journal.status()))
+}
+
+/// Finishes a rotation the app quit during. The CLI writes the new key pair and
+/// the re-encrypted store in one go, so the store files show how far it got:
+/// both unchanged means nothing was written and the rotation runs again; both
+/// changed means it had finished, and the journal is just marked complete. A new
+/// key with the old store can't be repaired here and points at the snapshot
+/// taken before the rotation. Needs a token from `require_elevation` for
+/// `rotate_keypair`.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn resume_key_rotation(
+    app: AppHandle,
+    elevations: State<'_, Elevations>,
+    elevation_token: String,
+) -> Result<RotationStatus, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let mut journal = read_journal(&app)?
+        .filter(|journal| journal.completed_at.is_none())
+        .ok_or_else(|| AppError::InvalidInput("No key rotation to resume".to_string()))?;
+    elevations.redeem(&elevation_token, Operation::RotateKeypair)?;
+
+    let key_changed = store_digest("public.pem")? != journal.public_key_digest;
+    let store_changed = store_digest("secrets.json")? != journal.secrets_digest;
+    match (key_changed, store_changed) {
+        (false, false) => {
+            journal.started_at = Utc::now();
+            rotate(&app, journal.clone()).await?;
+            journal = read_journal(&app)?.unwrap_or(journal);
+        }
+        (true, true) => {
+            journal.complete();
+            write_journal(&app, &journal)?;
+            emit_progress(
+                &app,
+                RotationStage::Complete,
+                journal.secrets.len(),
+                journal.secrets.len(),
+            );
+        }
+        _ => {
+            return Err(AppError::Cli(format!(
+                "The rotation stopped between writing the new key pair and the re-encrypted \
+                 secrets. Restore snapshot {} to get back to the state before it.",
+                journal.snapshot_id
+            )))
+        }
+    }
+    app.state::<StoreCache>().invalidate();
+    Ok(journal.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal() -> Journal {
+        Journal {
+            started_at: Utc::now(),
+            completed_at: None,
+            snapshot_id: "20260101T000000Z-rotate_keypair".to_string(),
+            public_key_digest: Some("ab".repeat(32)),
+            secrets_digest: None,
+            secrets: ["A", "B", "C"]
+                .iter()
+                .map(|name| JournalEntry {
+                    name: name.to_string(),
+                    state: SecretState::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn cli_output_maps_to_stages() {
+        let output = [
+            ("🔄 Starting key rotation...", None),
+            ("Found 3 secret(s) to re-encrypt.", None),
+            ("🔓 Loading current private key...", None),
+            (
+                "🔓 Decrypting secrets with current key...",
+                Some(RotationStage::Decrypting),
+            ),
+            (
+                "🔐 Generating new key pair...",
+                Some(RotationStage::GeneratingKeys),
+            ),
+            (
+                "🔒 Re-encrypting secrets with new key...",
+                Some(RotationStage::Reencrypting),
+            ),
+            (
+                "💾 Backed up old private key to: /home/u/.vibesafe/key_backup/private.pem",
+                Some(RotationStage::Saving),
+            ),
+        ];
+        for (line, stage) in output {
+            assert_eq!(stage_of(line), stage, "{}", line);
+        }
+    }
+
+    #[test]
+    fn status_counts_reencrypted_secrets() {
+        let mut journal = journal();
+        journal.secrets[1].state = SecretState::Reencrypted;
+        let status = journal.status();
+        assert_eq!((status.done, status.total), (1, 3));
+        assert!(status.completed_at.is_none());
+
+        journal.complete();
+        let status = journal.status();
+        assert_eq!((status.done, status.total), (3, 3));
+        assert!(status.completed_at.is_some());
+    }
+
+    #[test]
+    fn journals_round_trip() {
+        let journal = journal();
+        let json = serde_json::to_string(&journal).unwrap();
+        assert!(json.contains("\"state\":\"pending\""));
+
+        let read: Journal = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.snapshot_id, journal.snapshot_id);
+        assert_eq!(read.public_key_digest, journal.public_key_digest);
+        assert_eq!(read.secrets_digest, None);
+        assert_eq!(
+            read.secrets
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>(),
+            ["A", "B", "C"]
+        );
+    }
+}
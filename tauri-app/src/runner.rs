@@ -0,0 +1,325 @@
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::cli;
+use crate::error::AppError;
+use crate::names;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State, Window};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+const PROCESS_OUTPUT_EVENT: &str = "process:output";
+const PROCESS_EXIT_EVENT: &str = "process:exit";
+/// How often the exit watcher checks on a managed process.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Inherited by launched programs; everything else in the app's environment is
+/// dropped so stray credentials don't leak into them.
+const PASSTHROUGH_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "LANG",
+    "LC_ALL",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMFILES",
+];
+
+/// Programs started by `run_with_secrets` that are still running, by id.
+#[derive(Default)]
+pub struct ManagedProcesses(Mutex<HashMap<String, Child>>);
+
+impl ManagedProcesses {
+    /// Asks every running program to stop. Used on app exit.
+    pub fn kill_all(&self) {
+        for child in self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values_mut()
+        {
+            let _ = child.start_kill();
+        }
+    }
+
+    fn insert(&self, id: String, child: Child) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, child);
+    }
+
+    /// Asks the program with `id` to stop.
+    fn kill(&self, id: &str) -> Result<(), AppError> {
+        let mut processes = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let child = processes
+            .get_mut(id)
+            .ok_or_else(|| AppError::InvalidInput(format!("No running process with id {}", id)))?;
+        child
+            .start_kill()
+            .map_err(|e| AppError::Cli(format!("Failed to stop the process: {}", e)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProcessOutput {
+    id: String,
+    stream: &'static str,
+    line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProcessExit {
+    id: String,
+    /// `null` when the program was killed by a signal or couldn't be waited for.
+    exit_code: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunningProcess {
+    pub id: String,
+}
+
+pub fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `cli::sanitize_line`, plus masking of the injected values themselves in case
+/// the program prints them.
+fn mask_line(line: &str, values: &[Zeroizing<String>]) -> String {
+    let mut line = cli::sanitize_line(line);
+    for value in values.iter().filter(|value| !value.is_empty()) {
+        line = line.replace(value.as_str(), cli::REDACTED);
+    }
+    line
+}
+
+fn forward_output(
+    app: AppHandle,
+    id: String,
+    stream: &'static str,
+    output: impl AsyncRead + Unpin + Send + 'static,
+    values: Arc<Vec<Zeroizing<String>>>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(output).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let _ = app.emit_all(
+                PROCESS_OUTPUT_EVENT,
+                ProcessOutput {
+                    id: id.clone(),
+                    stream,
+                    line: mask_line(&line, &values),
+                },
+            );
+        }
+    });
+}
+
+/// `program` with only `PASSTHROUGH_ENV` inherited. A bare program name is looked
+/// up on that `PATH`.
+fn base_command(program: &str, args: &[String], cwd: &str) -> Command {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(cwd)
+        .env_clear()
+        .envs(
+            PASSTHROUGH_ENV
+                .iter()
+                .filter_map(|name| Some((name, env::var_os(name)?))),
+        )
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    command
+}
+
+/// Reads each secret in `secret_env` and sets it on `command`. Returns the
+/// values, for masking the program's output.
+async fn inject(
+    command: &mut Command,
+    secret_env: &HashMap<String, String>,
+) -> Result<Vec<Zeroizing<String>>, AppError> {
+    let mut values = Vec::with_capacity(secret_env.len());
+    for (variable, secret) in secret_env {
+        let stdout = cli::get_secret(secret).await?;
+        let value = Zeroizing::new(String::from_utf8_lossy(&stdout).into_owned());
+        command.env(variable, value.as_str());
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// Polls rather than awaiting the child so `kill_managed_process` and shutdown
+/// can reach it through `ManagedProcesses` meanwhile.
+async fn watch_exit(app: AppHandle, id: String) {
+    loop {
+        tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        let processes = app.state::<ManagedProcesses>();
+        let mut processes = processes.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let exit_code = match processes.get_mut(&id).map(Child::try_wait) {
+            Some(Ok(None)) => continue,
+            Some(Ok(Some(status))) => status.code(),
+            Some(Err(_)) | None => None,
+        };
+        processes.remove(&id);
+        drop(processes);
+        let _ = app.emit_all(PROCESS_EXIT_EVENT, ProcessExit { id, exit_code });
+        return;
+    }
+}
+
+/// Starts `program` in `cwd` with secrets injected as environment variables;
+/// `secret_env` maps variable name to secret name. Nothing is written to disk.
+/// With passkey protection on, the user confirms once for the whole batch.
+///
+/// Returns at once with the process id. Output arrives line by line as
+/// "process:output" events, masked like CLI output and with the injected values
+/// replaced; "process:exit" reports the exit code. Values never leave the child's
+/// environment otherwise. `kill_managed_process` stops the program itself; child
+/// processes it started may outlive it.
+#[command]
+pub async fn run_with_secrets(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    program: String,
+    args: Vec<String>,
+    cwd: String,
+    secret_env: HashMap<String, String>,
+) -> Result<RunningProcess, AppError> {
+    if !Path::new(&cwd).is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "Working directory doesn't exist: {}",
+            cwd
+        )));
+    }
+    for (variable, secret) in &secret_env {
+        if !is_env_name(variable) {
+            return Err(AppError::InvalidInput(format!(
+                "'{}' isn't a valid environment variable name",
+                variable
+            )));
+        }
+        names::check_secret_name(secret)?;
+    }
+
+    if !secret_env.is_empty() && crate::cached_status(&app, false).await?.passkey_enabled {
+        let reason = format!("Run {} with {} secret(s)", program, secret_env.len());
+        auth::confirm_presence(provider.inner().clone(), window, reason).await?;
+    }
+
+    let mut command = base_command(&program, &args, &cwd);
+    let values = inject(&mut command, &secret_env).await?;
+    for secret in secret_env.values() {
+        audit::record(&app, secret, AccessAction::Read, None)?;
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| AppError::InvalidInput(format!("Failed to start {}: {}", program, e)))?;
+    let id = Uuid::new_v4().to_string();
+    let values = Arc::new(values);
+    if let Some(stdout) = child.stdout.take() {
+        forward_output(app.clone(), id.clone(), "stdout", stdout, values.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_output(app.clone(), id.clone(), "stderr", stderr, values);
+    }
+
+    app.state::<ManagedProcesses>().insert(id.clone(), child);
+    tauri::async_runtime::spawn(watch_exit(app, id.clone()));
+    Ok(RunningProcess { id })
+}
+
+/// Stops a program started by `run_with_secrets`. Its "process:exit" event
+/// follows once it has exited.
+#[command]
+pub fn kill_managed_process(
+    processes: State<'_, ManagedProcesses>,
+    id: String,
+) -> Result<(), AppError> {
+    processes.kill(&id)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn secrets_reach_the_child_and_kill_stops_it() {
+        let _fake = crate::test_support::fake_cli(r#"[ "$1" = get ] && printf 'value of %s' "$2""#);
+        let cwd = tempfile::tempdir().unwrap();
+        // Cargo sets CARGO_MANIFEST_DIR for the test process; it isn't allowlisted.
+        assert!(env::var_os("CARGO_MANIFEST_DIR").is_some());
+        let script = r#"printf '%s|%s\n' "$API_KEY" "${CARGO_MANIFEST_DIR-unset}"; exec sleep 30"#;
+        let mut command = base_command(
+            "sh",
+            &["-c".to_string(), script.to_string()],
+            &cwd.path().to_string_lossy(),
+        );
+        let secret_env = HashMap::from([("API_KEY".to_string(), "STRIPE_KEY".to_string())]);
+        let values = inject(&mut command, &secret_env).await.unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].as_str(), "value of STRIPE_KEY");
+
+        let mut child = command.spawn().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "value of STRIPE_KEY|unset\n");
+        assert_eq!(
+            mask_line(line.trim_end(), &values),
+            format!("{}|unset", cli::REDACTED)
+        );
+
+        let processes = ManagedProcesses::default();
+        processes.insert("run".to_string(), child);
+        processes.kill("run").unwrap();
+        let mut child = processes.0.lock().unwrap().remove("run").unwrap();
+        let status = tokio::time::timeout(Duration::from_secs(10), child.wait())
+            .await
+            .expect("the program outlived the kill")
+            .unwrap();
+        assert!(!status.success());
+        let mut rest = String::new();
+        stdout.read_to_string(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+        assert!(processes.kill("run").is_err());
+    }
+
+    #[tokio::test]
+    async fn bare_program_names_are_found_on_path() {
+        let cwd = tempfile::tempdir().unwrap();
+        let output = base_command("true", &[], &cwd.path().to_string_lossy())
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success());
+    }
+}
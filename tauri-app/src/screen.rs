@@ -0,0 +1,56 @@
+use crate::error::AppError;
+use serde::Serialize;
+use tauri::command;
+
+#[derive(Debug, Default, Serialize)]
+pub struct ScreenRecording {
+    pub recording: bool,
+    /// The recording or sharing app, when it could be identified.
+    pub app: Option<String>,
+}
+
+/// Process names of apps that capture or share the screen while they run
+/// (`screencaptureui` is the system Screenshot/recording tool).
+#[cfg(target_os = "macos")]
+const RECORDER_PROCESSES: &[(&str, &str)] = &[
+    ("screencaptureui", "Screenshot"),
+    ("QuickTime Player", "QuickTime Player"),
+    ("obs", "OBS"),
+    ("Loom", "Loom"),
+    ("CptHost", "Zoom screen sharing"),
+    ("ScreenFlow", "ScreenFlow"),
+    ("CleanShot X", "CleanShot X"),
+    ("Kap", "Kap"),
+];
+
+/// macOS has no public API reporting whether the screen is being captured, so
+/// this looks for running capture and sharing apps. A recorder that isn't in
+/// the list goes unnoticed; treat `false` as "nothing known", not a guarantee.
+#[cfg(target_os = "macos")]
+#[command]
+pub async fn is_screen_being_recorded() -> Result<ScreenRecording, AppError> {
+    let output = tokio::process::Command::new("/bin/ps")
+        .args(["-A", "-c", "-o", "comm="])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to list processes: {}", e))?;
+    let processes = String::from_utf8_lossy(&output.stdout);
+
+    let app = processes.lines().find_map(|process| {
+        RECORDER_PROCESSES
+            .iter()
+            .find(|(name, _)| process.trim() == *name)
+            .map(|(_, label)| label.to_string())
+    });
+    Ok(ScreenRecording {
+        recording: app.is_some(),
+        app,
+    })
+}
+
+/// Not detectable elsewhere yet; always reports no recording.
+#[cfg(not(target_os = "macos"))]
+#[command]
+pub async fn is_screen_being_recorded() -> Result<ScreenRecording, AppError> {
+    Ok(ScreenRecording::default())
+}
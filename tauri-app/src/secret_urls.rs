@@ -0,0 +1,59 @@
+use crate::error::AppError;
+use crate::metadata;
+use crate::state::{self, VaultState};
+use tauri::{command, AppHandle, Manager, Url};
+
+/// An http(s) URL with a host, normalized.
+fn check_url(url: &str) -> Result<String, AppError> {
+    let parsed = Url::parse(url.trim())
+        .map_err(|e| AppError::InvalidInput(format!("Invalid URL {}: {}", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(AppError::InvalidInput(format!(
+            "{} is not an http or https URL",
+            url
+        )));
+    }
+    Ok(parsed.to_string())
+}
+
+/// Associates a URL (say, the provider's dashboard) with a secret, or clears it
+/// when `url` is empty. Only http and https are accepted. Dropped when the
+/// secret is deleted and moved when it's renamed.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn set_secret_url(app: AppHandle, name: String, url: String) -> Result<(), AppError> {
+    crate::names::check_secret_name(&name)?;
+    state::require(&app, &[VaultState::Ready]).await?;
+    if url.trim().is_empty() {
+        return metadata::update(&app, |metadata| {
+            metadata.urls.remove(&name);
+            Ok(())
+        });
+    }
+    let url = check_url(&url)?;
+    if !crate::cached_secrets(&app, false)
+        .await?
+        .iter()
+        .any(|secret| secret.name == name)
+    {
+        return Err(AppError::InvalidInput(format!("No secret named {}", name)));
+    }
+    metadata::update(&app, |metadata| {
+        metadata.urls.insert(name, url);
+        Ok(())
+    })
+}
+
+/// Opens the secret's URL in the default browser. The URL is checked again, in
+/// case the metadata file was edited by hand.
+#[command]
+pub fn open_secret_url(app: AppHandle, name: String) -> Result<(), AppError> {
+    let url = metadata::load(&app)?
+        .urls
+        .remove(&name)
+        .ok_or_else(|| AppError::InvalidInput(format!("{} has no URL", name)))?;
+    let url = check_url(&url)?;
+    tauri::api::shell::open(&app.shell_scope(), url, None)
+        .map_err(|e| AppError::Cli(format!("Failed to open the URL: {}", e)))
+}
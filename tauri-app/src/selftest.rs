@@ -0,0 +1,323 @@
+use crate::atomic::atomic_write;
+use crate::error::AppError;
+use crate::{cli, paths};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use tauri::{command, AppHandle, Manager};
+
+/// Emitted with the `ParsingSelfTest` when a CLI version change broke parsing.
+const OUTPUT_CHANGED_EVENT: &str = "vibesafe://cli-output-changed";
+/// In the app data directory: the CLI version the last self-test ran against.
+const LAST_VERSION_FILE: &str = "last_cli_version";
+
+/// One value the JSON and text output disagree on.
+#[derive(Debug, Serialize)]
+pub struct ParsingMismatch {
+    pub field: &'static str,
+    pub json: Value,
+    pub text: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParsingSelfTest {
+    pub cli_version: Option<String>,
+    /// Every output parsed and the two modes agree.
+    pub ok: bool,
+    pub mismatches: Vec<ParsingMismatch>,
+    /// Outputs that couldn't be read at all, each with the reason.
+    pub errors: Vec<String>,
+}
+
+/// What the human-readable `vibesafe status` reports, read off its marker lines.
+#[derive(Debug, Default)]
+struct TextStatus {
+    key_exists: Option<bool>,
+    passkey_enabled: Option<bool>,
+    claude_integration: Option<bool>,
+    secrets_count: Option<u32>,
+}
+
+fn parse_text_status(output: &str) -> TextStatus {
+    let mut status = TextStatus::default();
+    for line in output.lines().map(str::trim) {
+        if line.ends_with("Key pair initialized") {
+            status.key_exists = Some(true);
+        } else if line.ends_with("No key pair found") {
+            status.key_exists = Some(false);
+        } else if let Some(state) = line.split("Passkey protection:").nth(1) {
+            status.passkey_enabled = Some(state.trim() == "ENABLED");
+        } else if let Some(state) = line.split("Claude Code integration:").nth(1) {
+            status.claude_integration = Some(state.trim() == "CONFIGURED");
+        } else if let Some(count) = line.strip_prefix("Secrets stored:") {
+            status.secrets_count = count.trim().parse().ok();
+        }
+    }
+    status
+}
+
+/// Names in the human-readable `vibesafe list`, one "  • NAME" line each.
+/// `None` when the output has neither names nor the empty-store line.
+fn parse_text_list(output: &str) -> Option<BTreeSet<String>> {
+    if output.contains("No secrets stored.") {
+        return Some(BTreeSet::new());
+    }
+    if !output.contains("Stored secrets:") {
+        return None;
+    }
+    Some(
+        output
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('•'))
+            .map(|name| name.trim().to_string())
+            .collect(),
+    )
+}
+
+fn compare<T: Serialize + PartialEq>(
+    mismatches: &mut Vec<ParsingMismatch>,
+    field: &'static str,
+    json_value: T,
+    text_value: Option<T>,
+) {
+    if text_value.as_ref() != Some(&json_value) {
+        mismatches.push(ParsingMismatch {
+            field,
+            json: json!(json_value),
+            text: json!(text_value),
+        });
+    }
+}
+
+async fn run_text(args: &[&str]) -> Result<String, AppError> {
+    let stdout = cli::run_vibesafe_shared(args).await?;
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+async fn run_selftest() -> ParsingSelfTest {
+    let mut mismatches = Vec::new();
+    let mut errors = Vec::new();
+    let cli_version = cli::cli_version().await.ok().map(|v| v.to_string());
+
+    let json_status = match cli::run_vibesafe_shared(&["status", "--json"]).await {
+        Ok(stdout) => cli::parse_json::<crate::VibeSafeStatus>(&stdout, "status").await,
+        Err(error) => Err(error),
+    };
+    let json_list = match cli::run_vibesafe_shared(&["list", "--json"]).await {
+        Ok(stdout) => cli::parse_json::<Vec<crate::SecretInfo>>(&stdout, "list").await,
+        Err(error) => Err(error),
+    };
+    let text_status = run_text(&["status"])
+        .await
+        .map(|out| parse_text_status(&out));
+    let text_list = run_text(&["list"]).await.and_then(|out| {
+        parse_text_list(&out).ok_or_else(|| {
+            AppError::UnparseableCliOutput("The text list has no recognizable lines".to_string())
+        })
+    });
+
+    let json_names: Option<BTreeSet<String>> = json_list
+        .as_ref()
+        .ok()
+        .map(|list| list.iter().map(|secret| secret.name.clone()).collect());
+    match (&json_status, &text_status) {
+        (Ok(json_status), Ok(text_status)) => {
+            compare(
+                &mut mismatches,
+                "key_exists",
+                json_status.key_exists,
+                text_status.key_exists,
+            );
+            compare(
+                &mut mismatches,
+                "passkey_enabled",
+                json_status.passkey_enabled,
+                text_status.passkey_enabled,
+            );
+            compare(
+                &mut mismatches,
+                "claude_integration",
+                json_status.claude_integration,
+                text_status.claude_integration,
+            );
+            compare(
+                &mut mismatches,
+                "secrets_count",
+                json_status.secrets_count,
+                text_status.secrets_count,
+            );
+            if let Some(names) = &json_names {
+                // Both from the JSON side: status and list must agree with each other.
+                compare(
+                    &mut mismatches,
+                    "list_length",
+                    json_status.secrets_count,
+                    Some(names.len() as u32),
+                );
+            }
+        }
+        // An uninitialized store fails the same way in both modes; nothing to compare.
+        (Err(AppError::NotInitialized), _) => {}
+        (json_result, text_result) => {
+            for (mode, error) in [
+                ("status --json", json_result.as_ref().err()),
+                ("status", text_result.as_ref().err()),
+            ] {
+                if let Some(error) = error {
+                    errors.push(format!("{}: {}", mode, error));
+                }
+            }
+        }
+    }
+    match (json_names, &json_list, &text_list) {
+        (Some(json_names), _, Ok(text_names)) => {
+            compare(
+                &mut mismatches,
+                "names",
+                json_names,
+                Some(text_names.clone()),
+            );
+        }
+        (_, Err(AppError::NotInitialized), _) => {}
+        (_, json_result, text_result) => {
+            for (mode, error) in [
+                ("list --json", json_result.as_ref().err()),
+                ("list", text_result.as_ref().err()),
+            ] {
+                if let Some(error) = error {
+                    errors.push(format!("{}: {}", mode, error));
+                }
+            }
+        }
+    }
+
+    ParsingSelfTest {
+        cli_version,
+        ok: mismatches.is_empty() && errors.is_empty(),
+        mismatches,
+        errors,
+    }
+}
+
+/// Runs status and list in JSON and in text mode and checks the parsers still
+/// agree: the same flags, count and names either way, and a list as long as the
+/// count. Nothing is decrypted and the caches are bypassed.
+#[command]
+pub async fn selftest_parsing() -> ParsingSelfTest {
+    run_selftest().await
+}
+
+/// Runs the self-test when the CLI reports a different version than at the last
+/// check, and emits "vibesafe://cli-output-changed" if it fails, so the UI can
+/// warn before screens break. The first run only records the version.
+pub async fn check_after_cli_change(app: &AppHandle) {
+    let (version, dir) = match (cli::cli_version().await, paths::app_data_dir(app)) {
+        (Ok(version), Ok(dir)) => (version.to_string(), dir),
+        _ => return,
+    };
+    let path = dir.join(LAST_VERSION_FILE);
+    let previous = fs::read_to_string(&path).ok();
+    if previous.as_deref().map(str::trim) == Some(version.as_str()) {
+        return;
+    }
+    if previous.is_some() {
+        let report = run_selftest().await;
+        if !report.ok {
+            // The version isn't recorded, so the warning repeats until it passes.
+            let _ = app.emit_all(OUTPUT_CHANGED_EVENT, &report);
+            return;
+        }
+    }
+    if fs::create_dir_all(&dir).is_ok() {
+        let _ = atomic_write(&path, version.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `vibesafe status` as the CLI prints it.
+    const TEXT_STATUS: &str =
+        "🔐 VibeSafe Status\n\n✓ Key pair initialized\n   Location: /home/u/.vibesafe\n\n\
+                               ✓ Passkey protection: ENABLED\n\n\
+                               ✗ Claude Code integration: NOT CONFIGURED\n\nSecrets stored: 2\n";
+    const TEXT_LIST: &str = "Stored secrets:\n  • API_KEY\n  • DB_URL\n";
+    const JSON_STATUS: &str = r#"{"initialized":true,"key_exists":true,"passkey_enabled":true,"secrets_count":2,"claude_integration":false}"#;
+    const JSON_LIST: &str =
+        r#"[{"name":"API_KEY","created_at":null},{"name":"DB_URL","created_at":null}]"#;
+
+    /// A CLI answering status and list in both modes with the given outputs.
+    fn cli_printing(text_status: &str, text_list: &str, json_list: &str) -> String {
+        format!(
+            "case \"$*\" in\n\
+             --version) echo 'vibesafe, version 1.2.0' ;;\n\
+             'status --json') printf '%s' '{}' ;;\n\
+             status) printf '%s' '{}' ;;\n\
+             'list --json') printf '%s' '{}' ;;\n\
+             list) printf '%s' '{}' ;;\n\
+             *) exit 2 ;;\n\
+             esac",
+            JSON_STATUS, text_status, json_list, text_list
+        )
+    }
+
+    #[test]
+    fn text_status_is_read_off_its_marker_lines() {
+        let status = parse_text_status(TEXT_STATUS);
+        assert_eq!(status.key_exists, Some(true));
+        assert_eq!(status.passkey_enabled, Some(true));
+        assert_eq!(status.claude_integration, Some(false));
+        assert_eq!(status.secrets_count, Some(2));
+
+        let status = parse_text_status("✗ No key pair found\n");
+        assert_eq!(status.key_exists, Some(false));
+        assert_eq!(status.secrets_count, None);
+    }
+
+    #[test]
+    fn text_lists_need_a_recognizable_header() {
+        assert_eq!(
+            parse_text_list(TEXT_LIST)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["API_KEY", "DB_URL"]
+        );
+        assert_eq!(
+            parse_text_list("No secrets stored.\n"),
+            Some(BTreeSet::new())
+        );
+        assert_eq!(parse_text_list("Secrets:\n - API_KEY\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn matching_outputs_pass() {
+        let _fake = crate::test_support::fake_cli(&cli_printing(TEXT_STATUS, TEXT_LIST, JSON_LIST));
+        let report = run_selftest().await;
+        assert!(report.ok, "{:?}", report);
+        assert_eq!(report.cli_version.as_deref(), Some("1.2.0"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn changed_outputs_are_reported() {
+        let _fake = crate::test_support::fake_cli(&cli_printing(
+            &TEXT_STATUS.replace("Passkey protection: ENABLED", "Passkey: on"),
+            "Secrets:\n - API_KEY\n",
+            r#"[{"name":"API_KEY","created_at":null}]"#,
+        ));
+        let report = run_selftest().await;
+        assert!(!report.ok);
+        let fields: Vec<&str> = report.mismatches.iter().map(|m| m.field).collect();
+        assert_eq!(fields, ["passkey_enabled", "list_length"]);
+        assert_eq!(report.errors.len(), 1);
+        assert!(
+            report.errors[0].starts_with("list: "),
+            "{:?}",
+            report.errors
+        );
+    }
+}
@@ -0,0 +1,675 @@
+use crate::error::{AppError, KeyConflict};
+use crate::{atomic, cli, paths};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Manager, State};
+use uuid::Uuid;
+
+const SETTINGS_FILE: &str = "settings.json";
+const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+const SETTINGS_RESET_EVENT: &str = "settings:reset";
+const THEME_CHANGED_EVENT: &str = "theme:changed";
+/// How many served revisions `SettingsHistory` keeps as merge bases.
+const HISTORY_LEN: usize = 16;
+/// How long a token from `request_reset_token` stays valid.
+const RESET_TOKEN_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Keep the main window hidden on launch; the app is reachable from the tray.
+    pub start_minimized: bool,
+    /// Show and focus the main window once the app has started.
+    pub show_window_on_launch: bool,
+    /// Allow `search_secret_values`, which decrypts every secret to search it.
+    pub value_search_enabled: bool,
+    /// Extra fragments `find_placeholder_secrets` treats as placeholders.
+    pub placeholder_patterns: Vec<String>,
+    /// Secrets created more than this many days ago are listed as old by
+    /// `run_vault_audit`.
+    pub audit_max_age_days: u32,
+    /// Allow `check_breaches`, which sends hash prefixes of values to Have I Been
+    /// Pwned. Off by default.
+    pub breach_check_enabled: bool,
+    /// Run the CLI bundled with the app, using one on PATH only when it's missing.
+    /// When off, a CLI on PATH is preferred.
+    pub prefer_bundled_cli: bool,
+    /// Auto-clear names copied with `copy_secret_name` like values. Off by default,
+    /// since names aren't sensitive.
+    pub clear_copied_names: bool,
+    /// Secret holding an SSH key's passphrase for the askpass helper; `{key}` is
+    /// replaced with the key's file name.
+    pub ssh_askpass_name_template: String,
+    /// Secret holding the GitHub token `push_to_github_actions` authenticates with.
+    pub github_token_secret: String,
+    /// How an auto-clear removes a copied value from the clipboard.
+    pub clipboard_clear_strategy: ClipboardClearStrategy,
+    /// Run the scripts in hooks.json on secret lifecycle events. Off by default,
+    /// since hooks run arbitrary local programs.
+    pub hooks_enabled: bool,
+    /// Start the local REST API at launch. Off by default.
+    pub api_server_enabled: bool,
+    /// Must be a loopback address; the server refuses to start on anything else.
+    pub api_server_address: String,
+    pub api_server_port: u16,
+    pub theme: Theme,
+    /// Bumped on every save. `update_app_settings` callers send back the revision
+    /// they loaded so concurrent writers (another instance, a hand edit) are caught.
+    pub revision: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the OS light/dark setting.
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardClearStrategy {
+    /// Replace the value with an empty string.
+    Empty,
+    /// Write random text first, so a clipboard manager that keeps history only
+    /// keeps the junk as the latest entry, then replace it with an empty string.
+    OverwriteRandom,
+    /// Empty the clipboard through the OS, which some managers treat differently
+    /// from new content. Falls back to `Empty` where that isn't available.
+    NativeClear,
+}
+
+impl ClipboardClearStrategy {
+    pub const ALL: [ClipboardClearStrategy; 3] = [
+        ClipboardClearStrategy::Empty,
+        ClipboardClearStrategy::OverwriteRandom,
+        ClipboardClearStrategy::NativeClear,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClipboardClearStrategy::Empty => "empty",
+            ClipboardClearStrategy::OverwriteRandom => "overwrite-random",
+            ClipboardClearStrategy::NativeClear => "native-clear",
+        }
+    }
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            start_minimized: false,
+            show_window_on_launch: true,
+            value_search_enabled: false,
+            placeholder_patterns: Vec::new(),
+            audit_max_age_days: 365,
+            breach_check_enabled: false,
+            prefer_bundled_cli: true,
+            clear_copied_names: false,
+            ssh_askpass_name_template: "ssh-{key}".to_string(),
+            github_token_secret: "GITHUB_PAT".to_string(),
+            clipboard_clear_strategy: ClipboardClearStrategy::Empty,
+            hooks_enabled: false,
+            api_server_enabled: false,
+            api_server_address: "127.0.0.1".to_string(),
+            api_server_port: 7391,
+            theme: Theme::System,
+            revision: 0,
+        }
+    }
+}
+
+pub fn settings_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(settings_path_in(&paths::app_config_dir(app)?))
+}
+
+pub fn settings_path_in(config_dir: &Path) -> PathBuf {
+    config_dir.join(SETTINGS_FILE)
+}
+
+/// Settings as served at recent revisions, the base for three-way merges in
+/// `update_app_settings`.
+#[derive(Default)]
+pub struct SettingsHistory(Mutex<VecDeque<AppSettings>>);
+
+impl SettingsHistory {
+    fn remember(&self, settings: &AppSettings) {
+        let mut history = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if history
+            .iter()
+            .any(|known| known.revision == settings.revision)
+        {
+            return;
+        }
+        if history.len() == HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(settings.clone());
+    }
+
+    fn at(&self, revision: u64) -> Option<AppSettings> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|known| known.revision == revision)
+            .cloned()
+    }
+}
+
+/// Loads the persisted settings, falling back to defaults when the file doesn't exist
+/// yet or there is no config directory to hold it. In the latter case saving still
+/// fails, so changes last only until the app quits.
+pub fn load_settings(app: &AppHandle) -> Result<AppSettings, AppError> {
+    let path = match paths::app_config_dir(app) {
+        Ok(dir) => dir.join(SETTINGS_FILE),
+        Err(_) => return Ok(AppSettings::default()),
+    };
+    let settings = read_settings_file(&path)?;
+    app.state::<SettingsHistory>().remember(&settings);
+    Ok(settings)
+}
+
+/// Reads settings.json at `path`, with defaults when it doesn't exist. For the
+/// helper modes that run without an `AppHandle`.
+pub fn read_settings_file(path: &Path) -> Result<AppSettings, AppError> {
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| AppError::Cli(format!("Failed to read settings: {}", e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| AppError::InvalidInput(format!("Failed to parse settings: {}", e)))
+}
+
+/// Writes `settings` as the revision after both the one on disk and its own, and
+/// returns what was saved.
+pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<AppSettings, AppError> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Cli(format!("Failed to create config directory: {}", e)))?;
+    }
+
+    let current = load_settings(app).map(|s| s.revision).unwrap_or_default();
+    let saved = AppSettings {
+        revision: current.max(settings.revision) + 1,
+        ..settings.clone()
+    };
+    atomic::atomic_write_json(&path, &saved)
+        .map_err(|e| AppError::Cli(format!("Failed to write settings: {}", e)))?;
+    app.state::<SettingsHistory>().remember(&saved);
+    Ok(saved)
+}
+
+fn to_fields(settings: &AppSettings) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(mut fields)) => {
+            fields.remove("revision");
+            fields
+        }
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// Applies the caller's edits (`ours`, made on top of `base`) to what is on disk
+/// now (`theirs`). Fields only one side changed merge cleanly; fields both sides
+/// changed differently are conflicts. Without a base every difference conflicts.
+fn merge(
+    base: Option<&AppSettings>,
+    ours: &AppSettings,
+    theirs: &AppSettings,
+) -> Result<AppSettings, AppError> {
+    let base = base.map(to_fields);
+    let ours = to_fields(ours);
+    let mut merged = to_fields(theirs);
+    let mut conflicts = Vec::new();
+
+    for (key, our_value) in ours {
+        let their_value = merged.get(&key).cloned().unwrap_or(Value::Null);
+        if our_value == their_value {
+            continue;
+        }
+        let base_value = base.as_ref().and_then(|base| base.get(&key)).cloned();
+        match base_value {
+            Some(base_value) if base_value == their_value => {
+                merged.insert(key, our_value);
+            }
+            Some(base_value) if base_value == our_value => {}
+            base_value => conflicts.push(KeyConflict {
+                key,
+                base: base_value.unwrap_or(Value::Null),
+                ours: our_value,
+                theirs: their_value,
+            }),
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(AppError::ConflictDetected {
+            revision: theirs.revision,
+            conflicts,
+        });
+    }
+    let mut merged: AppSettings = serde_json::from_value(Value::Object(merged))
+        .map_err(|e| AppError::InvalidInput(format!("Merged settings are invalid: {}", e)))?;
+    merged.revision = theirs.revision;
+    Ok(merged)
+}
+
+#[command]
+pub fn get_app_settings(app: AppHandle) -> Result<AppSettings, AppError> {
+    load_settings(&app)
+}
+
+fn notify_settings_changed(app: &AppHandle, settings: &AppSettings) -> Result<(), AppError> {
+    app.emit_all(SETTINGS_CHANGED_EVENT, settings)
+        .map_err(|e| AppError::Cli(format!("Failed to emit settings change: {}", e)))
+}
+
+/// Saves settings edited from `settings.revision`. If the file changed since,
+/// edits to different fields are merged; edits to the same field fail with
+/// `CONFLICT_DETECTED` listing each field's base, ours and theirs values.
+/// Returns what was saved, including the new revision.
+#[command]
+pub fn update_app_settings(app: AppHandle, settings: AppSettings) -> Result<AppSettings, AppError> {
+    let current = load_settings(&app)?;
+    let settings = if settings.revision == current.revision {
+        settings
+    } else {
+        let base = app.state::<SettingsHistory>().at(settings.revision);
+        merge(base.as_ref(), &settings, &current)?
+    };
+
+    let saved = save_settings(&app, &settings)?;
+    cli::apply_settings(&saved);
+    notify_settings_changed(&app, &saved)?;
+    Ok(saved)
+}
+
+#[command]
+pub fn list_themes() -> Vec<&'static str> {
+    Theme::ALL.iter().map(|theme| theme.as_str()).collect()
+}
+
+/// Stored with the app settings rather than in the webview, so clearing its local
+/// storage doesn't lose the choice.
+#[command]
+pub fn get_theme(app: AppHandle) -> Result<Theme, AppError> {
+    Ok(load_settings(&app)?.theme)
+}
+
+/// Saves `theme` ("system", "light" or "dark") and emits "theme:changed".
+#[command]
+pub fn set_theme(app: AppHandle, theme: String) -> Result<Theme, AppError> {
+    let theme = Theme::ALL
+        .iter()
+        .copied()
+        .find(|known| known.as_str() == theme)
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "Unknown theme '{}'. Expected one of: {}",
+                theme,
+                list_themes().join(", ")
+            ))
+        })?;
+
+    let mut settings = load_settings(&app)?;
+    settings.theme = theme;
+    let settings = save_settings(&app, &settings)?;
+    notify_settings_changed(&app, &settings)?;
+    app.emit_all(THEME_CHANGED_EVENT, theme)
+        .map_err(|e| AppError::Cli(format!("Failed to emit theme change: {}", e)))?;
+    Ok(theme)
+}
+
+#[command]
+pub fn list_clipboard_clear_strategies() -> Vec<&'static str> {
+    ClipboardClearStrategy::ALL
+        .iter()
+        .map(|strategy| strategy.as_str())
+        .collect()
+}
+
+/// Saves how auto-clears empty the clipboard: "empty", "overwrite-random" or
+/// "native-clear". Which one works depends on the user's clipboard manager.
+#[command]
+pub fn set_clipboard_clear_strategy(
+    app: AppHandle,
+    strategy: String,
+) -> Result<ClipboardClearStrategy, AppError> {
+    let strategy = ClipboardClearStrategy::ALL
+        .iter()
+        .copied()
+        .find(|known| known.as_str() == strategy)
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "Unknown clipboard clear strategy '{}'. Expected one of: {}",
+                strategy,
+                list_clipboard_clear_strategies().join(", ")
+            ))
+        })?;
+
+    let mut settings = load_settings(&app)?;
+    settings.clipboard_clear_strategy = strategy;
+    let settings = save_settings(&app, &settings)?;
+    notify_settings_changed(&app, &settings)?;
+    Ok(strategy)
+}
+
+/// Format version written by `export_settings`. Version 0 is a bare settings.json.
+const SETTINGS_EXPORT_VERSION: u64 = 1;
+/// Keys containing any of these are dropped from exports.
+const SENSITIVE_KEY_FRAGMENTS: &[&str] = &["secret", "passphrase", "password", "token", "auth"];
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| {
+                let key = key.to_lowercase();
+                !SENSITIVE_KEY_FRAGMENTS
+                    .iter()
+                    .any(|fragment| key.contains(fragment))
+            });
+            map.values_mut().for_each(redact);
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// All persisted app settings as one redacted JSON document, for bug reports.
+/// Never contains secret values.
+#[command]
+pub fn export_settings(app: AppHandle) -> Result<Value, AppError> {
+    let mut settings = serde_json::to_value(load_settings(&app)?)
+        .map_err(|e| AppError::Cli(format!("Failed to serialize settings: {}", e)))?;
+    redact(&mut settings);
+    if let Some(fields) = settings.as_object_mut() {
+        fields.remove("revision");
+    }
+
+    Ok(serde_json::json!({
+        "version": SETTINGS_EXPORT_VERSION,
+        "app_settings": settings,
+    }))
+}
+
+/// Brings an export of any known version to the current shape.
+fn migrate_export(mut export: Value) -> Result<Value, AppError> {
+    let version = match export.get("version") {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| {
+            AppError::InvalidInput("Settings export has an invalid version".to_string())
+        })?,
+    };
+
+    if version == 0 {
+        export = serde_json::json!({ "version": 1, "app_settings": export });
+    } else if version > SETTINGS_EXPORT_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "Settings export version {} is newer than this app supports ({})",
+            version, SETTINGS_EXPORT_VERSION
+        )));
+    }
+    Ok(export)
+}
+
+/// Restores settings from `export_settings` output (or an older settings.json).
+/// Settings missing from the export keep their defaults.
+#[command]
+pub fn import_settings(app: AppHandle, json: String) -> Result<AppSettings, AppError> {
+    let export: Value = serde_json::from_str(&json)
+        .map_err(|e| AppError::InvalidInput(format!("Settings export isn't valid JSON: {}", e)))?;
+    if !export.is_object() {
+        return Err(AppError::InvalidInput(
+            "Settings export must be a JSON object".to_string(),
+        ));
+    }
+
+    let app_settings = migrate_export(export)?
+        .get_mut("app_settings")
+        .map(Value::take)
+        .ok_or_else(|| AppError::InvalidInput("Settings export has no app_settings".to_string()))?;
+    let settings: AppSettings = serde_json::from_value(app_settings)
+        .map_err(|e| AppError::InvalidInput(format!("Settings export is invalid: {}", e)))?;
+
+    let settings = save_settings(&app, &settings)?;
+    cli::apply_settings(&settings);
+    notify_settings_changed(&app, &settings)?;
+    Ok(settings)
+}
+
+/// What `reset_settings` puts back to defaults.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum ResetScope {
+    AppSettings,
+    All,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ResetReport {
+    /// Copies of the previous files, kept so a reset can be undone by hand.
+    pub archived: Vec<PathBuf>,
+}
+
+/// Moves `path` aside to `<name>.pre-reset-<timestamp>` before it is rewritten.
+fn archive_for_reset(path: &Path) -> Result<Option<PathBuf>, AppError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let archive = path.with_file_name(format!("{}.pre-reset-{}", file_name, timestamp));
+
+    fs::rename(path, &archive)
+        .map_err(|e| AppError::Cli(format!("Failed to archive {}: {}", file_name, e)))?;
+    Ok(Some(archive))
+}
+
+/// The outstanding single-use token `reset_settings` requires, so a stray call
+/// can't wipe the app's configuration.
+#[derive(Default)]
+pub struct ResetToken(Mutex<Option<(String, Instant)>>);
+
+impl ResetToken {
+    /// Consumes the outstanding token. A wrong guess burns it too.
+    fn redeem(&self, token: &str) -> Result<(), AppError> {
+        let issued = self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+        match issued {
+            Some((expected, issued_at))
+                if expected == token && issued_at.elapsed() < RESET_TOKEN_TTL =>
+            {
+                Ok(())
+            }
+            _ => Err(AppError::InvalidInput(
+                "Invalid or expired reset token. Request a new one.".to_string(),
+            )),
+        }
+    }
+}
+
+#[command]
+pub fn request_reset_token(reset_token: State<'_, ResetToken>) -> String {
+    let token = Uuid::new_v4().to_string();
+    *reset_token.0.lock().unwrap_or_else(PoisonError::into_inner) =
+        Some((token.clone(), Instant::now()));
+    token
+}
+
+/// Puts app configuration back to defaults. Secrets are never touched. Needs a
+/// token from `request_reset_token`; emits "settings:reset" so the UI reloads.
+#[command]
+pub fn reset_settings(
+    app: AppHandle,
+    reset_token: State<'_, ResetToken>,
+    scope: ResetScope,
+    confirm_token: String,
+) -> Result<ResetReport, AppError> {
+    reset_token.redeem(&confirm_token)?;
+    let mut report = ResetReport::default();
+
+    match scope {
+        ResetScope::AppSettings | ResetScope::All => {
+            // Keeps counting from the old file so stale revisions still conflict.
+            let revision = load_settings(&app).map(|s| s.revision).unwrap_or_default();
+            report
+                .archived
+                .extend(archive_for_reset(&settings_path(&app)?)?);
+            let defaults = save_settings(
+                &app,
+                &AppSettings {
+                    revision,
+                    ..AppSettings::default()
+                },
+            )?;
+            cli::apply_settings(&defaults);
+            notify_settings_changed(&app, &defaults)?;
+        }
+    }
+
+    app.emit_all(SETTINGS_RESET_EVENT, &report)
+        .map_err(|e| AppError::Cli(format!("Failed to emit settings reset: {}", e)))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_settings_fall_back_to_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = settings_path_in(dir.path());
+        let settings = read_settings_file(&path).unwrap();
+        assert!(settings.show_window_on_launch);
+        assert!(!settings.value_search_enabled);
+        assert_eq!(settings.api_server_address, "127.0.0.1");
+
+        // Fields added since the file was written get their defaults.
+        fs::write(&path, r#"{ "theme": "dark", "revision": 4 }"#).unwrap();
+        let settings = read_settings_file(&path).unwrap();
+        assert_eq!(settings.theme, Theme::Dark);
+        assert_eq!(settings.revision, 4);
+        assert_eq!(settings.audit_max_age_days, 365);
+    }
+
+    #[test]
+    fn a_corrupt_file_is_reported_not_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = settings_path_in(dir.path());
+        fs::write(&path, "{ \"theme\": ").unwrap();
+        assert!(matches!(
+            read_settings_file(&path),
+            Err(AppError::InvalidInput(_))
+        ));
+        fs::write(&path, r#"{ "theme": "sepia" }"#).unwrap();
+        assert!(read_settings_file(&path).is_err());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"{ "theme": "sepia" }"#
+        );
+    }
+
+    #[test]
+    fn old_exports_are_migrated() {
+        let bare = serde_json::json!({ "theme": "light" });
+        let migrated = migrate_export(bare).unwrap();
+        assert_eq!(migrated["version"], 1);
+        assert_eq!(migrated["app_settings"]["theme"], "light");
+
+        let current = serde_json::json!({ "version": 1, "app_settings": {} });
+        assert_eq!(migrate_export(current.clone()).unwrap(), current);
+        assert!(migrate_export(serde_json::json!({ "version": 2 })).is_err());
+        assert!(migrate_export(serde_json::json!({ "version": "1" })).is_err());
+    }
+
+    #[test]
+    fn exports_drop_sensitive_keys() {
+        let mut value = serde_json::to_value(AppSettings::default()).unwrap();
+        value["nested"] = serde_json::json!([{ "api_token": "x", "kept": true }]);
+        redact(&mut value);
+        assert!(value.get("github_token_secret").is_none());
+        assert_eq!(value["nested"][0], serde_json::json!({ "kept": true }));
+        assert_eq!(value["theme"], "system");
+    }
+
+    #[test]
+    fn edits_to_different_fields_merge() {
+        let base = AppSettings {
+            revision: 1,
+            ..AppSettings::default()
+        };
+        let ours = AppSettings {
+            theme: Theme::Dark,
+            ..base.clone()
+        };
+        let theirs = AppSettings {
+            hooks_enabled: true,
+            revision: 2,
+            ..base.clone()
+        };
+        let merged = merge(Some(&base), &ours, &theirs).unwrap();
+        assert_eq!(merged.theme, Theme::Dark);
+        assert!(merged.hooks_enabled);
+        assert_eq!(merged.revision, 2);
+
+        let theirs = AppSettings {
+            theme: Theme::Light,
+            revision: 2,
+            ..base.clone()
+        };
+        match merge(Some(&base), &ours, &theirs) {
+            Err(AppError::ConflictDetected {
+                revision,
+                conflicts,
+            }) => {
+                assert_eq!(revision, 2);
+                assert_eq!(conflicts.len(), 1);
+                assert_eq!(conflicts[0].key, "theme");
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+        // Without the base, any difference is a conflict.
+        assert!(merge(None, &ours, &base).is_err());
+    }
+
+    #[test]
+    fn reset_tokens_work_once() {
+        let reset = ResetToken::default();
+        assert!(reset.redeem("anything").is_err());
+        *reset.0.lock().unwrap() = Some(("token".to_string(), Instant::now()));
+        assert!(reset.redeem("guess").is_err());
+        // The wrong guess burned it.
+        assert!(reset.redeem("token").is_err());
+        *reset.0.lock().unwrap() = Some(("token".to_string(), Instant::now()));
+        assert!(reset.redeem("token").is_ok());
+        assert!(reset.redeem("token").is_err());
+    }
+}
@@ -0,0 +1,512 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::cache::StoreCache;
+use crate::error::AppError;
+use crate::hooks::{self, HookEvent};
+use crate::state::{self, VaultState};
+use crate::{cli, metadata, names};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use crypto_box::aead::{AeadCore, AeadInPlace, OsRng};
+use crypto_box::{Nonce, PublicKey, SalsaBox, SecretKey, KEY_SIZE};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Manager, State, Window};
+use zeroize::Zeroizing;
+
+/// The vault secret holding this machine's sharing key, an X25519 secret key in
+/// base64. Created on first use and marked read-only.
+const IDENTITY_SECRET: &str = "VIBESAFE_SHARE_IDENTITY";
+const ARMOR_BEGIN: &str = "-----BEGIN VIBESAFE SHARED SECRET-----";
+const ARMOR_END: &str = "-----END VIBESAFE SHARED SECRET-----";
+const FORMAT_VERSION: &str = "1";
+const LINE_WIDTH: usize = 64;
+/// Poly1305 tag appended to the ciphertext.
+const TAG_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+const AUDIT_REASON: &str = "Shared as an encrypted file";
+
+#[derive(Debug, Serialize)]
+pub struct ShareKey {
+    /// Base64; what a teammate passes as `recipient_public_key`.
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SharedSecretFile {
+    pub output_path: PathBuf,
+    pub sender_fingerprint: String,
+    pub recipient_fingerprint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedShare {
+    pub name: String,
+    /// Whose sharing key encrypted the file; compare it with the sender's.
+    pub sender_fingerprint: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The first 16 bytes of the key's SHA-256 in hex, short enough to read out to
+/// the other side.
+fn fingerprint(key: &PublicKey) -> String {
+    Sha256::digest(key.as_bytes())[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn parse_public_key(text: &str) -> Result<PublicKey, AppError> {
+    BASE64
+        .decode(text.trim())
+        .ok()
+        .filter(|bytes| bytes.len() == KEY_SIZE && bytes.iter().any(|&byte| byte != 0))
+        .and_then(|bytes| PublicKey::from_slice(&bytes).ok())
+        .ok_or_else(|| {
+            AppError::InvalidInput(
+                "The recipient key isn't a sharing key: expected 32 bytes in base64, as \
+                 get_share_public_key returns"
+                    .to_string(),
+            )
+        })
+}
+
+/// The sharing key from the vault, `None` if it was never created.
+async fn load_identity(app: &AppHandle) -> Result<Option<SecretKey>, AppError> {
+    let exists = crate::cached_secrets(app, false)
+        .await?
+        .iter()
+        .any(|secret| secret.name == IDENTITY_SECRET);
+    if !exists {
+        return Ok(None);
+    }
+    let stdout = cli::get_secret(IDENTITY_SECRET).await?;
+    let bytes = Zeroizing::new(BASE64.decode(&stdout[..]).unwrap_or_default());
+    SecretKey::from_slice(&bytes).map(Some).map_err(|_| {
+        AppError::UnparseableCliOutput(format!("{} isn't a sharing key", IDENTITY_SECRET))
+    })
+}
+
+/// The sharing key, generated and stored in the vault if there isn't one yet.
+async fn identity(app: &AppHandle) -> Result<SecretKey, AppError> {
+    if let Some(key) = load_identity(app).await? {
+        return Ok(key);
+    }
+    let key = SecretKey::generate(&mut OsRng);
+    let bytes = Zeroizing::new(key.to_bytes());
+    // 44 base64 characters and the newline, sized so the buffer never reallocates.
+    let mut input = Zeroizing::new(String::with_capacity(48));
+    BASE64.encode_string(&bytes[..], &mut input);
+    input.push('\n');
+    cli::add_with_stdin(IDENTITY_SECRET, input.as_bytes()).await?;
+    app.state::<StoreCache>().invalidate();
+    metadata::update(app, |metadata| {
+        metadata.readonly.insert(IDENTITY_SECRET.to_string());
+        Ok(())
+    })?;
+    Ok(key)
+}
+
+/// A shared-secret file: headers in the clear, then the value encrypted with
+/// crypto_box (X25519, XSalsa20-Poly1305) from the sender's sharing key to the
+/// recipient's. The box authenticates the sender, so a file that decrypts came
+/// from the key in `Sender`.
+struct Armored {
+    name: Option<String>,
+    sender: PublicKey,
+    recipient_fingerprint: String,
+    created_at: DateTime<Utc>,
+    nonce: Nonce,
+    ciphertext: Vec<u8>,
+}
+
+impl Armored {
+    fn encode(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", ARMOR_BEGIN);
+        let _ = writeln!(out, "Version: {}", FORMAT_VERSION);
+        if let Some(name) = &self.name {
+            let _ = writeln!(out, "Name: {}", name);
+        }
+        let _ = writeln!(out, "Sender: {}", BASE64.encode(self.sender.as_bytes()));
+        let _ = writeln!(out, "Sender-Fingerprint: {}", fingerprint(&self.sender));
+        let _ = writeln!(out, "Recipient-Fingerprint: {}", self.recipient_fingerprint);
+        let _ = writeln!(out, "Created: {}", self.created_at.to_rfc3339());
+        let _ = writeln!(out, "Nonce: {}", BASE64.encode(self.nonce));
+        out.push('\n');
+        let body = BASE64.encode(&self.ciphertext);
+        for line in body.as_bytes().chunks(LINE_WIDTH) {
+            out.push_str(&String::from_utf8_lossy(line));
+            out.push('\n');
+        }
+        let _ = writeln!(out, "{}", ARMOR_END);
+        out
+    }
+
+    fn decode(text: &str) -> Result<Self, AppError> {
+        let corrupt = |why: &str| AppError::CorruptShare(why.to_string());
+        let mut lines = text.lines().map(str::trim_end);
+        if lines.next() != Some(ARMOR_BEGIN) {
+            return Err(corrupt("it isn't a VibeSafe shared secret"));
+        }
+        let mut headers = HashMap::new();
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = line
+                .split_once(": ")
+                .ok_or_else(|| corrupt("a header line is malformed"))?;
+            headers.insert(key, value);
+        }
+        let mut body = String::new();
+        let mut ended = false;
+        for line in lines {
+            if line == ARMOR_END {
+                ended = true;
+                break;
+            }
+            body.push_str(line.trim());
+        }
+        if !ended {
+            return Err(corrupt("it's cut off"));
+        }
+
+        let header = |key: &str| {
+            headers
+                .get(key)
+                .copied()
+                .ok_or_else(|| AppError::CorruptShare(format!("the {} header is missing", key)))
+        };
+        let version = header("Version")?;
+        if version != FORMAT_VERSION {
+            return Err(AppError::CorruptShare(format!(
+                "format version {} isn't supported",
+                version
+            )));
+        }
+        let sender = BASE64
+            .decode(header("Sender")?)
+            .ok()
+            .and_then(|bytes| PublicKey::from_slice(&bytes).ok())
+            .ok_or_else(|| corrupt("the sender key is invalid"))?;
+        if fingerprint(&sender) != header("Sender-Fingerprint")? {
+            return Err(corrupt(
+                "the sender fingerprint doesn't match the sender key",
+            ));
+        }
+        let created_at = DateTime::parse_from_rfc3339(header("Created")?)
+            .map(|created| created.with_timezone(&Utc))
+            .map_err(|_| corrupt("the timestamp is invalid"))?;
+        let nonce = BASE64
+            .decode(header("Nonce")?)
+            .ok()
+            .filter(|bytes| bytes.len() == NONCE_SIZE)
+            .map(|bytes| *Nonce::from_slice(&bytes))
+            .ok_or_else(|| corrupt("the nonce is invalid"))?;
+        let ciphertext = BASE64
+            .decode(&body)
+            .map_err(|_| corrupt("the encrypted body isn't base64"))?;
+        if ciphertext.len() < TAG_SIZE {
+            return Err(corrupt("the encrypted body is too short"));
+        }
+
+        Ok(Armored {
+            name: headers.get("Name").map(|name| name.to_string()),
+            sender,
+            recipient_fingerprint: header("Recipient-Fingerprint")?.to_string(),
+            created_at,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// The value, decrypted with the recipient's key.
+    fn open(&self, identity: &SecretKey) -> Result<Zeroizing<Vec<u8>>, AppError> {
+        let mut buffer = Zeroizing::new(self.ciphertext.clone());
+        SalsaBox::new(&self.sender, identity)
+            .decrypt_in_place(&self.nonce, b"", &mut *buffer)
+            .map_err(|_| {
+                AppError::CorruptShare(
+                    "it fails authentication, so it was altered after it was written".to_string(),
+                )
+            })?;
+        Ok(buffer)
+    }
+}
+
+/// This vault's sharing key, for teammates to encrypt to. It is created (and
+/// stored in the vault as a read-only VIBESAFE_SHARE_IDENTITY secret) the first
+/// time it's needed.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn get_share_public_key(app: AppHandle) -> Result<ShareKey, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let public_key = identity(&app).await?.public_key();
+    Ok(ShareKey {
+        public_key: BASE64.encode(public_key.as_bytes()),
+        fingerprint: fingerprint(&public_key),
+    })
+}
+
+/// Writes the value of `name`, encrypted to a teammate's sharing key (from their
+/// `get_share_public_key`), to `output_path` as an armored text file for
+/// `import_shared_secret`. The file carries the sender's key and fingerprint,
+/// the time and, unless `include_name` is false, the secret's name. Needs a
+/// fresh confirmation; the access log gets the name.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn share_secret_encrypted(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    name: String,
+    recipient_public_key: String,
+    output_path: String,
+    include_name: Option<bool>,
+) -> Result<SharedSecretFile, AppError> {
+    names::check_secret_name(&name)?;
+    if name == IDENTITY_SECRET {
+        return Err(AppError::InvalidInput(
+            "The sharing key itself can't be shared".to_string(),
+        ));
+    }
+    state::require(&app, &[VaultState::Ready]).await?;
+    let recipient = parse_public_key(&recipient_public_key)?;
+    let recipient_fingerprint = fingerprint(&recipient);
+    let output_path = PathBuf::from(output_path);
+    let reason = format!("Share {} with key {}", name, recipient_fingerprint);
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let identity = identity(&app).await?;
+    let stdout = cli::get_secret(&name).await?;
+    let value = &stdout[..];
+    // Room for the tag, so encrypting in place never reallocates the plaintext.
+    let mut buffer = Zeroizing::new(Vec::with_capacity(value.len() + TAG_SIZE));
+    buffer.extend_from_slice(value);
+    drop(stdout);
+    let nonce = SalsaBox::generate_nonce(&mut OsRng);
+    SalsaBox::new(&recipient, &identity)
+        .encrypt_in_place(&nonce, b"", &mut *buffer)
+        .map_err(|_| AppError::Cli("Failed to encrypt the value".to_string()))?;
+
+    let armored = Armored {
+        name: include_name.unwrap_or(true).then(|| name.clone()),
+        sender: identity.public_key(),
+        recipient_fingerprint: recipient_fingerprint.clone(),
+        created_at: Utc::now(),
+        nonce,
+        ciphertext: std::mem::take(&mut *buffer),
+    };
+    atomic_write(&output_path, armored.encode().as_bytes())
+        .map_err(|e| AppError::Cli(format!("Failed to write {}: {}", output_path.display(), e)))?;
+    audit::record(&app, &name, AccessAction::Read, Some(AUDIT_REASON))?;
+
+    Ok(SharedSecretFile {
+        output_path,
+        sender_fingerprint: fingerprint(&armored.sender),
+        recipient_fingerprint,
+    })
+}
+
+/// Decrypts a file from `share_secret_encrypted` with this vault's sharing key
+/// and adds the value as `name`, or as the name in the file when `name` is
+/// omitted. Fails with `WRONG_RECIPIENT` when the file was encrypted to another
+/// key and `CORRUPT_SHARE` when it is malformed or was altered. Check the
+/// returned sender fingerprint against the teammate's.
+///
+/// Valid in: Ready.
+#[command]
+pub async fn import_shared_secret(
+    app: AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<ImportedShare, AppError> {
+    state::require(&app, &[VaultState::Ready]).await?;
+    let path = PathBuf::from(path);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            return Err(AppError::CorruptShare("it isn't text".to_string()))
+        }
+        Err(e) => {
+            return Err(AppError::Cli(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    let armored = Armored::decode(&text)?;
+    let name = name.or_else(|| armored.name.clone()).ok_or_else(|| {
+        AppError::InvalidInput("The file doesn't name the secret; choose a name".to_string())
+    })?;
+    names::check_secret_name(&name)?;
+
+    let wrong_recipient = || AppError::WrongRecipient {
+        recipient_fingerprint: armored.recipient_fingerprint.clone(),
+    };
+    let identity = load_identity(&app).await?.ok_or_else(wrong_recipient)?;
+    if fingerprint(&identity.public_key()) != armored.recipient_fingerprint {
+        return Err(wrong_recipient());
+    }
+    let mut buffer = armored.open(&identity)?;
+    if buffer.contains(&b'\n') {
+        return Err(AppError::InvalidInput(
+            "Multi-line values can't be passed to the CLI".to_string(),
+        ));
+    }
+    // Decrypting dropped the tag, which leaves room for the newline.
+    buffer.push(b'\n');
+    cli::add_with_stdin(&name, &buffer).await?;
+    app.state::<StoreCache>().invalidate();
+    hooks::fire(&app, HookEvent::Added, &name, "import_shared_secret");
+
+    Ok(ImportedShare {
+        name,
+        sender_fingerprint: fingerprint(&armored.sender),
+        created_at: armored.created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> (SecretKey, SecretKey) {
+        (SecretKey::from([1u8; 32]), SecretKey::from([2u8; 32]))
+    }
+
+    fn sealed(value: &[u8]) -> String {
+        let (sender, recipient) = keys();
+        let mut buffer = value.to_vec();
+        let nonce = SalsaBox::generate_nonce(&mut OsRng);
+        SalsaBox::new(&recipient.public_key(), &sender)
+            .encrypt_in_place(&nonce, b"", &mut buffer)
+            .unwrap();
+        Armored {
+            name: Some("API_KEY".to_string()),
+            sender: sender.public_key(),
+            recipient_fingerprint: fingerprint(&recipient.public_key()),
+            created_at: DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            nonce,
+            ciphertext: buffer,
+        }
+        .encode()
+    }
+
+    fn open(text: &str) -> Result<Zeroizing<Vec<u8>>, AppError> {
+        Armored::decode(text)?.open(&keys().1)
+    }
+
+    #[test]
+    fn armor_round_trips() {
+        let value = vec![b'x'; 100];
+        let text = sealed(&value);
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH
+            || line.starts_with("-----")
+            || line.contains(": ")));
+
+        let armored = Armored::decode(&text).unwrap();
+        let (sender, recipient) = keys();
+        assert_eq!(armored.name.as_deref(), Some("API_KEY"));
+        assert_eq!(
+            fingerprint(&armored.sender),
+            fingerprint(&sender.public_key())
+        );
+        assert_eq!(
+            armored.recipient_fingerprint,
+            fingerprint(&recipient.public_key())
+        );
+        assert_eq!(armored.created_at.to_rfc3339(), "2026-01-02T03:04:05+00:00");
+        assert_eq!(*open(&text).unwrap(), value);
+        assert_eq!(*open(&text.replace('\n', "\r\n")).unwrap(), value);
+    }
+
+    #[test]
+    fn a_changed_body_fails_authentication() {
+        let text = sealed(b"value");
+        let body_line = text.lines().rev().nth(1).unwrap();
+        let mut bytes = BASE64.decode(body_line).unwrap();
+        bytes[0] ^= 1;
+        let tampered = text.replace(body_line, &BASE64.encode(bytes));
+        assert!(Armored::decode(&tampered).is_ok());
+        assert!(matches!(open(&tampered), Err(AppError::CorruptShare(_))));
+    }
+
+    #[test]
+    fn a_swapped_sender_is_caught() {
+        let text = sealed(b"value");
+        let (_, recipient) = keys();
+        let other = BASE64.encode(SecretKey::from([3u8; 32]).public_key().as_bytes());
+        let sender_line = text
+            .lines()
+            .find(|line| line.starts_with("Sender: "))
+            .unwrap();
+
+        // Without a matching fingerprint the header check fails...
+        let swapped = text.replace(sender_line, &format!("Sender: {}", other));
+        assert!(matches!(
+            Armored::decode(&swapped),
+            Err(AppError::CorruptShare(_))
+        ));
+        // ...and with one, the box no longer opens.
+        let mut armored = Armored::decode(&text).unwrap();
+        armored.sender = SecretKey::from([3u8; 32]).public_key();
+        assert!(matches!(
+            armored.open(&recipient),
+            Err(AppError::CorruptShare(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_files_are_corrupt() {
+        let text = sealed(b"value");
+        let cut = &text[..text.len() - ARMOR_END.len() - 1];
+        let nonce_line = text
+            .lines()
+            .find(|line| line.starts_with("Nonce: "))
+            .unwrap();
+        for broken in [
+            "hello".to_string(),
+            cut.to_string(),
+            text.replace("Version: 1", "Version: 2"),
+            text.replace(nonce_line, "Nonce: AAAA"),
+            text.replace("Created: ", "Created: yesterday "),
+            text.replace("Created: ", "Created "),
+        ] {
+            assert!(
+                matches!(Armored::decode(&broken), Err(AppError::CorruptShare(_))),
+                "{}",
+                broken
+            );
+        }
+    }
+
+    #[test]
+    fn recipient_keys_must_be_32_nonzero_bytes() {
+        let (_, recipient) = keys();
+        let encoded = BASE64.encode(recipient.public_key().as_bytes());
+        assert!(parse_public_key(&format!("{}\n", encoded)).is_ok());
+        for text in [
+            String::new(),
+            "not base64".to_string(),
+            BASE64.encode([5u8; 31]),
+            BASE64.encode([0u8; 32]),
+        ] {
+            assert!(parse_public_key(&text).is_err(), "{}", text);
+        }
+    }
+}
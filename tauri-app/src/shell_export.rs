@@ -0,0 +1,307 @@
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::{cli, names, runner};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State, Window};
+
+/// How long the export socket waits for its one reader.
+#[cfg(unix)]
+const EXPORT_TTL: std::time::Duration = std::time::Duration::from_secs(10);
+const EXPORT_REASON: &str = "Exported to a shell";
+/// What the load command needs installed. socat is tried first since many
+/// distributions' default netcat has no `-U`.
+const LOAD_REQUIREMENT: &str = "socat, or a netcat with -U such as OpenBSD netcat";
+
+/// `value` in POSIX single quotes, which have no escapes: each ' closes the
+/// quotes, adds a quoted ' and reopens them.
+pub fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// One line that sets `name` to `value` in this shell. Values go inside single
+    /// quotes, so `$`, backticks and newlines stay literal.
+    fn export_line(self, name: &str, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export {}={}\n", name, posix_quote(value)),
+            // fish single quotes understand only \\ and \'.
+            Shell::Fish => format!(
+                "set -gx {} '{}'\n",
+                name,
+                value.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+        }
+    }
+
+    /// What to run in the terminal to load the script from `socket`: read with
+    /// socat, else `nc -U`, else print `LOAD_REQUIREMENT` to stderr.
+    #[cfg(unix)]
+    fn load_command(self, socket: &str) -> String {
+        let socket = posix_quote(socket);
+        let missing = posix_quote(&format!("vibesafe: loading needs {}", LOAD_REQUIREMENT));
+        match self {
+            Shell::Bash | Shell::Zsh => format!(
+                "eval \"$(if command -v socat >/dev/null 2>&1; then socat - UNIX-CONNECT:{0}; \
+                 elif command -v nc >/dev/null 2>&1; then nc -U {0}; else echo {1} >&2; fi)\"",
+                socket, missing
+            ),
+            Shell::Fish => format!(
+                "if command -q socat; socat - UNIX-CONNECT:{0}; else if command -q nc; \
+                 nc -U {0}; else; echo {1} >&2; end | source",
+                socket, missing
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShellExport {
+    /// Unix socket that serves the script once.
+    pub path: String,
+    /// Unix seconds after which the socket is gone.
+    pub expires_at: u64,
+    /// Command that loads the script into the current shell.
+    pub command: String,
+    /// What `command` needs installed to read the socket.
+    pub requires: &'static str,
+}
+
+/// Serves `script` to the first connection on `listener`, or to nobody once
+/// `EXPORT_TTL` passes, then removes the socket and its directory.
+#[cfg(unix)]
+fn serve_once(
+    listener: std::os::unix::net::UnixListener,
+    dir: std::path::PathBuf,
+    script: zeroize::Zeroizing<String>,
+) {
+    use std::io::{ErrorKind, Write};
+    use std::time::{Duration, Instant};
+
+    let deadline = Instant::now() + EXPORT_TTL;
+    if listener.set_nonblocking(true).is_ok() {
+        while Instant::now() < deadline {
+            match listener.accept() {
+                Ok((mut stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let _ = stream.write_all(script.as_bytes());
+                    break;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(50))
+                }
+                Err(_) => break,
+            }
+        }
+    }
+    drop(listener);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Builds a script that exports `names` as environment variables in `shell` and
+/// hands it out through a one-shot Unix socket in a private (0700) directory, so
+/// the values never pass through the webview. Only the socket path is returned;
+/// it serves one read and is removed after that or after 10 seconds.
+///
+/// Always asks for a fresh confirmation. Where the app has no confirmation
+/// backend, the CLI's own passkey prompt has to stand in for it.
+#[command]
+pub async fn generate_shell_export(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    names: Vec<String>,
+    shell: Shell,
+) -> Result<ShellExport, AppError> {
+    if names.is_empty() {
+        return Err(AppError::InvalidInput("No secrets selected".to_string()));
+    }
+    for name in &names {
+        names::check_secret_name(name)?;
+        if !runner::is_env_name(name) {
+            return Err(AppError::InvalidInput(format!(
+                "'{}' can't be used as an environment variable name",
+                name
+            )));
+        }
+    }
+
+    let reason = format!("Export {} secret(s) to a shell", names.len());
+    auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+
+    let mut script = zeroize::Zeroizing::new(String::new());
+    for name in &names {
+        let stdout = cli::get_secret(name).await?;
+        let value = zeroize::Zeroizing::new(String::from_utf8_lossy(&stdout).into_owned());
+        script.push_str(&shell.export_line(name, &value));
+    }
+    for name in &names {
+        audit::record(&app, name, AccessAction::Read, Some(EXPORT_REASON))?;
+    }
+
+    serve(&app, shell, script)
+}
+
+#[cfg(unix)]
+fn serve(
+    app: &AppHandle,
+    shell: Shell,
+    script: zeroize::Zeroizing<String>,
+) -> Result<ShellExport, AppError> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use tauri::Manager;
+
+    let failed =
+        |e: std::io::Error| AppError::Cli(format!("Failed to create export socket: {}", e));
+    let dir = std::env::temp_dir().join(format!("vibesafe-export-{}", uuid::Uuid::new_v4()));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .map_err(failed)?;
+    app.state::<crate::shred::TempFiles>().register(dir.clone());
+
+    let socket = dir.join("export.sock");
+    let listener = std::os::unix::net::UnixListener::bind(&socket).map_err(failed)?;
+    std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600)).map_err(failed)?;
+    std::thread::spawn(move || serve_once(listener, dir, script));
+
+    let path = socket.to_string_lossy().into_owned();
+    Ok(ShellExport {
+        command: shell.load_command(&path),
+        requires: LOAD_REQUIREMENT,
+        expires_at: audit::now_secs() + EXPORT_TTL.as_secs(),
+        path,
+    })
+}
+
+#[cfg(not(unix))]
+fn serve(
+    _app: &AppHandle,
+    _shell: Shell,
+    _script: zeroize::Zeroizing<String>,
+) -> Result<ShellExport, AppError> {
+    Err(AppError::InvalidInput(
+        "Shell export is only available on macOS and Linux".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    const HOSTILE: &[&str] = &[
+        "it's",
+        "back\\slash\\",
+        "\\'",
+        "$(touch pwned)",
+        "`touch pwned`",
+        "$HOME ${HOME}",
+        "two\nlines\n",
+        "",
+    ];
+
+    #[test]
+    fn export_lines_quote_everything() {
+        assert_eq!(
+            Shell::Bash.export_line("A", "it's $(x)"),
+            "export A='it'\\''s $(x)'\n"
+        );
+        assert_eq!(Shell::Zsh.export_line("A", "`x`\n"), "export A='`x`\n'\n");
+        assert_eq!(
+            Shell::Fish.export_line("A", "it's \\ $(x)"),
+            "set -gx A 'it\\'s \\\\ $(x)'\n"
+        );
+    }
+
+    /// Runs each export line in the real shell, where it's installed, and reads
+    /// the variable back.
+    #[test]
+    fn shells_read_back_the_exact_value() {
+        for (shell, program) in [
+            (Shell::Bash, "bash"),
+            (Shell::Zsh, "zsh"),
+            (Shell::Fish, "fish"),
+        ] {
+            for value in HOSTILE {
+                let script = format!("{}printf %s \"$VALUE\"", shell.export_line("VALUE", value));
+                let dir = tempfile::tempdir().unwrap();
+                let output = match Command::new(program)
+                    .arg("-c")
+                    .arg(&script)
+                    .current_dir(dir.path())
+                    .output()
+                {
+                    Ok(output) => output,
+                    Err(_) => break,
+                };
+                assert!(output.status.success(), "{} failed on {:?}", program, value);
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stdout),
+                    *value,
+                    "{}",
+                    program
+                );
+                assert!(
+                    !dir.path().join("pwned").exists(),
+                    "{} ran {:?}",
+                    program,
+                    value
+                );
+            }
+        }
+    }
+
+    /// Runs the bash load command with only fake socat/nc on PATH, each printing
+    /// an export naming itself and the arguments it got.
+    #[cfg(unix)]
+    #[test]
+    fn the_load_command_prefers_socat_then_nc() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin = tempfile::tempdir().unwrap();
+        let socket = "/tmp/vibesafe-export-x/export.sock";
+        let load = |path: &std::path::Path| {
+            Command::new("/bin/sh")
+                .arg("-c")
+                .arg(format!(
+                    "{}; printf %s \"$VIA\"",
+                    Shell::Bash.load_command(socket)
+                ))
+                .env("PATH", path)
+                .output()
+                .unwrap()
+        };
+        let install = |tool: &str| {
+            let staging = bin.path().join(format!("{}.tmp", tool));
+            std::fs::write(
+                &staging,
+                format!("#!/bin/sh\necho \"export VIA='{} $*'\"\n", tool),
+            )
+            .unwrap();
+            std::fs::set_permissions(&staging, std::fs::Permissions::from_mode(0o755)).unwrap();
+            std::fs::rename(&staging, bin.path().join(tool)).unwrap();
+        };
+
+        let output = load(bin.path());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(LOAD_REQUIREMENT));
+        install("nc");
+        assert_eq!(
+            String::from_utf8_lossy(&load(bin.path()).stdout),
+            format!("nc -U {}", socket)
+        );
+        install("socat");
+        assert_eq!(
+            String::from_utf8_lossy(&load(bin.path()).stdout),
+            format!("socat - UNIX-CONNECT:{}", socket)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+const OVERWRITE_CHUNK: usize = 64 * 1024;
+
+/// Overwrites a file with zeros, flushes it to disk, then unlinks it. Directories
+/// are shredded recursively. A path that doesn't exist is not an error.
+///
+/// On copy-on-write filesystems (APFS, Btrfs) and SSDs the overwrite may land on
+/// fresh blocks, so it is best effort there; the unlink always happens.
+pub fn secure_delete(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            secure_delete(&entry?.path())?;
+        }
+        return fs::remove_dir(path);
+    }
+
+    // Symlinks are unlinked without touching their target.
+    if metadata.is_file() {
+        // A failed overwrite must not keep the file around.
+        let _ = overwrite(path, metadata.len());
+    }
+    fs::remove_file(path)
+}
+
+fn overwrite(path: &Path, len: u64) -> io::Result<()> {
+    let mut file = match OpenOptions::new().write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            make_writable(path)?;
+            OpenOptions::new().write(true).open(path)?
+        }
+        Err(e) => return Err(e),
+    };
+
+    let zeros = [0u8; OVERWRITE_CHUNK];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(OVERWRITE_CHUNK as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+}
+
+/// Read-only files can't be opened for writing, and on Windows can't be deleted either.
+#[cfg(windows)]
+fn make_writable(path: &Path) -> io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(windows))]
+fn make_writable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o200);
+    fs::set_permissions(path, permissions)
+}
+
+/// Creates a new directory under the system temp dir that only the current user
+/// can enter (0700 on Unix; Windows temp dirs are per-user already).
+pub fn private_temp_dir(prefix: &str) -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("{}-{}", prefix, uuid::Uuid::new_v4()));
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+/// Files written during this session that must not outlive it, shredded when the
+/// app quits. Held in managed state, so any command or task can register paths.
+#[derive(Default)]
+pub struct TempFiles {
+    paths: Mutex<HashSet<PathBuf>>,
+}
+
+impl TempFiles {
+    pub fn register(&self, path: PathBuf) {
+        self.paths
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(path);
+    }
+
+    /// Shreds every registered path. Called from the quit paths in `main`.
+    pub fn shred_all(&self) {
+        let paths: Vec<PathBuf> = self
+            .paths
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain()
+            .collect();
+        for path in paths {
+            let _ = secure_delete(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_paths_are_gone_after_shutdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("export.env");
+        fs::write(&file, "API_KEY=value").unwrap();
+        let nested = dir.path().join("bundle");
+        fs::create_dir_all(nested.join("inner")).unwrap();
+        fs::write(nested.join("inner").join("key.pem"), "key").unwrap();
+        let read_only = nested.join("read-only");
+        fs::write(&read_only, "value").unwrap();
+        let mut permissions = fs::metadata(&read_only).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&read_only, permissions).unwrap();
+        let kept = dir.path().join("kept");
+        fs::write(&kept, "not registered").unwrap();
+
+        let temp_files = TempFiles::default();
+        temp_files.register(file.clone());
+        temp_files.register(nested.clone());
+        temp_files.register(dir.path().join("never-written"));
+        temp_files.shred_all();
+
+        assert!(!file.exists());
+        assert!(!nested.exists());
+        assert!(kept.exists());
+        // Shredded paths are forgotten, so a second run has nothing to do.
+        assert!(temp_files.paths.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn concurrent_registrations_are_all_shredded() {
+        let dir = tempfile::tempdir().unwrap();
+        let temp_files = TempFiles::default();
+        std::thread::scope(|scope| {
+            for i in 0..16 {
+                let (temp_files, dir) = (&temp_files, dir.path());
+                scope.spawn(move || {
+                    let path = dir.join(format!("file-{}", i));
+                    fs::write(&path, "value").unwrap();
+                    temp_files.register(path);
+                });
+            }
+        });
+        temp_files.shred_all();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::auth::SharedAuthProvider;
+use crate::error::AppError;
+use tauri::{command, AppHandle, State, Window};
+
+/// The longest lifetime accepted, a week; ssh-add takes anything, but a key left
+/// in the agent indefinitely should be a deliberate `None`.
+const MAX_LIFETIME_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[cfg(unix)]
+mod agent {
+    use crate::audit::{self, AccessAction};
+    use crate::cli;
+    use crate::error::AppError;
+    use std::path::{Path, PathBuf};
+    use std::process::Stdio;
+    use tauri::AppHandle;
+    use tokio::io::AsyncWriteExt;
+    use tokio::process::Command;
+    use zeroize::Zeroizing;
+
+    const AUDIT_REASON: &str = "Loaded into ssh-agent";
+
+    /// The agent socket from `SSH_AUTH_SOCK`, if it names a socket that exists.
+    fn agent_socket() -> Result<PathBuf, AppError> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let socket = std::env::var_os("SSH_AUTH_SOCK")
+            .map(PathBuf::from)
+            .ok_or_else(|| AppError::SshAgentUnavailable("SSH_AUTH_SOCK isn't set".to_string()))?;
+        match std::fs::metadata(&socket) {
+            Ok(metadata) if metadata.file_type().is_socket() => Ok(socket),
+            _ => Err(AppError::SshAgentUnavailable(format!(
+                "{} isn't a socket",
+                socket.display()
+            ))),
+        }
+    }
+
+    pub async fn load(
+        app: &AppHandle,
+        name: &str,
+        lifetime_secs: Option<u64>,
+    ) -> Result<(), AppError> {
+        let socket = agent_socket()?;
+        let key = cli::get_secret(name).await?;
+        add_key(&socket, key, lifetime_secs).await?;
+        audit::record(app, name, AccessAction::Read, Some(AUDIT_REASON))
+    }
+
+    /// Pipes `key` to `ssh-add -` for the agent at `socket`.
+    async fn add_key(
+        socket: &Path,
+        mut key: Zeroizing<Vec<u8>>,
+        lifetime_secs: Option<u64>,
+    ) -> Result<(), AppError> {
+        // ssh-add wants the key file's final newline, which a pasted key may lack.
+        if !key.ends_with(b"\n") {
+            key.push(b'\n');
+        }
+
+        let mut command = Command::new("ssh-add");
+        if let Some(lifetime) = lifetime_secs {
+            command.arg("-t").arg(lifetime.to_string());
+        }
+        let mut child = command
+            .arg("-")
+            .env("SSH_AUTH_SOCK", socket)
+            // No passphrase prompt: stdin is the key, and there's no terminal.
+            .env("SSH_ASKPASS_REQUIRE", "never")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AppError::Cli(format!("Failed to run ssh-add: {}", e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let written = stdin.write_all(&key).await;
+            drop(stdin);
+            written
+                .map_err(|e| AppError::Cli(format!("Failed to pass the key to ssh-add: {}", e)))?;
+        }
+        drop(key);
+
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| AppError::Cli(format!("Failed to wait for ssh-add: {}", e)))?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            let message = cli::sanitize_line(message.trim());
+            return Err(match output.status.code() {
+                // ssh-add's code for "could not connect to the agent".
+                Some(2) => AppError::SshAgentUnavailable(message),
+                _ => AppError::Cli(format!("ssh-add failed: {}", message)),
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::process::{Child, Command as StdCommand};
+        use std::time::Duration;
+
+        /// An `ssh-agent` on a private socket, killed when dropped.
+        struct Agent(Child);
+
+        impl Drop for Agent {
+            fn drop(&mut self) {
+                let _ = self.0.kill();
+                let _ = self.0.wait();
+            }
+        }
+
+        fn openssh_installed() -> bool {
+            StdCommand::new("ssh-add").arg("-h").output().is_ok()
+        }
+
+        fn start_agent(socket: &Path) -> Agent {
+            let agent = Agent(
+                StdCommand::new("ssh-agent")
+                    .arg("-D")
+                    .arg("-a")
+                    .arg(socket)
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .unwrap(),
+            );
+            for _ in 0..100 {
+                if socket.exists() {
+                    return agent;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            panic!("ssh-agent didn't create its socket");
+        }
+
+        fn keygen(path: &Path, passphrase: &str) -> Zeroizing<Vec<u8>> {
+            let status = StdCommand::new("ssh-keygen")
+                .args(["-q", "-t", "ed25519", "-C", "test", "-N", passphrase, "-f"])
+                .arg(path)
+                .status()
+                .unwrap();
+            assert!(status.success());
+            Zeroizing::new(std::fs::read(path).unwrap())
+        }
+
+        fn listed(socket: &Path) -> String {
+            let output = StdCommand::new("ssh-add")
+                .arg("-l")
+                .env("SSH_AUTH_SOCK", socket)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+
+        #[tokio::test]
+        async fn keys_without_a_final_newline_are_loaded() {
+            if !openssh_installed() {
+                return;
+            }
+            let dir = tempfile::tempdir().unwrap();
+            let socket = dir.path().join("agent.sock");
+            let _agent = start_agent(&socket);
+            let key_path = dir.path().join("id_ed25519");
+            let mut key = keygen(&key_path, "");
+            while key.last() == Some(&b'\n') {
+                key.pop();
+            }
+
+            add_key(&socket, key, Some(60)).await.unwrap();
+            let fingerprint = StdCommand::new("ssh-keygen")
+                .arg("-lf")
+                .arg(key_path.with_extension("pub"))
+                .output()
+                .unwrap();
+            let fingerprint = String::from_utf8_lossy(&fingerprint.stdout);
+            let fingerprint = fingerprint.split_whitespace().nth(1).unwrap();
+            assert!(listed(&socket).contains(fingerprint));
+        }
+
+        #[tokio::test]
+        async fn encrypted_keys_fail_instead_of_prompting() {
+            if !openssh_installed() {
+                return;
+            }
+            let dir = tempfile::tempdir().unwrap();
+            let socket = dir.path().join("agent.sock");
+            let _agent = start_agent(&socket);
+            let key = keygen(&dir.path().join("id_ed25519"), "passphrase");
+
+            assert!(matches!(
+                add_key(&socket, key, None).await,
+                Err(AppError::Cli(_))
+            ));
+            assert!(!listed(&socket).contains("test"));
+        }
+
+        #[tokio::test]
+        async fn a_missing_agent_is_unavailable() {
+            if !openssh_installed() {
+                return;
+            }
+            let dir = tempfile::tempdir().unwrap();
+            let key = keygen(&dir.path().join("id_ed25519"), "");
+
+            assert!(matches!(
+                add_key(&dir.path().join("agent.sock"), key, None).await,
+                Err(AppError::SshAgentUnavailable(_))
+            ));
+        }
+    }
+}
+
+/// Adds the private key stored as `name` to the running ssh-agent, piping it to
+/// `ssh-add -` so it never touches disk, and for `lifetime_secs` if given. The
+/// key has to be unencrypted: there's no terminal for a passphrase prompt.
+/// Needs a fresh confirmation; the access log gets the name. Fails with
+/// `SSH_AGENT_UNAVAILABLE` when no agent is running, and on Windows.
+#[command]
+pub async fn load_into_ssh_agent(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    name: String,
+    lifetime_secs: Option<u64>,
+) -> Result<(), AppError> {
+    crate::names::check_secret_name(&name)?;
+    if let Some(lifetime) = lifetime_secs {
+        if lifetime == 0 || lifetime > MAX_LIFETIME_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "The lifetime must be between 1 and {} seconds",
+                MAX_LIFETIME_SECS
+            )));
+        }
+    }
+    #[cfg(unix)]
+    {
+        let reason = format!("Load {} into ssh-agent", name);
+        crate::auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+        agent::load(&app, &name, lifetime_secs).await
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (app, window, provider);
+        Err(AppError::SshAgentUnavailable(
+            "the app can only load keys into one on macOS and Linux".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,263 @@
+use crate::auth::{self, SharedAuthProvider};
+use crate::cli;
+use crate::error::AppError;
+use crate::VibeSafeStatus;
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle, State, Window};
+use zeroize::Zeroizing;
+
+/// Whether the passkey or passphrase has been confirmed since launch or the last
+/// lock. Only matters for stores that have one.
+static UNLOCKED: LockFlag = LockFlag(AtomicBool::new(false));
+
+/// The flag behind `UNLOCKED`, separate so tests can use their own.
+#[derive(Debug, Default)]
+struct LockFlag(AtomicBool);
+
+impl LockFlag {
+    /// Whether this unlocked it, rather than it already being unlocked.
+    fn unlock(&self) -> bool {
+        !self.0.swap(true, Ordering::SeqCst)
+    }
+
+    fn lock(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    fn is_unlocked(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Where the app stands with the CLI and the store, so the frontend doesn't have
+/// to work it out from status booleans and error codes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum VaultState {
+    /// Neither a bundled CLI nor one on PATH exists.
+    CliMissing,
+    /// A CLI exists but doesn't speak the JSON status the app relies on.
+    CliIncompatible,
+    /// The CLI works but has no key pair yet; `vibesafe_init` is next.
+    #[default]
+    Uninitialized,
+    /// The store has a passkey or passphrase that hasn't been confirmed since
+    /// launch or since the app last locked; `unlock_vault` or
+    /// `unlock_with_passphrase` is next.
+    Locked,
+    Ready,
+}
+
+impl fmt::Display for VaultState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Called after any successful passkey confirmation or passphrase check. Leaving Locked starts the
+/// local API if it's enabled, since it doesn't run while locked.
+pub fn unlock(app: &AppHandle) {
+    if UNLOCKED.unlock() {
+        tauri::async_runtime::spawn(crate::api_server::start_if_enabled(app.clone()));
+    }
+}
+
+/// Called when the app locks (sleep, `wipe_caches`).
+pub fn lock() {
+    UNLOCKED.lock();
+}
+
+pub fn is_unlocked() -> bool {
+    UNLOCKED.is_unlocked()
+}
+
+/// The state a status the CLI reported means.
+pub fn of(status: &VibeSafeStatus, unlocked: bool) -> VaultState {
+    match status.initialized {
+        true if (status.passkey_enabled || status.passphrase_protected) && !unlocked => {
+            VaultState::Locked
+        }
+        true => VaultState::Ready,
+        false => VaultState::Uninitialized,
+    }
+}
+
+/// The one place the state is decided, from the outcome of `vibesafe status`
+/// and whether the app is `unlocked`. Failures that say nothing about the state
+/// (a crash, a cancelled prompt) are passed through, since the state is
+/// unknown.
+pub fn from_status(
+    status: &Result<VibeSafeStatus, AppError>,
+    unlocked: bool,
+) -> Result<VaultState, AppError> {
+    match status {
+        Ok(status) => Ok(of(status, unlocked)),
+        Err(AppError::NotInitialized) => Ok(VaultState::Uninitialized),
+        Err(AppError::CliNotFound) => Ok(VaultState::CliMissing),
+        Err(AppError::UnparseableCliOutput(_)) | Err(AppError::UnsupportedCli(_)) => {
+            Ok(VaultState::CliIncompatible)
+        }
+        Err(other) => Err(other.clone()),
+    }
+}
+
+/// The current state, from the status cache.
+pub async fn current(app: &AppHandle) -> Result<VaultState, AppError> {
+    from_status(&crate::cached_status(app, false).await, is_unlocked())
+}
+
+/// Fails with `InvalidState` unless the app is in one of `allowed`. Commands call
+/// this first and list the states they accept in their doc comment.
+pub async fn require(app: &AppHandle, allowed: &[VaultState]) -> Result<(), AppError> {
+    let current = current(app).await?;
+    if allowed.contains(&current) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidState {
+            current,
+            required: allowed.to_vec(),
+        })
+    }
+}
+
+#[command]
+pub async fn get_vault_state(app: AppHandle) -> Result<VaultState, AppError> {
+    current(&app).await
+}
+
+/// Confirms the passkey so the app leaves Locked. Where there is no confirmation
+/// backend the CLI's passkey is tested instead, as for exports.
+///
+/// Valid in: Locked.
+#[command]
+pub async fn unlock_vault(
+    app: AppHandle,
+    provider: State<'_, SharedAuthProvider>,
+    window: Window,
+) -> Result<VaultState, AppError> {
+    require(&app, &[VaultState::Locked]).await?;
+    auth::confirm_export(
+        &app,
+        provider.inner().clone(),
+        window,
+        "Unlock VibeSafe".to_string(),
+    )
+    .await?;
+    unlock(&app);
+    current(&app).await
+}
+
+/// Checks the key's passphrase by decrypting a secret with it, so the app leaves
+/// Locked. Wrong passphrases count towards the same backoff and lockout as
+/// refused confirmations (see `auth::rate_limited`). An empty store has nothing
+/// to check against, and unlocks.
+///
+/// Valid in: Locked.
+#[command]
+pub async fn unlock_with_passphrase(
+    app: AppHandle,
+    passphrase: String,
+) -> Result<VaultState, AppError> {
+    require(&app, &[VaultState::Locked]).await?;
+    let passphrase = Zeroizing::new(passphrase);
+    let secrets = crate::cached_secrets(&app, false).await?;
+    auth::rate_limited(&app, async {
+        match secrets.first() {
+            Some(secret) => cli::check_passphrase(&secret.name, &passphrase).await,
+            None => Ok(()),
+        }
+    })
+    .await?;
+    current(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use VaultState::*;
+
+    fn status(initialized: bool, passkey_enabled: bool) -> Result<VibeSafeStatus, AppError> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "initialized": initialized,
+            "key_exists": initialized,
+            "passkey_enabled": passkey_enabled,
+            "secrets_count": 0,
+            "claude_integration": false,
+        }))
+        .unwrap())
+    }
+
+    fn passphrase_store() -> Result<VibeSafeStatus, AppError> {
+        let mut store = status(true, false)?;
+        store.passphrase_protected = true;
+        Ok(store)
+    }
+
+    /// Each way the CLI can answer, named by the state it leads to when locked.
+    fn probe(to: VaultState) -> Result<VibeSafeStatus, AppError> {
+        match to {
+            CliMissing => Err(AppError::CliNotFound),
+            CliIncompatible => Err(AppError::UnsupportedCli("0.1".to_string())),
+            Uninitialized => Err(AppError::NotInitialized),
+            Locked => status(true, true),
+            Ready => status(true, false),
+        }
+    }
+
+    #[test]
+    fn every_state_can_follow_every_other() {
+        let states = [CliMissing, CliIncompatible, Uninitialized, Locked, Ready];
+        for from in states {
+            for to in states {
+                assert_eq!(from_status(&probe(from), false).unwrap(), from);
+                assert_eq!(
+                    from_status(&probe(to), false).unwrap(),
+                    to,
+                    "{} -> {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_passkey_store_is_locked_until_confirmed() {
+        assert_eq!(from_status(&status(true, true), false).unwrap(), Locked);
+        assert_eq!(from_status(&status(true, true), true).unwrap(), Ready);
+        assert_eq!(from_status(&status(true, false), false).unwrap(), Ready);
+        assert_eq!(
+            from_status(&status(false, true), false).unwrap(),
+            Uninitialized
+        );
+    }
+
+    #[test]
+    fn a_passphrase_store_is_locked_until_checked() {
+        assert_eq!(from_status(&passphrase_store(), false).unwrap(), Locked);
+        assert_eq!(from_status(&passphrase_store(), true).unwrap(), Ready);
+    }
+
+    #[test]
+    fn lock_and_unlock_move_between_locked_and_ready() {
+        let store = status(true, true);
+        let flag = LockFlag::default();
+        assert_eq!(from_status(&store, flag.is_unlocked()).unwrap(), Locked);
+        flag.unlock();
+        assert_eq!(from_status(&store, flag.is_unlocked()).unwrap(), Ready);
+        flag.lock();
+        assert_eq!(from_status(&store, flag.is_unlocked()).unwrap(), Locked);
+    }
+
+    #[test]
+    fn other_failures_leave_the_state_unknown() {
+        assert!(from_status(&Err(AppError::Cli("crashed".to_string())), true).is_err());
+        assert!(from_status(&Err(AppError::AuthCancelled), true).is_err());
+        assert!(from_status(
+            &Err(AppError::UnparseableCliOutput("status".to_string())),
+            true
+        )
+        .is_ok());
+    }
+}
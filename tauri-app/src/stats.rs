@@ -0,0 +1,183 @@
+use crate::cache::StoreCache;
+use crate::error::AppError;
+use crate::{hygiene, metadata, paths, SecretInfo};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use tauri::{command, AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsDetail {
+    Full,
+    /// The CLI reports no creation times (older versions), so `added_per_month`
+    /// is empty and every secret's age is unknown.
+    Reduced,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AgeDistribution {
+    pub under_30_days: usize,
+    pub under_90_days: usize,
+    pub under_365_days: usize,
+    pub older: usize,
+    /// No creation time from the CLI.
+    pub unknown: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageFootprint {
+    /// The files in the CLI's store directory: keys, secrets.json, config.json.
+    pub store_bytes: u64,
+    pub metadata_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStats {
+    pub generated_at: DateTime<Utc>,
+    pub detail: StatsDetail,
+    pub total: usize,
+    /// Secrets per project; a secret in two projects counts in both.
+    pub by_project: BTreeMap<String, usize>,
+    /// Secrets in at least one project.
+    pub in_a_project: usize,
+    pub percent_in_a_project: f64,
+    /// Secrets created per `YYYY-MM`, oldest first.
+    pub added_per_month: BTreeMap<String, usize>,
+    pub age: AgeDistribution,
+    pub storage: StorageFootprint,
+}
+
+/// The last stats, with the store generation and metadata revision they were
+/// computed from. A change to either makes them stale.
+#[derive(Default)]
+pub struct StatsCache {
+    last: Mutex<Option<(u64, u64, VaultStats)>>,
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (part as f64 * 1000.0 / total as f64).round() / 10.0
+    }
+}
+
+/// The aggregations over the list and metadata.
+fn aggregate(
+    secrets: &[SecretInfo],
+    metadata: &metadata::Metadata,
+    storage: StorageFootprint,
+    now: DateTime<Utc>,
+) -> VaultStats {
+    let live: HashSet<&str> = secrets.iter().map(|secret| secret.name.as_str()).collect();
+    let mut by_project = BTreeMap::new();
+    let mut in_a_project = HashSet::new();
+    for (name, project) in &metadata.projects {
+        let members: HashSet<&str> = project
+            .secrets
+            .iter()
+            .map(|entry| entry.secret.as_str())
+            .filter(|secret| live.contains(secret))
+            .collect();
+        in_a_project.extend(members.iter().copied());
+        by_project.insert(name.clone(), members.len());
+    }
+
+    let mut added_per_month = BTreeMap::new();
+    let mut age = AgeDistribution::default();
+    for secret in secrets {
+        let created = match secret
+            .created_at
+            .as_deref()
+            .and_then(hygiene::parse_created)
+        {
+            Some(created) => created,
+            None => {
+                age.unknown += 1;
+                continue;
+            }
+        };
+        *added_per_month
+            .entry(created.format("%Y-%m").to_string())
+            .or_insert(0) += 1;
+        match (now - created).num_days() {
+            days if days < 30 => age.under_30_days += 1,
+            days if days < 90 => age.under_90_days += 1,
+            days if days < 365 => age.under_365_days += 1,
+            _ => age.older += 1,
+        }
+    }
+
+    VaultStats {
+        generated_at: now,
+        detail: if age.unknown == secrets.len() && !secrets.is_empty() {
+            StatsDetail::Reduced
+        } else {
+            StatsDetail::Full
+        },
+        total: secrets.len(),
+        by_project,
+        in_a_project: in_a_project.len(),
+        percent_in_a_project: percent(in_a_project.len(), secrets.len()),
+        added_per_month,
+        age,
+        storage,
+    }
+}
+
+/// Total size of the regular files directly in `dir`; 0 when it doesn't exist.
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Totals for a stats panel: secrets per project, additions per month, an age
+/// distribution and the storage used. Built from the cached list and the
+/// metadata file, never from values, off the async runtime; the result is kept
+/// until the store or metadata changes. With an older CLI that reports no
+/// creation times, `detail` is "reduced" and the time-based figures are empty.
+#[command]
+pub async fn get_vault_stats(
+    app: AppHandle,
+    cache: State<'_, StatsCache>,
+) -> Result<VaultStats, AppError> {
+    let generation = app.state::<StoreCache>().secrets.generation();
+    let metadata = metadata::load(&app)?;
+    if let Some((cached_generation, revision, stats)) =
+        &*cache.last.lock().unwrap_or_else(PoisonError::into_inner)
+    {
+        if *cached_generation == generation && *revision == metadata.revision {
+            return Ok(stats.clone());
+        }
+    }
+
+    let secrets = crate::cached_secrets(&app, false).await?;
+    let revision = metadata.revision;
+    let metadata_path = metadata::metadata_path(&app)?;
+    let store = paths::store_dir()?;
+    let stats = tauri::async_runtime::spawn_blocking(move || {
+        let storage = StorageFootprint {
+            store_bytes: dir_size(&store),
+            metadata_bytes: fs::metadata(&metadata_path).map_or(0, |file| file.len()),
+        };
+        aggregate(&secrets, &metadata, storage, Utc::now())
+    })
+    .await
+    .map_err(|e| AppError::Cli(format!("Failed to compute stats: {}", e)))?;
+
+    *cache.last.lock().unwrap_or_else(PoisonError::into_inner) =
+        Some((generation, revision, stats.clone()));
+    Ok(stats)
+}
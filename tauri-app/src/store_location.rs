@@ -0,0 +1,254 @@
+use crate::error::AppError;
+use crate::{atomic, cloud_sync, health, paths};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle};
+
+const LOCATION_FILE: &str = "store_location.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LocationFile {
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoreLocation {
+    pub path: String,
+    /// Chosen at init rather than the default.
+    pub custom: bool,
+}
+
+fn location_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(paths::app_config_dir(app)?.join(LOCATION_FILE))
+}
+
+/// The location saved at init, if one was chosen.
+pub fn saved(app: &AppHandle) -> Result<Option<PathBuf>, AppError> {
+    saved_in(&paths::app_config_dir(app)?)
+}
+
+/// `saved` for the helper modes, which run without an `AppHandle`.
+pub fn saved_in(config_dir: &Path) -> Result<Option<PathBuf>, AppError> {
+    let path = config_dir.join(LOCATION_FILE);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(AppError::Cli(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    serde_json::from_str::<LocationFile>(&contents)
+        .map(|file| Some(file.path))
+        .map_err(|e| AppError::InvalidInput(format!("{} is invalid: {}", path.display(), e)))
+}
+
+/// Points every CLI run at the saved location. Called once at startup.
+pub fn load(app: &AppHandle) {
+    if let Ok(config_dir) = paths::app_config_dir(app) {
+        load_from(&config_dir);
+    }
+}
+
+/// `load` for the helper modes, which run without an `AppHandle`.
+pub fn load_from(config_dir: &Path) {
+    if let Ok(Some(dir)) = saved_in(config_dir) {
+        paths::set_store_override(Some(dir));
+    }
+}
+
+pub fn save(app: &AppHandle, dir: &Path) -> Result<(), AppError> {
+    let path = location_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::Cli(format!("Failed to create config directory: {}", e)))?;
+    }
+    atomic::atomic_write_json(
+        &path,
+        &LocationFile {
+            path: dir.to_path_buf(),
+        },
+    )
+    .map_err(|e| AppError::Cli(format!("Failed to save the store location: {}", e)))
+}
+
+/// `dir` with symlinks in its existing part resolved, so a link into a synced
+/// folder is caught before the directory is created.
+fn resolve(dir: &Path) -> PathBuf {
+    let existing = dir.ancestors().find(|ancestor| ancestor.exists());
+    match existing.and_then(|ancestor| Some((ancestor, ancestor.canonicalize().ok()?))) {
+        Some((ancestor, resolved)) => match dir.strip_prefix(ancestor) {
+            Ok(rest) => resolved.join(rest),
+            Err(_) => dir.to_path_buf(),
+        },
+        None => dir.to_path_buf(),
+    }
+}
+
+/// Whether `dir` has anything in it other than a store, which init would mix
+/// its files into.
+fn holds_other_files(dir: &Path) -> bool {
+    let holds_store = ["secrets.json", "private.pem"]
+        .iter()
+        .any(|file| dir.join(file).is_file());
+    !holds_store && fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Checks a location for a new store: absolute, an empty directory, one holding
+/// a store, or not there yet, writable, and outside a cloud-synced folder unless
+/// `accept_sync_warning` is set. Creates the directory.
+pub fn validate(dir: &str, accept_sync_warning: bool) -> Result<PathBuf, AppError> {
+    let dir = PathBuf::from(dir.trim());
+    if !dir.is_absolute() {
+        return Err(AppError::InvalidInput(
+            "The store location must be an absolute path".to_string(),
+        ));
+    }
+    if dir.exists() && !dir.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+    if holds_other_files(&dir) {
+        return Err(AppError::InvalidInput(format!(
+            "{} isn't empty. Choose an empty folder or one that already holds a store.",
+            dir.display()
+        )));
+    }
+    if let Some(provider) = cloud_sync::classify(&resolve(&dir)) {
+        if !accept_sync_warning {
+            return Err(AppError::InvalidInput(format!(
+                "{} is in {}. Sync can replace a file while the CLI is writing it and \
+                 corrupt the store. Choose another folder, or accept the warning to use it \
+                 anyway.",
+                dir.display(),
+                provider.label()
+            )));
+        }
+    }
+    if !health::writable(&dir) {
+        return Err(AppError::InvalidInput(format!(
+            "{} can't be created or isn't writable",
+            dir.display()
+        )));
+    }
+    Ok(dir)
+}
+
+/// Where the store is, and whether it was chosen at init.
+#[command]
+pub fn get_store_location(app: AppHandle) -> Result<StoreLocation, AppError> {
+    Ok(StoreLocation {
+        path: paths::store_dir()?.display().to_string(),
+        custom: saved(&app)?.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(dir: &Path, accept_sync_warning: bool) -> Result<PathBuf, AppError> {
+        validate(&dir.to_string_lossy(), accept_sync_warning)
+    }
+
+    #[test]
+    fn missing_directories_are_created() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("volume").join("vault");
+        assert_eq!(check(&dir, false).unwrap(), dir);
+        assert!(dir.is_dir());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn relative_paths_and_files_are_rejected() {
+        assert!(validate("vault", false).is_err());
+        let root = tempfile::tempdir().unwrap();
+        let file = root.path().join("vault");
+        fs::write(&file, "").unwrap();
+        assert!(check(&file, false).is_err());
+        assert!(check(&file.join("inside"), false).is_err());
+    }
+
+    #[test]
+    fn non_empty_directories_need_to_hold_a_store() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("notes.txt"), "").unwrap();
+        assert!(check(root.path(), false).is_err());
+
+        fs::write(root.path().join("secrets.json"), "{}").unwrap();
+        assert!(check(root.path(), false).is_ok());
+    }
+
+    #[test]
+    fn synced_folders_need_the_warning_accepted() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("Dropbox (Acme)").join("vault");
+        assert!(check(&dir, false).is_err());
+        assert!(!dir.exists());
+        assert_eq!(check(&dir, true).unwrap(), dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_into_synced_folders_are_followed() {
+        let root = tempfile::tempdir().unwrap();
+        let synced = root.path().join("OneDrive");
+        fs::create_dir(&synced).unwrap();
+        let link = root.path().join("vault-link");
+        std::os::unix::fs::symlink(&synced, &link).unwrap();
+        assert!(check(&link.join("vault"), false).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_directories_are_rejected() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("locked");
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o500)).unwrap();
+        // Permissions don't bind root.
+        let enforced = fs::write(dir.join("probe"), "").is_err();
+        if enforced {
+            assert!(check(&dir, false).is_err());
+            assert!(check(&dir.join("vault"), false).is_err());
+        }
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn the_saved_location_reaches_every_cli_run() {
+        let fake = crate::test_support::fake_cli("printf %s \"$VIBESAFE_HOME\"");
+        let config = tempfile::tempdir().unwrap();
+        let store = fake.path("store");
+        fs::write(
+            config.path().join(LOCATION_FILE),
+            serde_json::to_vec(&LocationFile {
+                path: store.clone(),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+
+        let previous = paths::store_override();
+        load_from(config.path());
+        let runs = [
+            crate::cli::run_vibesafe(&["list"]).await,
+            crate::cli::run_vibesafe(&["get", "A"]).await,
+        ];
+        paths::set_store_override(previous);
+        for run in runs {
+            assert_eq!(run.unwrap(), store.to_string_lossy().as_bytes());
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::cache::StoreCache;
+use crate::paths;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+const STORE_CHANGED_EVENT: &str = "store:changed";
+/// A CLI write touches several files in a row; they are reported as one change.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The watcher on the CLI's store directory, once it exists.
+#[derive(Default)]
+pub struct StoreWatcher(Mutex<Option<RecommendedWatcher>>);
+
+/// Starts watching the store so changes made with the CLI while the app runs drop
+/// the caches and emit "store:changed". Does nothing if already watching or if the
+/// store doesn't exist yet; `vibesafe_init` and status fetches call this again.
+pub fn start(app: &AppHandle) {
+    let state = app.state::<StoreWatcher>();
+    let mut watcher = state.0.lock().unwrap_or_else(PoisonError::into_inner);
+    if watcher.is_some() {
+        return;
+    }
+    let dir = match paths::store_dir() {
+        Ok(dir) if dir.is_dir() => dir,
+        _ => return,
+    };
+
+    let (created, mut changed) = match watch(&dir) {
+        Some(watching) => watching,
+        None => return,
+    };
+    *watcher = Some(created);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while next_change(&mut changed).await {
+            app.state::<StoreCache>().invalidate();
+            let _ = app.emit_all(STORE_CHANGED_EVENT, ());
+        }
+    });
+}
+
+/// A watcher on `dir` and the channel it reports events on.
+fn watch(dir: &Path) -> Option<(RecommendedWatcher, mpsc::UnboundedReceiver<()>)> {
+    let (changes, changed) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if event.is_ok() {
+            let _ = changes.send(());
+        }
+    })
+    .ok()?;
+    watcher.watch(dir, RecursiveMode::NonRecursive).ok()?;
+    Some((watcher, changed))
+}
+
+/// Waits for the next change, then for `DEBOUNCE` so the rest of a burst is
+/// folded into it. False once the watcher is gone.
+async fn next_change(changed: &mut mpsc::UnboundedReceiver<()>) -> bool {
+    if changed.recv().await.is_none() {
+        return false;
+    }
+    tokio::time::sleep(DEBOUNCE).await;
+    while changed.try_recv().is_ok() {}
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tokio::time::timeout;
+
+    /// Long enough for the OS to deliver an event.
+    const DELIVERY: Duration = Duration::from_secs(2);
+
+    #[tokio::test]
+    async fn a_cli_write_is_one_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_watcher, mut changed) = watch(dir.path()).unwrap();
+
+        // What `vibesafe add` touches.
+        fs::write(dir.path().join("secrets.json.tmp"), "{}").unwrap();
+        fs::rename(
+            dir.path().join("secrets.json.tmp"),
+            dir.path().join("secrets.json"),
+        )
+        .unwrap();
+        fs::write(dir.path().join("config.json"), "{}").unwrap();
+
+        assert!(timeout(DELIVERY, next_change(&mut changed)).await.unwrap());
+        assert!(timeout(DEBOUNCE, next_change(&mut changed)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn later_changes_are_reported_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_watcher, mut changed) = watch(dir.path()).unwrap();
+        for round in 0..2 {
+            fs::write(
+                dir.path().join("secrets.json"),
+                format!("{{\"{}\": 1}}", round),
+            )
+            .unwrap();
+            assert!(timeout(DELIVERY, next_change(&mut changed)).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn other_directories_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let (_watcher, mut changed) = watch(dir.path()).unwrap();
+        fs::write(other.path().join("secrets.json"), "{}").unwrap();
+        assert!(timeout(DEBOUNCE, next_change(&mut changed)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn dropping_the_watcher_ends_the_loop() {
+        let dir = tempfile::tempdir().unwrap();
+        let (watcher, mut changed) = watch(dir.path()).unwrap();
+        drop(watcher);
+        assert!(!timeout(DELIVERY, next_change(&mut changed)).await.unwrap());
+    }
+}
@@ -0,0 +1,416 @@
+use crate::atomic::atomic_write;
+use crate::audit::{self, AccessAction};
+use crate::auth::{self, SharedAuthProvider};
+use crate::error::AppError;
+use crate::shred::TempFiles;
+use crate::{cli, names};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager, State, Window};
+use zeroize::Zeroizing;
+
+const AUDIT_REASON: &str = "Rendered into a config file template";
+/// Expressions in `{{ }}` that don't start with this are left as they are, so
+/// templates meant for another tool pass through.
+const NAMESPACE: &str = "vibesafe.";
+
+/// One `{{ vibesafe.NAME | filter ... }}` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    /// From `default("...")`: used when the secret doesn't exist.
+    pub default: Option<String>,
+    /// From `base64`: the value's bytes, base64-encoded.
+    pub base64: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Secret(Placeholder),
+}
+
+fn invalid(expression: &str, why: &str) -> AppError {
+    AppError::InvalidInput(format!(
+        "Invalid placeholder {{{{{}}}}}: {}",
+        expression, why
+    ))
+}
+
+/// Where the `}}` closing an expression starts, ignoring any inside a quoted
+/// string.
+fn find_close(expression: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in expression.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if expression[i..].starts_with("}}") => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// A `"..."` or `'...'` string at the start of `input`, with `\\` and an escaped
+/// quote unescaped, and the rest of `input` after it.
+fn quoted(input: &str) -> Option<(String, &str)> {
+    let quote = input.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut value = String::new();
+    let mut escaped = false;
+    for (i, c) in input.char_indices().skip(1) {
+        if escaped {
+            value.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some((value, &input[i + 1..]));
+        } else {
+            value.push(c);
+        }
+    }
+    None
+}
+
+/// Parses what follows `vibesafe.` in an expression: a name, then any of the
+/// `| default("...")` and `| base64` filters.
+fn parse_placeholder(expression: &str, body: &str) -> Result<Placeholder, AppError> {
+    let end = body
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(body.len());
+    let mut placeholder = Placeholder {
+        name: body[..end].to_string(),
+        default: None,
+        base64: false,
+    };
+    if placeholder.name.is_empty() {
+        return Err(invalid(expression, "no secret name"));
+    }
+
+    let mut rest = body[end..].trim_start();
+    while !rest.is_empty() {
+        rest = rest
+            .strip_prefix('|')
+            .ok_or_else(|| invalid(expression, "expected | before a filter"))?
+            .trim_start();
+        if let Some(after) = rest.strip_prefix("base64") {
+            placeholder.base64 = true;
+            rest = after.trim_start();
+        } else if let Some(after) = rest.strip_prefix("default") {
+            let after = after
+                .trim_start()
+                .strip_prefix('(')
+                .ok_or_else(|| invalid(expression, "default needs (\"...\")"))?
+                .trim_start();
+            let (value, after) = quoted(after)
+                .ok_or_else(|| invalid(expression, "default needs a quoted string"))?;
+            rest = after
+                .trim_start()
+                .strip_prefix(')')
+                .ok_or_else(|| invalid(expression, "default is missing its )"))?
+                .trim_start();
+            placeholder.default = Some(value);
+        } else {
+            return Err(invalid(expression, "unknown filter"));
+        }
+    }
+    Ok(placeholder)
+}
+
+/// Splits a template into text and placeholders. `\{{` stands for a literal
+/// `{{`. Expressions outside the `vibesafe.` namespace are kept as text.
+pub fn parse(template: &str) -> Result<Vec<Segment>, AppError> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            text.push_str(&rest[..start - 1]);
+            text.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = match find_close(after) {
+            Some(end) => end,
+            None if after.trim_start().starts_with(NAMESPACE) => {
+                return Err(invalid(after.trim_end(), "no closing }}"))
+            }
+            None => {
+                text.push_str(&rest[start..]);
+                rest = "";
+                break;
+            }
+        };
+        let expression = &after[..end];
+        match expression.trim().strip_prefix(NAMESPACE) {
+            Some(body) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Secret(parse_placeholder(expression, body)?));
+            }
+            None => {
+                text.push_str("{{");
+                text.push_str(expression);
+                text.push_str("}}");
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+/// Joins the segments, taking each placeholder's value from `values` (keyed by
+/// placeholder name) or its default. Values are inserted as they are and never
+/// parsed again, so a value containing `{{ ... }}` comes out literally.
+pub fn substitute(
+    segments: &[Segment],
+    values: &HashMap<String, Zeroizing<Vec<u8>>>,
+) -> Result<Zeroizing<String>, AppError> {
+    let mut output = Zeroizing::new(String::new());
+    for segment in segments {
+        let placeholder = match segment {
+            Segment::Text(text) => {
+                output.push_str(text);
+                continue;
+            }
+            Segment::Secret(placeholder) => placeholder,
+        };
+        match (values.get(&placeholder.name), &placeholder.default) {
+            (Some(value), _) if placeholder.base64 => output.push_str(&BASE64.encode(&**value)),
+            (Some(value), _) => output.push_str(std::str::from_utf8(value).map_err(|_| {
+                AppError::InvalidInput(format!(
+                    "{} isn't text; use {{{{ vibesafe.{} | base64 }}}}",
+                    placeholder.name, placeholder.name
+                ))
+            })?),
+            (None, Some(default)) if placeholder.base64 => {
+                output.push_str(&BASE64.encode(default.as_bytes()))
+            }
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => {
+                return Err(AppError::InvalidInput(format!(
+                    "No value for {}",
+                    placeholder.name
+                )))
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderedTemplate {
+    pub output_path: PathBuf,
+    /// Secrets whose values were written into the file.
+    pub secrets: Vec<String>,
+    /// Placeholders filled from their `default` because the secret doesn't exist.
+    pub defaulted: Vec<String>,
+    pub ephemeral: bool,
+}
+
+fn io_failed(what: &str, path: &Path, e: std::io::Error) -> AppError {
+    AppError::Cli(format!("Failed to {} {}: {}", what, path.display(), e))
+}
+
+/// Renders the template at `template_path` into `output_path` (owner-only
+/// permissions). `{{ vibesafe.NAME }}` becomes the value of secret NAME, or of
+/// `mappings[NAME]` when the template's names differ from the vault's;
+/// `| default("...")` covers a missing secret and `| base64` encodes the value.
+/// A placeholder whose secret doesn't exist and has no default fails the render,
+/// listing every such name. The confirmation names the secrets going into the
+/// file, and the access log gets each. With `ephemeral` the output is shredded
+/// when the app quits.
+#[command]
+pub async fn render_template(
+    app: AppHandle,
+    window: Window,
+    provider: State<'_, SharedAuthProvider>,
+    template_path: String,
+    output_path: String,
+    mappings: HashMap<String, String>,
+    ephemeral: bool,
+) -> Result<RenderedTemplate, AppError> {
+    let (template_path, output_path) = (PathBuf::from(template_path), PathBuf::from(output_path));
+    if template_path == output_path {
+        return Err(AppError::InvalidInput(
+            "The output would overwrite the template".to_string(),
+        ));
+    }
+    let template =
+        fs::read_to_string(&template_path).map_err(|e| io_failed("read", &template_path, e))?;
+    let segments = parse(&template)?;
+
+    let existing: HashSet<String> = crate::cached_secrets(&app, true)
+        .await?
+        .into_iter()
+        .map(|secret| secret.name)
+        .collect();
+    // Placeholder name -> secret name, for the placeholders that have a secret.
+    let mut sources = HashMap::new();
+    let (mut missing, mut defaulted) = (BTreeSet::new(), BTreeSet::new());
+    for segment in &segments {
+        if let Segment::Secret(placeholder) = segment {
+            let secret = mappings.get(&placeholder.name).unwrap_or(&placeholder.name);
+            names::check_secret_name(secret)?;
+            if existing.contains(secret) {
+                sources.insert(placeholder.name.clone(), secret.clone());
+            } else if placeholder.default.is_some() {
+                defaulted.insert(placeholder.name.clone());
+            } else {
+                missing.insert(secret.clone());
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "The template uses secrets that don't exist: {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        )));
+    }
+
+    let secrets: BTreeSet<String> = sources.values().cloned().collect();
+    let secrets: Vec<String> = secrets.into_iter().collect();
+    if !secrets.is_empty() {
+        let reason = format!(
+            "Write {} into {}",
+            secrets.join(", "),
+            output_path.display()
+        );
+        auth::confirm_export(&app, provider.inner().clone(), window, reason).await?;
+    }
+
+    let mut values = HashMap::new();
+    for (placeholder, secret) in &sources {
+        let stdout = cli::get_secret(secret).await?;
+        let value = stdout;
+        values.insert(placeholder.clone(), value);
+    }
+    let output = substitute(&segments, &values)?;
+    for secret in &secrets {
+        audit::record(&app, secret, AccessAction::Read, Some(AUDIT_REASON))?;
+    }
+    atomic_write(&output_path, output.as_bytes())
+        .map_err(|e| io_failed("write", &output_path, e))?;
+    if ephemeral {
+        app.state::<TempFiles>().register(output_path.clone());
+    }
+
+    Ok(RenderedTemplate {
+        output_path,
+        secrets,
+        defaulted: defaulted.into_iter().collect(),
+        ephemeral,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, values: &[(&str, &[u8])]) -> Result<String, AppError> {
+        let values = values
+            .iter()
+            .map(|(name, value)| (name.to_string(), Zeroizing::new(value.to_vec())))
+            .collect();
+        substitute(&parse(template)?, &values).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn placeholders_are_replaced_and_other_text_kept() {
+        assert_eq!(
+            render(
+                "url: {{ vibesafe.DB_URL }}\nkey: {{vibesafe.API-KEY}}\n",
+                &[("DB_URL", b"postgres://db"), ("API-KEY", b"sk-1")],
+            )
+            .unwrap(),
+            "url: postgres://db\nkey: sk-1\n"
+        );
+        assert_eq!(
+            render("{{ .Values.x }} and {{ other }} stay", &[]).unwrap(),
+            "{{ .Values.x }} and {{ other }} stay"
+        );
+    }
+
+    #[test]
+    fn filters_apply_in_any_order() {
+        let template = "{{ vibesafe.A | base64 }} {{ vibesafe.B | default(\"x}}y\") }} \
+                        {{ vibesafe.C | default('it\\'s') | base64 }}";
+        assert_eq!(
+            render(template, &[("A", b"\xff\x00")]).unwrap(),
+            "/wA= x}}y aXQncw=="
+        );
+        assert_eq!(
+            parse("{{ vibesafe.C | base64 | default(\"d\") }}").unwrap(),
+            [Segment::Secret(Placeholder {
+                name: "C".to_string(),
+                default: Some("d".to_string()),
+                base64: true,
+            })]
+        );
+    }
+
+    #[test]
+    fn escaped_braces_are_literal() {
+        assert_eq!(
+            render("\\{{ vibesafe.A }} {{ vibesafe.A }}", &[("A", b"v")]).unwrap(),
+            "{{ vibesafe.A }} v"
+        );
+    }
+
+    #[test]
+    fn values_are_not_parsed_again() {
+        assert_eq!(
+            render(
+                "{{ vibesafe.A }}",
+                &[("A", b"{{ vibesafe.B }}"), ("B", b"leaked")]
+            )
+            .unwrap(),
+            "{{ vibesafe.B }}"
+        );
+    }
+
+    #[test]
+    fn missing_and_binary_values_are_errors() {
+        assert!(render("{{ vibesafe.A }}", &[]).is_err());
+        assert!(render("{{ vibesafe.A }}", &[("A", b"\xff")]).is_err());
+        assert_eq!(
+            render("{{ vibesafe.A | base64 }}", &[("A", b"\xff")]).unwrap(),
+            "/w=="
+        );
+    }
+
+    #[test]
+    fn malformed_placeholders_are_rejected() {
+        for template in [
+            "{{ vibesafe. }}",
+            "{{ vibesafe.A",
+            "{{ vibesafe.A | upper }}",
+            "{{ vibesafe.A base64 }}",
+            "{{ vibesafe.A | default(x) }}",
+            "{{ vibesafe.A | default(\"x\" }}",
+            "{{ vibesafe.A | default(\"x) }}",
+        ] {
+            assert!(parse(template).is_err(), "{}", template);
+        }
+        assert_eq!(
+            parse("{{ unclosed").unwrap(),
+            [Segment::Text("{{ unclosed".to_string())]
+        );
+    }
+}
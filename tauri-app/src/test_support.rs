@@ -0,0 +1,48 @@
+use crate::cli;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use tempfile::TempDir;
+
+/// The CLI override is process-wide, so tests that install a fake take turns.
+static CLI_LOCK: Mutex<()> = Mutex::new(());
+
+/// A shell script standing in for the CLI until dropped. It runs in `dir`, so
+/// it can keep state in files there.
+pub struct FakeCli {
+    pub dir: TempDir,
+    _turn: MutexGuard<'static, ()>,
+}
+
+impl FakeCli {
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.dir.path().join(file)
+    }
+}
+
+impl Drop for FakeCli {
+    fn drop(&mut self) {
+        cli::set_test_cli(None);
+    }
+}
+
+/// Installs `body` as the CLI. `$1`, `$2`, ... are the arguments the wrapper
+/// passes.
+pub fn fake_cli(body: &str) -> FakeCli {
+    let turn = CLI_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("vibesafe");
+    let staging = dir.path().join("vibesafe.tmp");
+    fs::write(
+        &staging,
+        format!("#!/bin/sh\ncd \"$(dirname \"$0\")\" || exit 1\n{}\n", body),
+    )
+    .expect("write fake CLI");
+    fs::set_permissions(&staging, fs::Permissions::from_mode(0o755)).expect("chmod fake CLI");
+    // Renamed into place so no writable descriptor to the script is open when
+    // it's executed (ETXTBSY).
+    fs::rename(&staging, &script).expect("install fake CLI");
+    cli::set_test_cli(Some(script));
+    FakeCli { dir, _turn: turn }
+}
@@ -0,0 +1,169 @@
+use crate::cli::{self, CliVersion};
+use crate::error::AppError;
+use crate::{cli_config, paths};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use tauri::{command, AppHandle};
+
+#[derive(Debug, Serialize)]
+pub struct UpgradeStep {
+    pub name: &'static str,
+    pub ok: bool,
+    /// Why the step failed, or what it found.
+    pub detail: Option<String>,
+}
+
+impl UpgradeStep {
+    fn from_result(name: &'static str, result: Result<String, AppError>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                ok: true,
+                detail: Some(detail),
+            },
+            Err(error) => Self {
+                name,
+                ok: false,
+                detail: Some(error.to_string()),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpgradeCheck {
+    pub previous_version: String,
+    pub current_version: String,
+    /// Every step passed: the new CLI reads the store as the old one did.
+    pub readable: bool,
+    pub steps: Vec<UpgradeStep>,
+    /// What to do next when something failed; empty when `readable`.
+    pub suggestions: Vec<String>,
+}
+
+/// Number of entries in secrets.json, parsed the way the CLI's storage layer does.
+fn count_stored_secrets() -> Result<usize, AppError> {
+    count_secrets_in(&paths::store_dir()?)
+}
+
+/// `count_stored_secrets` for the store files in `dir`, which may be a copy.
+pub fn count_secrets_in(dir: &Path) -> Result<usize, AppError> {
+    let path = dir.join("secrets.json");
+    if !path.exists() {
+        return Ok(0);
+    }
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| AppError::Cli(format!("Failed to read secrets.json: {}", e)))?;
+    serde_json::from_str::<Map<String, Value>>(&contents)
+        .map(|secrets| secrets.len())
+        .map_err(|e| AppError::UnparseableCliOutput(format!("secrets.json isn't an object: {}", e)))
+}
+
+/// Verifies the store after the CLI was upgraded from `previous_cli_version`:
+/// status and the list must parse under the new CLI, the list must agree with
+/// secrets.json, and config.json and the key pair must still be readable. Nothing
+/// is decrypted, so it never prompts. Failures come with rollback or migration
+/// steps instead of an error, so the UI can show all of them.
+#[command]
+pub async fn post_upgrade_check(
+    app: AppHandle,
+    previous_cli_version: String,
+) -> Result<UpgradeCheck, AppError> {
+    let previous = CliVersion::parse(&previous_cli_version).ok_or_else(|| {
+        AppError::InvalidInput(format!("'{}' isn't a CLI version", previous_cli_version))
+    })?;
+    let current = cli::cli_version().await?;
+
+    let mut steps = Vec::new();
+    let status = crate::cached_status(&app, true).await;
+    let initialized = matches!(&status, Ok(status) if status.initialized);
+    steps.push(UpgradeStep::from_result(
+        "status",
+        match status {
+            Ok(status) => Ok(format!("{} secrets", status.secrets_count)),
+            Err(AppError::NotInitialized) => Ok("not initialized".to_string()),
+            Err(error) => Err(error),
+        },
+    ));
+
+    let listed = crate::cached_secrets(&app, true)
+        .await
+        .map(|list| list.len());
+    let stored = count_stored_secrets();
+    steps.push(UpgradeStep::from_result(
+        "list",
+        match (&listed, &stored) {
+            (Ok(listed), Ok(stored)) if listed != stored => {
+                Err(AppError::UnparseableCliOutput(format!(
+                    "The CLI lists {} secrets but secrets.json holds {}",
+                    listed, stored
+                )))
+            }
+            (Ok(listed), _) => Ok(format!("{} secrets", listed)),
+            (Err(AppError::NotInitialized), _) => Ok("not initialized".to_string()),
+            (Err(error), _) => Err(error.clone()),
+        },
+    ));
+    steps.push(UpgradeStep::from_result(
+        "secrets.json",
+        stored.map(|stored| format!("{} entries", stored)),
+    ));
+    steps.push(UpgradeStep::from_result(
+        "config.json",
+        cli_config::read_config().map(|config| format!("{} keys", config.len())),
+    ));
+    let keys = paths::store_dir().map(|dir| {
+        ["private.pem", "public.pem"]
+            .iter()
+            .filter(|file| !dir.join(file).is_file())
+            .copied()
+            .collect::<Vec<_>>()
+    });
+    steps.push(UpgradeStep::from_result(
+        "key pair",
+        keys.and_then(|missing| match missing.as_slice() {
+            [] => Ok("present".to_string()),
+            // Uninitialized stores have no key pair to lose.
+            _ if !initialized => Ok("not created yet".to_string()),
+            missing => Err(AppError::Cli(format!("Missing {}", missing.join(", ")))),
+        }),
+    ));
+
+    let readable = steps.iter().all(|step| step.ok);
+    let mut suggestions = Vec::new();
+    if !readable {
+        suggestions.push(
+            "Don't add or change secrets until this passes; the store files haven't been modified."
+                .to_string(),
+        );
+        suggestions.push(format!(
+            "Roll back with `pip install vibesafe=={}` and run this check again.",
+            previous
+        ));
+        if current > previous {
+            suggestions.push(format!(
+                "Check the release notes for VibeSafe CLI {} for a store migration step.",
+                current
+            ));
+        } else {
+            suggestions.push(format!(
+                "The CLI went from {} to {}; an older CLI may not read a store written by a newer one.",
+                previous, current
+            ));
+        }
+        suggestions.push(
+            "Copy ~/.vibesafe somewhere safe before trying a migration or restoring from key_backup."
+                .to_string(),
+        );
+    }
+
+    Ok(UpgradeCheck {
+        previous_version: previous.to_string(),
+        current_version: current.to_string(),
+        readable,
+        steps,
+        suggestions,
+    })
+}
@@ -0,0 +1,98 @@
+use crate::auth::SharedAuthProvider;
+use crate::cli;
+use crate::error::AppError;
+use crate::index;
+use crate::selftest;
+use crate::state::{self, VaultState};
+use serde::Serialize;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+const WARMUP_COMPLETE_EVENT: &str = "warmup-complete";
+const READY_EVENT: &str = "vibesafe://ready";
+
+/// Timings for the debug console, in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+struct WarmupStats {
+    /// Status plus the secret list, which a cold status fetch loads alongside it.
+    status_ms: u128,
+    index_ms: u128,
+    indexed: usize,
+    total_ms: u128,
+    error: Option<AppError>,
+}
+
+/// Loads status, the secret list and the search index in the background at
+/// launch, so the first interaction doesn't wait on the CLI. Nothing here needs
+/// a passkey; values are never read.
+pub async fn warm_up(app: AppHandle) {
+    let started = Instant::now();
+    let mut stats = WarmupStats {
+        status_ms: 0,
+        index_ms: 0,
+        indexed: 0,
+        total_ms: 0,
+        error: None,
+    };
+
+    let step = Instant::now();
+    let result = crate::cached_status(&app, false).await;
+    stats.status_ms = step.elapsed().as_millis();
+
+    // A store that isn't initialized has nothing to index.
+    match result {
+        Ok(_) => {
+            let step = Instant::now();
+            match index::prime(&app).await {
+                Ok(indexed) => stats.indexed = indexed,
+                Err(e) => stats.error = Some(e),
+            }
+            stats.index_ms = step.elapsed().as_millis();
+        }
+        Err(e) => stats.error = Some(e),
+    }
+
+    stats.total_ms = started.elapsed().as_millis();
+    let _ = app.emit_all(WARMUP_COMPLETE_EVENT, stats);
+}
+
+/// Per-subsystem initialization times in milliseconds, and the state the app
+/// came up in (`null` if status failed for an unrelated reason).
+#[derive(Debug, Clone, Serialize)]
+struct ReadyTimings {
+    cli_discovery_ms: u128,
+    auth_backend_ms: u128,
+    warmup_ms: u128,
+    state: Option<VaultState>,
+}
+
+/// Startup work that doesn't need to finish before the window shows. Each
+/// subsystem also initializes on first use, so commands that arrive before this
+/// is done wait for it instead of failing.
+pub async fn initialize(app: AppHandle) {
+    let step = Instant::now();
+    let _ = tauri::async_runtime::spawn_blocking(cli::locate_cli).await;
+    let cli_discovery_ms = step.elapsed().as_millis();
+
+    // On Linux this opens the system bus connection for polkit.
+    let step = Instant::now();
+    let provider = app.state::<SharedAuthProvider>().inner().clone();
+    let _ = tauri::async_runtime::spawn_blocking(move || provider.backend()).await;
+    let auth_backend_ms = step.elapsed().as_millis();
+
+    let step = Instant::now();
+    warm_up(app.clone()).await;
+    let warmup_ms = step.elapsed().as_millis();
+    let state = state::current(&app).await.ok();
+
+    let _ = app.emit_all(
+        READY_EVENT,
+        ReadyTimings {
+            cli_discovery_ms,
+            auth_backend_ms,
+            warmup_ms,
+            state,
+        },
+    );
+    selftest::check_after_cli_change(&app).await;
+}
@@ -39,6 +39,16 @@ class TestStorage:
         if os.name != 'nt':  # Skip permission check on Windows
             assert oct(Path(temp_dir).stat().st_mode)[-3:] == '700'
     
+    def test_vibesafe_home_sets_default_directory(self, temp_dir, monkeypatch):
+        """Test that VIBESAFE_HOME replaces ~/.vibesafe as the default location"""
+        store = Path(temp_dir) / 'store'
+        monkeypatch.setenv('VIBESAFE_HOME', str(store))
+        storage = StorageManager()
+        
+        assert storage.base_dir == store
+        assert storage.secrets_file == store / 'secrets.json'
+        assert store.exists()
+    
     def test_save_and_load_keys(self, storage_manager, key_pair):
         """Test saving and loading key pair"""
         private_key, public_key = key_pair